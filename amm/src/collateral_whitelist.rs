@@ -30,6 +30,23 @@ impl AMMContract {
         self.collateral_whitelist.0.to_vec()
     }
 
+    /**
+     * @returns the distinct collateral tokens used by at least one market that hasn't been finalized
+     */
+    pub fn get_active_collateral_tokens(&self) -> Vec<AccountId> {
+        self.active_collateral_tokens.keys().collect()
+    }
+
+    /**
+     * @notice markets keep functioning after their collateral is removed from the whitelist, this lets UIs warn about retired collateral
+     * @param market_id is the index of the market to check
+     * @returns whether the market's collateral token is currently whitelisted
+     */
+    pub fn is_market_collateral_whitelisted(&self, market_id: U64) -> bool {
+        let market = self.get_market_expect(market_id);
+        self.collateral_whitelist.0.get(&market.pool.collateral_token_id).is_some()
+    }
+
 
     /**
      * @notice sets the list of tokens that are to be used as collateral
@@ -55,4 +72,17 @@ impl AMMContract {
         self.collateral_whitelist.0.insert(&to_add.account_id, &to_add.decimals);
         logger::log_whitelist(&self.collateral_whitelist);
     }
-}
\ No newline at end of file
+
+    /**
+     * @notice removes a single specified `AccountId` from the whitelist, existing markets using it keep functioning
+     * @param to_remove the `AccountId` of the token to remove
+     */
+    pub fn remove_from_collateral_whitelist(
+        &mut self,
+        to_remove: AccountId
+    ) {
+        self.assert_gov();
+        self.collateral_whitelist.0.remove(&to_remove).expect("ERR_NOT_WHITELISTED");
+        logger::log_whitelist(&self.collateral_whitelist);
+    }
+}
@@ -1,4 +1,21 @@
 // TODO: add to or just implement in top of `protocol.rs`
 
 pub const MIN_OUTCOMES: u16 = 2; // Minimum number of outcomes a market must have in order to be valid
-pub const MAX_OUTCOMES: u16 = 8; // Minimum number of outcomes a market can have in order to be valid
\ No newline at end of file
+pub const MAX_OUTCOMES: u16 = 8; // Maximum number of outcomes a pool supports, upper bound and default of the `max_outcomes` setting
+pub const MAX_CLAIM_BATCH_SIZE: usize = 16; // Maximum number of markets that can be claimed in a single `claim_earnings_batch` call
+pub const MAX_TREASURY_BENEFICIARIES: usize = 8; // Maximum number of accounts `sweep_treasury` splits protocol fees between
+pub const MAX_CURVE_SAMPLES: usize = 32; // Maximum number of amounts that can be priced in a single `sample_buy_curve` call
+pub const MAX_NOTIFICATION_ENDPOINT_LENGTH: usize = 256; // Maximum length in bytes of a market's `notification_endpoint`
+pub const DEFAULT_MAX_SWAP_FEE_BPS: u16 = 500; // Default maximum swap fee in basis points, 5%
+pub const FEE_BPS_DENOMINATION: u16 = 10_000; // A swap fee of `FEE_BPS_DENOMINATION` bps equals 100%
+
+// Prepaid gas recommendations returned by `estimate_operation_gas`, estimates with headroom that haven't been measured in simulation
+pub const GAS_ESTIMATE_BUY: u64 = 30_000_000_000_000; // Base cost of `buy`, excluding per outcome costs
+pub const GAS_ESTIMATE_SELL: u64 = 25_000_000_000_000; // Base cost of `sell`, excluding per outcome costs
+pub const GAS_ESTIMATE_ADD_LIQUIDITY: u64 = 30_000_000_000_000; // Base cost of `add_liquidity`, excluding per outcome costs
+pub const GAS_ESTIMATE_EXIT_POOL: u64 = 25_000_000_000_000; // Base cost of `exit_pool`, excluding per outcome costs
+pub const GAS_ESTIMATE_REDEEM: u64 = 20_000_000_000_000; // Base cost of `burn_outcome_tokens_redeem_collateral`, excluding per outcome costs
+pub const GAS_ESTIMATE_CLAIM: u64 = 25_000_000_000_000; // Base cost of `claim_earnings`, excluding per outcome costs
+pub const GAS_ESTIMATE_PER_OUTCOME: u64 = 2_000_000_000_000; // Pool operations iterate over every outcome
+pub const GAS_ESTIMATE_FT_TRANSFER_CALL: u64 = 25_000_000_000_000; // `ft_transfer_call` and `ft_resolve_transfer` on the collateral token
+pub const GAS_ESTIMATE_PAYOUT_TRANSFER: u64 = 10_000_000_000_000; // Outgoing `ft_transfer` of collateral back to the sender
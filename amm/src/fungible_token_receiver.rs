@@ -17,6 +17,16 @@ pub struct CreateMarketArgs {
     pub collateral_token_id: AccountId, // `AccountId` of collateral that traded in the market
     pub swap_fee: U128, // Swap fee denominated as ration in same denomination as the collateral
     pub is_scalar: bool, // Wether market is scalar market or not
+    pub ambiguity_policy: Option<AmbiguityPolicy>, // How categorical answers matching multiple tags are handled, defaults to `FirstMatch`, duplicate tags require it to be set
+    pub lp_lockup_ms: Option<WrappedTimestamp>, // Period after an LP's latest liquidity addition during which `exit_pool` is blocked
+    pub price_tick: Option<WrappedBalance>, // Tick size that spot prices are rounded to, `None` for continuous prices
+    pub notification_endpoint: Option<String>, // Endpoint off-chain services can notify of resolution events, not called by the contract
+    pub max_pool_collateral: Option<WrappedBalance>, // Cap on the collateral locked in the market, `add_liquidity` calls that would exceed it are rejected
+    pub start_time: Option<WrappedTimestamp>, // Time before which trading and adding liquidity is blocked, has to be before `end_time`
+    pub pricing_mode: Option<PricingMode>, // How the pool prices outcomes, defaults to `ConstantProduct`
+    pub fee_schedule: Option<FeeSchedule>, // Swap fee that moves linearly from `start_fee` at creation to `end_fee` at `end_time`, replaces `swap_fee`
+    pub min_bet: Option<WrappedBalance>, // Minimum amount of collateral a single buy has to spend, doesn't apply to liquidity
+    pub max_bet: Option<WrappedBalance>, // Maximum amount of collateral a single buy can spend, doesn't apply to liquidity
 }
 
 /**
@@ -25,7 +35,9 @@ pub struct CreateMarketArgs {
 #[derive(Serialize, Deserialize)]
 pub struct AddLiquidityArgs {
     pub market_id: U64, // id of the market to add liquidity to
-    pub weight_indication: Option<Vec<U128>> // token weights that dictate the initial odd price distribution
+    pub weight_indication: Option<Vec<U128>>, // token weights that dictate the initial odd price distribution
+    pub min_lp_tokens_out: Option<U128>, // the minimum amount of LP tokens the sender expects out, this is to prevent slippage
+    pub deadline: Option<WrappedTimestamp>, // timestamp in ms after which the liquidity should no longer be added
 }
 
 /**
@@ -35,12 +47,27 @@ pub struct AddLiquidityArgs {
 pub struct BuyArgs {
     pub market_id: U64, // id of the market that shares are to be purchased from
     pub outcome_target: u16, // outcome that the sender buys shares in
-    pub min_shares_out: WrappedBalance // the minimum amount of share tokens the user expects out, this is to prevent slippage
+    pub min_shares_out: WrappedBalance, // the minimum amount of share tokens the user expects out, this is to prevent slippage
+    pub deadline: Option<WrappedTimestamp>, // timestamp in ms after which the purchase should no longer execute
 }
 
+/**
+ * @notice `buy_exact_shares` args
+ */
 #[derive(Serialize, Deserialize)]
+pub struct BuyExactSharesArgs {
+    pub market_id: U64, // id of the market that shares are to be purchased from
+    pub outcome_target: u16, // outcome that the sender buys shares in
+    pub shares_out: WrappedBalance, // the exact amount of share tokens the sender wants to buy
+    pub max_collateral_in: WrappedBalance, // the maximum amount of collateral the sender is willing to spend, this is to prevent slippage
+    pub deadline: Option<WrappedTimestamp>, // timestamp in ms after which the purchase should no longer execute
+}
+
+#[derive(Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum Payload {
     BuyArgs(BuyArgs),
+    BuyExactSharesArgs(BuyExactSharesArgs),
     AddLiquidityArgs(AddLiquidityArgs),
     CreateMarketArgs(CreateMarketArgs)
 }
@@ -75,8 +102,17 @@ impl FungibleTokenReceiver for AMMContract {
 
         let payload: Payload = serde_json::from_str(&msg).expect("Failed to parse the payload, invalid `msg` format");
         let res = match payload {
-            Payload::BuyArgs(payload) => self.buy(&sender_id, amount, payload), 
-            Payload::AddLiquidityArgs(payload) => self.add_liquidity(&sender_id, amount, payload),
+            // The values returned by `buy` and `add_liquidity` are the amounts of shares and LP tokens minted, all collateral is used
+            Payload::BuyArgs(payload) => {
+                self.buy(&sender_id, amount, payload);
+                PromiseOrValue::Value(U128(0))
+            },
+            // Returns the collateral that wasn't needed to buy the requested shares, which is refunded by the token
+            Payload::BuyExactSharesArgs(payload) => self.buy_exact_shares(&sender_id, amount, payload),
+            Payload::AddLiquidityArgs(payload) => {
+                self.add_liquidity(&sender_id, amount, payload);
+                PromiseOrValue::Value(U128(0))
+            },
             Payload::CreateMarketArgs(payload) => self.ft_create_market_callback(&sender_id, amount, payload).into()
         };
 
@@ -124,6 +160,11 @@ mod mock_token_basic_tests {
         tags
     }
 
+    // Distinct tags, markets can't be created with duplicate outcome tags
+    fn outcome_tags(len: u16) -> Vec<String> {
+        (0..len).map(|outcome| outcome.to_string()).collect()
+    }
+
     fn to_valid(account: AccountId) -> ValidAccountId {
         account.try_into().expect("invalid account")
     }
@@ -150,7 +191,7 @@ mod mock_token_basic_tests {
     }
 
     #[test]
-    #[should_panic(expected = "alice.near has balance of: 0 but requires: 12910000000000000000000")]
+    #[should_panic(expected = "alice.near has balance of: 0 but requires: 14320000000000000000000")]
     fn transfer_storage_no_funds() {
         testing_env!(get_context(token()));
         let mut contract = AMMContract::init(
@@ -165,14 +206,24 @@ mod mock_token_basic_tests {
                 extra_info: empty_string(),
                 outcomes: 2,
                 sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                outcome_tags: empty_string_vec(2),
+                outcome_tags: outcome_tags(2),
                 categories: empty_string_vec(2),
                 end_time: 1609951265967.into(),
                 resolution_time: 1619882574000.into(), // (~1 day after end_time)
                 collateral_token_id: token(),
                 swap_fee: (10_u128.pow(24) / 50).into(), // 2%
                 challenge_period: U64(1),
-                is_scalar: false
+                is_scalar: false,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
             }
         );
 
@@ -204,7 +255,7 @@ mod mock_token_basic_tests {
                 description: empty_string(),
                 extra_info: empty_string(),
                 outcomes: 2,
-                outcome_tags: empty_string_vec(2),
+                outcome_tags: outcome_tags(2),
                 categories: empty_string_vec(2),
                 end_time: 1609951265967.into(),
                 sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
@@ -212,7 +263,17 @@ mod mock_token_basic_tests {
                 collateral_token_id: token(),
                 swap_fee: (10_u128.pow(24) / 50).into(), // 2%
                 challenge_period: U64(1),
-                is_scalar: false
+                is_scalar: false,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
             }
         );
 
@@ -1,7 +1,47 @@
 use crate::*;
 
+/**
+ * @notice set of governance parameters to change in a single `update_config` call, `None` fields are left untouched
+ */
+#[derive(Serialize, Deserialize)]
+pub struct PartialConfig {
+    pub min_market_duration_ms: Option<U64>, // Minimum time in ms between market creation and `end_time`
+    pub oracle_timeout_ms: Option<U64>, // Time after `resolution_time` after which unresolved markets can be forced invalid, disabling it requires `set_oracle_timeout_ms`
+    pub max_swap_fee_bps: Option<u16>, // Maximum swap fee for new markets in basis points
+    pub protocol_fee_fraction: Option<u16>, // Part of every swap fee that goes to the treasury, denominated in 1e4
+    pub treasury_beneficiaries: Option<Vec<(ValidAccountId, u16)>>, // Accounts that receive swept protocol fees and their share denominated in 1e4
+    pub allow_negative_bounds: Option<bool>, // If false scalar markets can't be created with negative bounds
+}
+
+/**
+ * @notice result of `get_config`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct ContractConfig {
+    pub gov: AccountId, // The gov of all markets
+    pub oracle: AccountId, // The Flux Oracle address
+    pub paused: bool, // If true certain functions are no longer callable
+    pub max_swap_fee_bps: u16, // Maximum swap fee for new markets in basis points
+    pub collateral_whitelist: Vec<collateral_whitelist::Token>, // Tokens that can be used as collateral
+}
+
 #[near_bindgen]
 impl AMMContract {
+    /**
+     * @returns the contract's governance configuration and collateral whitelist
+     */
+    pub fn get_config(&self) -> ContractConfig {
+        ContractConfig {
+            gov: self.gov.to_string(),
+            oracle: self.oracle.to_string(),
+            paused: self.paused,
+            max_swap_fee_bps: self.max_swap_fee_bps,
+            collateral_whitelist: self.collateral_whitelist.0.iter()
+                .map(|(account_id, decimals)| collateral_whitelist::Token { account_id, decimals })
+                .collect(),
+        }
+    }
+
     /**
      * @returns the current governance `AccountId`
      */
@@ -33,6 +73,37 @@ impl AMMContract {
         self.gov = new_gov.into();
     }
 
+    /**
+     * @returns the `AccountId` that's been proposed as the next `gov`, if any
+     */
+    pub fn pending_gov(&self) -> Option<AccountId> {
+        self.pending_gov.clone()
+    }
+
+    /**
+     * @notice proposes a new `gov`, which only takes over once it calls `accept_governance`. Proposing again overwrites the pending candidate
+     * @param new_gov `AccountId` of the proposed `gov`
+     */
+    pub fn propose_governance(
+        &mut self,
+        new_gov: ValidAccountId
+    ) {
+        self.assert_gov();
+        let new_gov: AccountId = new_gov.into();
+        logger::log_governance_proposed(&self.gov, &new_gov);
+        self.pending_gov = Some(new_gov);
+    }
+
+    /**
+     * @notice finalizes a governance transfer, only callable by the pending `gov`
+     */
+    pub fn accept_governance(&mut self) {
+        let pending_gov = self.pending_gov.take().expect("ERR_NO_PENDING_GOV");
+        assert_eq!(env::predecessor_account_id(), pending_gov, "ERR_NOT_PENDING_GOV");
+        logger::log_governance_accepted(&self.gov, &pending_gov);
+        self.gov = pending_gov;
+    }
+
     /**
      * @notice pauses the protocol making certain functions un-callable, can only be called by `gov`
      */
@@ -48,6 +119,178 @@ impl AMMContract {
         self.assert_gov();
         self.paused = false;
     }
+
+    /**
+     * @notice sets whether users can exit pools and claim earnings while the protocol is paused, trading and adding liquidity stay blocked
+     * @param allow_withdrawals_while_paused if true `exit_pool` and claims skip the pause check
+     */
+    pub fn set_allow_withdrawals_while_paused(&mut self, allow_withdrawals_while_paused: bool) {
+        self.assert_gov();
+        self.allow_withdrawals_while_paused = allow_withdrawals_while_paused;
+    }
+
+    /**
+     * @notice halts the resolution of all markets without affecting trading or claims on finalized markets, can only be called by `gov`
+     */
+    pub fn halt_resolution(&mut self) {
+        self.assert_gov();
+        self.resolution_halted = true;
+    }
+
+    /**
+     * @notice resumes market resolution after a halt
+     */
+    pub fn resume_resolution(&mut self) {
+        self.assert_gov();
+        self.resolution_halted = false;
+    }
+
+    /**
+     * @notice sets how long after `resolution_time` markets without an oracle answer can be forced invalid
+     * @param oracle_timeout_ms timeout in ms, `None` disables `force_invalid`
+     */
+    pub fn set_oracle_timeout_ms(&mut self, oracle_timeout_ms: Option<U64>) {
+        self.assert_gov();
+        self.oracle_timeout_ms = oracle_timeout_ms.map(u64::from);
+    }
+
+    /**
+     * @notice sets the maximum swap fee new markets can be created with
+     * @param max_swap_fee_bps maximum fee in basis points, has to be below 100%
+     */
+    pub fn set_max_swap_fee_bps(&mut self, max_swap_fee_bps: u16) {
+        self.assert_gov();
+        assert!(max_swap_fee_bps < constants::FEE_BPS_DENOMINATION, "ERR_FEE_TOO_HIGH");
+        self.max_swap_fee_bps = max_swap_fee_bps;
+    }
+
+    /**
+     * @returns the maximum swap fee new markets can be created with, in basis points
+     */
+    pub fn get_max_swap_fee_bps(&self) -> u16 {
+        self.max_swap_fee_bps
+    }
+
+    /**
+     * @notice sets the maximum number of outcomes new markets can have, existing markets aren't affected
+     * @param max_outcomes maximum number of outcomes, between `MIN_OUTCOMES` and `MAX_OUTCOMES`
+     */
+    pub fn set_max_outcomes(&mut self, max_outcomes: u16) {
+        self.assert_gov();
+        assert!(max_outcomes >= constants::MIN_OUTCOMES && max_outcomes <= constants::MAX_OUTCOMES, "ERR_INVALID_MAX_OUTCOMES");
+        self.max_outcomes = max_outcomes;
+    }
+
+    /**
+     * @returns the maximum number of outcomes new markets can have
+     */
+    pub fn get_max_outcomes(&self) -> u16 {
+        self.max_outcomes
+    }
+
+    /**
+     * @notice sets whether scalar markets can be created with negative bounds
+     * @param allow_negative_bounds if false, scalar markets with a negative bound are rejected
+     */
+    pub fn set_allow_negative_bounds(&mut self, allow_negative_bounds: bool) {
+        self.assert_gov();
+        self.allow_negative_bounds = allow_negative_bounds;
+    }
+
+    /**
+     * @notice sets the minimum time between a market's creation and its `end_time`
+     * @param min_market_duration_ms minimum duration in ms, 0 disables the check
+     */
+    pub fn set_min_market_duration_ms(&mut self, min_market_duration_ms: U64) {
+        self.assert_gov();
+        self.min_market_duration_ms = min_market_duration_ms.into();
+    }
+
+    /**
+     * @returns the minimum time in ms between a market's creation and its `end_time`
+     */
+    pub fn get_min_market_duration_ms(&self) -> U64 {
+        self.min_market_duration_ms.into()
+    }
+
+    /**
+     * @notice sets the minimum amount of collateral that has to be provided when a market is first seeded, later additions aren't affected
+     * @param min_initial_liquidity minimum in the collateral's smallest unit, 0 disables the check
+     */
+    pub fn set_min_initial_liquidity(&mut self, min_initial_liquidity: WrappedBalance) {
+        self.assert_gov();
+        self.min_initial_liquidity = min_initial_liquidity.into();
+    }
+
+    /**
+     * @returns the minimum amount of collateral required to seed a market
+     */
+    pub fn get_min_initial_liquidity(&self) -> WrappedBalance {
+        U128(self.min_initial_liquidity)
+    }
+
+    /**
+     * @notice blocks `account_id` from trading, providing liquidity and withdrawing in every market, can only be called by `gov`
+     * @param account_id the account to block
+     */
+    pub fn add_to_blacklist(&mut self, account_id: ValidAccountId) {
+        self.assert_gov();
+        self.blacklist.insert(account_id.as_ref());
+    }
+
+    /**
+     * @notice restores `account_id`'s access to all markets, can only be called by `gov`
+     * @param account_id the account to unblock
+     */
+    pub fn remove_from_blacklist(&mut self, account_id: ValidAccountId) {
+        self.assert_gov();
+        self.blacklist.remove(account_id.as_ref());
+    }
+
+    /**
+     * @returns whether `account_id` is blocked from interacting with markets
+     */
+    pub fn is_blacklisted(&self, account_id: ValidAccountId) -> bool {
+        self.blacklist.contains(account_id.as_ref())
+    }
+
+    /**
+     * @notice applies several governance parameter changes at once, either all changes are applied or the call panics without changing anything
+     * The resulting configuration is validated as a whole before it's stored, so checks see the other parameters' new values
+     * @param config the parameters to change, `None` fields keep their current value
+     */
+    pub fn update_config(&mut self, config: PartialConfig) {
+        self.assert_gov();
+
+        let max_swap_fee_bps = config.max_swap_fee_bps.unwrap_or(self.max_swap_fee_bps);
+        let protocol_fee_fraction = config.protocol_fee_fraction.unwrap_or(self.protocol_fee_fraction);
+
+        assert!(max_swap_fee_bps < constants::FEE_BPS_DENOMINATION, "ERR_FEE_TOO_HIGH");
+        assert!(protocol_fee_fraction <= constants::FEE_BPS_DENOMINATION, "ERR_INVALID_PROTOCOL_FEE");
+        // The treasury can only take a cut of swap fees if markets are allowed to charge one
+        assert!(protocol_fee_fraction == 0 || max_swap_fee_bps > 0, "ERR_PROTOCOL_FEE_WITHOUT_SWAP_FEE");
+        let treasury_beneficiaries = config.treasury_beneficiaries.as_ref().map(|beneficiaries| treasury::to_beneficiaries(beneficiaries));
+        if let Some(beneficiaries) = &treasury_beneficiaries {
+            treasury::assert_valid_beneficiaries(beneficiaries);
+        }
+
+        logger::log_config_updated(&config);
+
+        self.max_swap_fee_bps = max_swap_fee_bps;
+        self.protocol_fee_fraction = protocol_fee_fraction;
+        if let Some(min_market_duration_ms) = config.min_market_duration_ms {
+            self.min_market_duration_ms = min_market_duration_ms.into();
+        }
+        if let Some(oracle_timeout_ms) = config.oracle_timeout_ms {
+            self.oracle_timeout_ms = Some(oracle_timeout_ms.into());
+        }
+        if let Some(beneficiaries) = treasury_beneficiaries {
+            self.treasury_beneficiaries = beneficiaries;
+        }
+        if let Some(allow_negative_bounds) = config.allow_negative_bounds {
+            self.allow_negative_bounds = allow_negative_bounds;
+        }
+    }
 }
 
 
@@ -67,10 +310,233 @@ impl AMMContract {
         assert!(!self.paused, "ERR_PROTCOL_PAUSED")
     }
 
+    /**
+     * @panics if the protocol is paused, unless `gov` allows withdrawals while paused
+     */
+    pub fn assert_withdrawals_allowed(&self) {
+        assert!(!self.paused || self.allow_withdrawals_while_paused, "ERR_PROTCOL_PAUSED")
+    }
+
+    /**
+     * @panics if `account_id` is blacklisted by `gov`
+     */
+    pub fn assert_not_blacklisted(&self, account_id: &AccountId) {
+        assert!(!self.blacklist.contains(account_id), "ERR_BLACKLISTED");
+    }
+
+    /**
+     * @panics if market resolution is halted
+     */
+    pub fn assert_resolution_not_halted(&self) {
+        assert!(!self.resolution_halted, "ERR_RESOLUTION_HALTED")
+    }
+
     /**
      * @panics if the predecessor is not the oracle
      */
     pub fn assert_oracle(&self) {
         assert_eq!(env::predecessor_account_id(), self.oracle, "ERR_NO_ORACLE_ADDRESS");
     }
-}
\ No newline at end of file
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod gov_tests {
+    use std::convert::TryInto;
+    use near_sdk::{ MockedBlockchain };
+    use near_sdk::{ testing_env };
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_NEGATIVE_BOUNDS_DISABLED")]
+    fn negative_scalar_bounds_rejected_when_disabled() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_allow_negative_bounds(false);
+
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["-10".to_string(), "10".to_string()];
+        args.is_scalar = true;
+        contract.create_market(&args);
+    }
+
+    #[test]
+    fn negative_scalar_bounds_allowed_by_default() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["-10".to_string(), "10".to_string()];
+        args.is_scalar = true;
+        contract.create_market(&args);
+
+        contract.set_allow_negative_bounds(false);
+        args.outcome_tags = vec!["0".to_string(), "10".to_string()];
+        contract.create_market(&args);
+    }
+
+    #[test]
+    fn two_step_governance_transfer() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+
+        contract.propose_governance(alice().try_into().unwrap());
+        contract.propose_governance(carol().try_into().unwrap());
+        assert_eq!(contract.pending_gov(), Some(carol()));
+
+        // The current gov keeps full control until the transfer is accepted
+        contract.pause();
+        contract.unpause();
+
+        testing_env!(get_context(carol(), 0));
+        contract.accept_governance();
+
+        assert_eq!(contract.gov(), carol());
+        assert_eq!(contract.pending_gov(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_PENDING_GOV")]
+    fn accept_governance_not_pending() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.propose_governance(carol().try_into().unwrap());
+
+        testing_env!(get_context(alice(), 0));
+        contract.accept_governance();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_TOO_SHORT")]
+    fn create_market_below_min_duration() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_min_market_duration_ms(U64(3_600_000));
+
+        let args = default_market_args();
+        let now: u64 = 1609951265967 - 3_599_999;
+        testing_env!(get_context(alice(), ms_to_ns(now)));
+        contract.create_market(&args);
+    }
+
+    #[test]
+    fn create_market_above_min_duration() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_min_market_duration_ms(U64(3_600_000));
+        assert_eq!(contract.get_min_market_duration_ms(), U64(3_600_000));
+
+        let args = default_market_args();
+        let now: u64 = 1609951265967 - 3_600_000;
+        testing_env!(get_context(alice(), ms_to_ns(now)));
+        let market_id = contract.create_market(&args);
+        assert_eq!(market_id, U64(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FEE_TOO_HIGH")]
+    fn set_max_swap_fee_bps_full_fee() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_max_swap_fee_bps(10_000);
+    }
+
+    #[test]
+    fn update_config_applies_all_changes() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.update_config(PartialConfig {
+            min_market_duration_ms: Some(U64(3600000)),
+            oracle_timeout_ms: Some(U64(86400000)),
+            max_swap_fee_bps: Some(1000),
+            protocol_fee_fraction: Some(2500),
+            treasury_beneficiaries: Some(vec![(carol().try_into().unwrap(), 10000)]),
+            allow_negative_bounds: None,
+        });
+
+        assert_eq!(contract.get_min_market_duration_ms(), U64(3600000));
+        assert_eq!(contract.oracle_timeout_ms, Some(86400000));
+        assert_eq!(contract.get_max_swap_fee_bps(), 1000);
+        assert_eq!(contract.get_protocol_fee_fraction(), 2500);
+        assert_eq!(contract.get_treasury_beneficiaries(), vec![(carol(), 10000)]);
+        assert!(contract.allow_negative_bounds);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROTOCOL_FEE_WITHOUT_SWAP_FEE")]
+    fn update_config_rejects_protocol_fee_without_swap_fee() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.update_config(PartialConfig {
+            min_market_duration_ms: Some(U64(3600000)),
+            oracle_timeout_ms: None,
+            max_swap_fee_bps: Some(0),
+            protocol_fee_fraction: Some(2500),
+            treasury_beneficiaries: None,
+            allow_negative_bounds: None,
+        });
+    }
+
+    #[test]
+    fn get_config_reflects_state() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.pause();
+
+        let config = contract.get_config();
+        assert_eq!(config.gov, bob());
+        assert_eq!(config.oracle, oracle());
+        assert!(config.paused);
+        assert_eq!(config.max_swap_fee_bps, constants::DEFAULT_MAX_SWAP_FEE_BPS);
+        assert_eq!(config.collateral_whitelist.len(), 1);
+        assert_eq!(config.collateral_whitelist[0].account_id, token());
+        assert_eq!(config.collateral_whitelist[0].decimals, 24);
+    }
+
+    fn market_args_with_outcomes(outcomes: u16) -> CreateMarketArgs {
+        let mut args = default_market_args();
+        args.outcomes = outcomes;
+        args.outcome_tags = outcome_tags(outcomes);
+        args.categories = empty_string_vec(outcomes);
+        args
+    }
+
+    #[test]
+    fn create_market_at_max_outcomes() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        assert_eq!(contract.get_max_outcomes(), constants::MAX_OUTCOMES);
+
+        let market_id = contract.create_market(&market_args_with_outcomes(constants::MAX_OUTCOMES));
+        assert_eq!(contract.get_market_expect(market_id).pool.outcomes, constants::MAX_OUTCOMES);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOO_MANY_OUTCOMES")]
+    fn create_market_over_max_outcomes() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        contract.create_market(&market_args_with_outcomes(constants::MAX_OUTCOMES + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOO_MANY_OUTCOMES")]
+    fn create_market_over_lowered_max_outcomes() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_max_outcomes(4);
+
+        testing_env!(get_context(alice(), 0));
+        contract.create_market(&market_args_with_outcomes(4));
+        contract.create_market(&market_args_with_outcomes(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_MAX_OUTCOMES")]
+    fn set_max_outcomes_above_pool_limit() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_max_outcomes(constants::MAX_OUTCOMES + 1);
+    }
+}
@@ -34,6 +34,16 @@ pub fn ms_to_ns(ms_timestamp: u64) -> u64 {
     ms_timestamp * 1_000_000
 }
 
+/**
+ * @panics if `deadline` is set and the current block time is past it
+ * @param deadline optional timestamp in ms after which the transaction should no longer execute
+ */
+pub fn assert_deadline(deadline: Option<WrappedTimestamp>) {
+    if let Some(deadline) = deadline {
+        assert!(ns_to_ms(env::block_timestamp()) <= u64::from(deadline), "ERR_DEADLINE_PASSED");
+    }
+}
+
 pub fn is_promise_success() -> bool {
     assert_eq!(
         env::promise_results_count(),
@@ -57,6 +67,57 @@ pub fn clamp_f64(value: f64, min: f64, max: f64) -> f64 {
     }
 }
 
+/**
+ * @returns the number of digits after the decimal point in `value`
+ */
+pub fn decimal_places(value: &str) -> u32 {
+    value.find('.').map(|index| (value.len() - index - 1) as u32).unwrap_or(0)
+}
+
+/**
+ * @notice parses a plain decimal string e.g. "-2.68" into a fixed point integer with `decimals` digits after the decimal point
+ * @returns `None` if `value` isn't a plain decimal, has more than `decimals` decimals or doesn't fit in an `i128`
+ */
+pub fn parse_fixed_point(value: &str, decimals: u32) -> Option<i128> {
+    let (negative, unsigned) = match value.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, value)
+    };
+    let (int_part, frac_part) = match unsigned.find('.') {
+        Some(index) => (&unsigned[..index], &unsigned[index + 1..]),
+        None => (unsigned, "")
+    };
+
+    if (int_part.is_empty() && frac_part.is_empty()) || frac_part.len() as u32 > decimals {
+        return None;
+    }
+
+    let mut fixed_point: i128 = 0;
+    let padding = std::iter::repeat('0').take((decimals - frac_part.len() as u32) as usize);
+    for c in int_part.chars().chain(frac_part.chars()).chain(padding) {
+        let digit = c.to_digit(10)? as i128;
+        fixed_point = fixed_point.checked_mul(10)?.checked_add(digit)?;
+    }
+
+    if negative {
+        Some(-fixed_point)
+    } else {
+        Some(fixed_point)
+    }
+}
+
+/**
+ * @notice distance between two fixed point values, exact for any pair of `i128`s since the difference always fits in a `u128`
+ * @returns `ERR_SCALAR_UNDERFLOW` if `upper` is smaller than `lower`
+ */
+pub fn checked_span(lower: i128, upper: i128) -> Result<u128, &'static str> {
+    if upper < lower {
+        return Err("ERR_SCALAR_UNDERFLOW");
+    }
+    // Two's complement subtraction is exact modulo 2^128, the true difference is below 2^128
+    Ok((upper as u128).wrapping_sub(lower as u128))
+}
+
 /** 
  * @notice refunds any cleared up or overpaid storage to original sender, also checks if the sender added enough deposit to cover storage
  * @param initial_storage is the storage at the beginning of the function call
@@ -1,7 +1,7 @@
 #![allow(clippy::too_many_arguments, clippy::ptr_arg)]
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::{U128, U64, ValidAccountId};
-use near_sdk::collections::{Vector, UnorderedMap, LookupMap};
+use near_sdk::collections::{Vector, UnorderedMap, LookupMap, LookupSet};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     serde_json,
@@ -34,6 +34,13 @@ mod oracle;
 mod market_creation;
 mod fungible_token;
 mod storage_manager;
+mod migration;
+mod treasury;
+mod payout;
+mod oracle_quorum;
+mod optimistic_resolution;
+#[cfg(test)]
+mod test_utils;
 
 pub mod collateral_whitelist; // pub for integration tests 
 pub mod math; // pub for integration tests
@@ -41,8 +48,9 @@ pub mod math; // pub for integration tests
 use crate::types::*;
 use crate::fungible_token_receiver::*;
 use crate::helper::*;
-use crate::market::Market;
-use crate::pool::Pool;
+use crate::market::{ Market, AmbiguityPolicy, FeeSchedule };
+use crate::pool::{ Pool, PricingMode };
+use crate::optimistic_resolution::ProposedOutcome;
 use crate::collateral_whitelist::Whitelist;
 use crate::storage_manager::AccountStorageBalance;
 
@@ -61,7 +69,30 @@ pub struct AMMContract {
     markets: Vector<Market>, // Vector containing all markets where the index represents the market id
     collateral_whitelist: Whitelist, // Map a token's account id to number of decimals it's denominated in
     paused: bool, // If true certain functions are no longer callable, settable by `gov`
-    accounts: LookupMap<AccountId, AccountStorageBalance> // Storage map
+    accounts: LookupMap<AccountId, AccountStorageBalance>, // Storage map
+    allow_negative_bounds: bool, // If false scalar markets can't be created with negative bounds, settable by `gov`
+    finalized_market_count: u64, // Number of markets that have been finalized, including invalid markets
+    invalid_market_count: u64, // Number of markets that have been finalized as invalid
+    pending_gov: Option<AccountId>, // Account proposed as the next `gov`, takes over once it accepts
+    min_market_duration_ms: u64, // Minimum time in ms between market creation and `end_time`, settable by `gov`
+    resolution_halted: bool, // If true no market can be resolved, settable by `gov`
+    oracle_timeout_ms: Option<u64>, // Time after `resolution_time` after which anyone can force an unresolved market invalid, settable by `gov`
+    active_collateral_tokens: UnorderedMap<AccountId, u64>, // Map a collateral token's account id to the number of non-finalized markets using it
+    max_swap_fee_bps: u16, // Maximum swap fee for new markets in basis points, settable by `gov`
+    protocol_fee_fraction: u16, // Part of every swap fee that goes to the treasury instead of LPs, denominated in 1e4, settable by `gov`
+    treasury_beneficiaries: Vec<(AccountId, u16)>, // Accounts that receive swept protocol fees and their share denominated in 1e4, settable by `gov`
+    treasury_balances: LookupMap<AccountId, Balance>, // Map a collateral token's account id to the protocol fees accrued in it
+    allow_withdrawals_while_paused: bool, // If true `exit_pool` and claims remain callable while the protocol is paused, settable by `gov`
+    min_initial_liquidity: Balance, // Minimum amount of collateral the first `add_liquidity` of a market has to provide, settable by `gov`
+    blacklist: LookupSet<AccountId>, // Accounts that can't trade, provide liquidity or withdraw from any market, settable by `gov`
+    failed_payouts: LookupMap<(AccountId, AccountId), Balance>, // Map (account id, collateral token id) to collateral from payouts whose transfer failed
+    oracles: Vec<AccountId>, // Oracle accounts whose aggregated answers can resolve markets through `submit_outcome`, settable by `gov`
+    oracle_quorum: u16, // Number of `oracles` answers required before a market is finalized, settable by `gov`
+    pending_answers: LookupMap<(u64, AccountId), Outcome>, // Map (market id, oracle id) to answers that haven't reached quorum yet
+    optimistic_resolution: bool, // If true oracle outcomes are proposed and only finalized after the market's challenge period, settable by `gov`
+    challenge_bond: Balance, // Minimum bond in NEAR required to challenge a proposed outcome, settable by `gov`
+    max_outcomes: u16, // Maximum number of outcomes new markets can have, settable by `gov` up to `MAX_OUTCOMES`
+    reentrancy_locks: LookupSet<(AccountId, u64)>, // (account id, market id) pairs with a payout from `sell`, `exit_pool`, `claim_earnings` or `claim_earnings_batch` in flight
 }
 
 #[near_bindgen]
@@ -79,16 +110,40 @@ impl AMMContract {
     ) -> Self {
         assert!(!env::state_exists(), "ERR_CONTRACT_IS_INITIALIZED");
         let collateral_whitelist: Whitelist = Whitelist::new(tokens);
+        let gov: AccountId = gov.into();
 
         logger::log_whitelist(&collateral_whitelist);
 
         Self {
             oracle: oracle.into(),
-            gov: gov.into(),
+            gov: gov.to_string(),
             markets: Vector::new(b"m".to_vec()),
             collateral_whitelist, 
             paused: false,
             accounts: LookupMap::new(b"as".to_vec()),
+            allow_negative_bounds: true,
+            finalized_market_count: 0,
+            invalid_market_count: 0,
+            pending_gov: None,
+            min_market_duration_ms: 0,
+            resolution_halted: false,
+            oracle_timeout_ms: None,
+            active_collateral_tokens: UnorderedMap::new(b"ac".to_vec()),
+            max_swap_fee_bps: constants::DEFAULT_MAX_SWAP_FEE_BPS,
+            protocol_fee_fraction: 0,
+            treasury_beneficiaries: vec![(gov, constants::FEE_BPS_DENOMINATION)],
+            treasury_balances: LookupMap::new(b"tb".to_vec()),
+            allow_withdrawals_while_paused: false,
+            min_initial_liquidity: 0,
+            blacklist: LookupSet::new(b"bl".to_vec()),
+            failed_payouts: LookupMap::new(b"fp".to_vec()),
+            oracles: vec![],
+            oracle_quorum: 0,
+            pending_answers: LookupMap::new(b"pa".to_vec()),
+            optimistic_resolution: false,
+            challenge_bond: 0,
+            max_outcomes: constants::MAX_OUTCOMES,
+            reentrancy_locks: LookupSet::new(b"rl".to_vec()),
         }
     }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 #![allow(clippy::too_many_arguments, clippy::ptr_arg)]
 use crate::*;
-use near_sdk::serde_json::json;
+use near_sdk::serde_json::{ self, json };
 use outcome_token::MintableToken;
 
 #[derive(serde::Serialize)]
@@ -13,22 +13,59 @@ pub enum TransactionType {
     RemoveLiquidity,
 }
 
+const EVENT_STANDARD: &str = "amm";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/**
+ * @notice NEP-297 event envelope, fields are serialized in declaration order
+ */
+#[derive(serde::Serialize)]
+struct Event<'a> {
+    standard: &'a str,
+    version: &'a str,
+    event: &'a str,
+    data: [serde_json::Value; 1],
+}
+
+/**
+ * @notice formats a NEP-297 compliant event log line
+ * @param event is the name of the event
+ * @param data is the event payload, every payload contains the `market_id`
+ * @returns the log line prefixed with `EVENT_JSON:`
+ */
+pub fn format_event(event: &str, data: serde_json::Value) -> String {
+    let event = Event {
+        standard: EVENT_STANDARD,
+        version: EVENT_STANDARD_VERSION,
+        event,
+        data: [data],
+    };
+
+    format!("EVENT_JSON:{}", serde_json::to_string(&event).unwrap())
+}
+
+fn log_event(event: &str, data: serde_json::Value) {
+    env::log(format_event(event, data).as_bytes());
+}
+
 pub fn log_transaction(tx_type: &TransactionType, account_id: &AccountId, input: u128, output: u128, market_id: U64, outcome_id: Option<u16>) {
-    env::log(
+    let event = match tx_type {
+        TransactionType::Redeem => "redeem",
+        TransactionType::ClaimEarnings => "claim_earnings",
+        _ => "trade",
+    };
+
+    log_event(
+        event,
         json!({
-            "type": "transactions",
-            "params": {
-                "account_id": account_id,
-                "input": U128(input),
-                "output": U128(output),
-                "market_id": market_id,
-                "outcome_id": outcome_id.unwrap_or(0),
-                "date": U64(ns_to_ms(env::block_timestamp())),
-                "type": tx_type,
-            }
+            "market_id": market_id,
+            "account_id": account_id,
+            "input": U128(input),
+            "output": U128(output),
+            "outcome_id": outcome_id.unwrap_or(0),
+            "date": U64(ns_to_ms(env::block_timestamp())),
+            "type": tx_type,
         })
-        .to_string()
-        .as_bytes()
     );
 }
 
@@ -126,6 +163,72 @@ pub fn log_exit_pool(pool: &Pool, account_id: &AccountId, pool_tokens_in: u128,
 	);
 }
 
+pub fn log_pool_token_transfer(pool: &Pool, sender: &AccountId, receiver_id: &AccountId, amount: u128, fees_earned: u128) {
+    log_event(
+        "pool_token_transfer",
+        json!({
+            "market_id": U64(pool.id),
+            "sender": sender,
+            "receiver_id": receiver_id,
+            "amount": U128(amount),
+            "fees_earned": U128(fees_earned),
+        })
+    );
+}
+
+pub fn log_failed_payout(account_id: &AccountId, collateral_token_id: &AccountId, amount: u128) {
+    log_event(
+        "payout_failed",
+        json!({
+            "account_id": account_id,
+            "collateral_token_id": collateral_token_id,
+            "amount": U128(amount),
+        })
+    );
+}
+
+pub fn log_oracle_answer(market_id: u64, oracle: &AccountId, outcome: &Outcome) {
+    log_event(
+        "oracle_answer_submitted",
+        json!({
+            "market_id": U64(market_id),
+            "oracle": oracle,
+            "outcome": outcome,
+        })
+    );
+}
+
+pub fn log_outcome_proposed(market: &Market) {
+    log_event(
+        "outcome_proposed",
+        json!({
+            "market_id": U64(market.pool.id),
+            "proposed_outcome": market.proposed_outcome,
+        })
+    );
+}
+
+pub fn log_outcome_challenged(market: &Market) {
+    log_event(
+        "outcome_challenged",
+        json!({
+            "market_id": U64(market.pool.id),
+            "proposed_outcome": market.proposed_outcome,
+        })
+    );
+}
+
+pub fn log_challenge_settled(market_id: u64, bond_receiver: &AccountId, challenge_succeeded: bool) {
+    log_event(
+        "challenge_settled",
+        json!({
+            "market_id": U64(market_id),
+            "bond_receiver": bond_receiver,
+            "challenge_succeeded": challenge_succeeded,
+        })
+    );
+}
+
 enum SwapType {
     Sell,
     Buy,
@@ -197,49 +300,84 @@ pub fn log_create_market(
     extra_info: &String,  
     categories: &Vec<String>,
 ) {
-	env::log(
-		json!({
-            "type": "markets".to_string(),
-            "action": "update",
-            "cap_id": format!("m_{}", market.pool.id),
-			"params": {
-                "id": U64(market.pool.id),
-                "description": description,
-                "extra_info": extra_info,
-                "outcome_tags": market.outcome_tags,
-                "end_time": U64(market.end_time),
-                "resolution_time": U64(market.resolution_time),
-                "finalized": market.finalized,
-                "payout_numerator": market.payout_numerator,
-                "categories": categories,
-                "creation_date": U64(ns_to_ms(env::block_timestamp())),
-                "enabled": market.enabled,
-                "is_scalar": market.is_scalar,
-			}
-		})
-		.to_string()
-		.as_bytes()
-	);
+    log_event(
+        "market_created",
+        json!({
+            "market_id": U64(market.pool.id),
+            "description": description,
+            "extra_info": extra_info,
+            "outcome_tags": market.outcome_tags,
+            "end_time": U64(market.end_time),
+            "resolution_time": U64(market.resolution_time),
+            "finalized": market.finalized,
+            "payout_numerator": market.payout_numerator,
+            "categories": categories,
+            "creation_date": U64(ns_to_ms(env::block_timestamp())),
+            "enabled": market.enabled,
+            "is_scalar": market.is_scalar,
+        })
+    );
 }
 
 pub fn log_market_status(market: &Market) {
-    env::log(
-		json!({
-            "type": "markets".to_string(),
-            "action": "update",
-            "cap_id": format!("m_{}", market.pool.id),
-			"params": {
-                "payout_numerator": market.payout_numerator,
-                "finalized": market.finalized,
-                "enabled": market.enabled,
-			}
-		})
-		.to_string()
-		.as_bytes()
-	);
+    let event = if market.finalized {
+        "market_resolved"
+    } else {
+        "market_updated"
+    };
+
+    log_event(
+        event,
+        json!({
+            "market_id": U64(market.pool.id),
+            "payout_numerator": market.payout_numerator,
+            "finalized": market.finalized,
+            "enabled": market.enabled,
+        })
+    );
+}
+
+pub fn log_market_outcome_overridden(market: &Market, previous_payout_numerator: &Option<Vec<U128>>) {
+    log_event(
+        "market_resolved",
+        json!({
+            "market_id": U64(market.pool.id),
+            "payout_numerator": market.payout_numerator,
+            "previous_payout_numerator": previous_payout_numerator,
+            "finalized": market.finalized,
+            "enabled": market.enabled,
+            "override": true,
+        })
+    );
 }
 
 // NEW_OWNER
+pub fn log_governance_proposed(gov: &AccountId, pending_gov: &AccountId) {
+    log_event(
+        "governance_proposed",
+        json!({
+            "gov": gov,
+            "pending_gov": pending_gov,
+        })
+    );
+}
+
+pub fn log_governance_accepted(previous_gov: &AccountId, gov: &AccountId) {
+    log_event(
+        "governance_accepted",
+        json!({
+            "previous_gov": previous_gov,
+            "gov": gov,
+        })
+    );
+}
+
+pub fn log_config_updated(config: &gov::PartialConfig) {
+    log_event(
+        "config_updated",
+        json!(config)
+    );
+}
 
 // LOG_JOIN
 // LOG_EXIT
@@ -278,18 +416,6 @@ pub fn log_claim_earnings(
     payout: u128
 ) {
     log_transaction(&TransactionType::ClaimEarnings, &claimer, 0, payout, market_id, None);
-    env::log(
-		json!({
-			"type": "claims".to_string(),
-			"params": {
-                "market_id": market_id,
-                "claimer": claimer,
-                "payout": U128(payout),
-			}
-		})
-		.to_string()
-		.as_bytes()
-	);
 }
 
 pub fn log_withdrawn_fees(pool_token: &MintableToken, account_id: &AccountId, withdrawn_amount: u128) {
@@ -329,3 +455,17 @@ pub fn log_account_outcome_spent(pool: &Pool, account_id: &AccountId, outcome_id
 		.as_bytes()
 	);
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod logger_tests {
+    use super::*;
+
+    #[test]
+    fn event_log_format() {
+        assert_eq!(
+            format_event("trade", json!({ "market_id": U64(0) })),
+            r#"EVENT_JSON:{"standard":"amm","version":"1.0.0","event":"trade","data":[{"market_id":"0"}]}"#
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::*;
+use near_sdk::json_types::I128;
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Market {
@@ -10,10 +11,291 @@ pub struct Market {
     pub finalized: bool, // If true the market has an outcome, if false the market it still undecided.
     pub enabled: bool, // If false the market is disabled for interaction.
     pub is_scalar: bool, // If true the market is scalar, false for categorical
+    pub min_lp_count_to_trade: Option<u64>, // Optional minimum number of distinct LPs required before `buy` and `sell` are allowed
+    pub categories: Vec<String>, // Categories for filtering and curation
+    pub ambiguity_policy: AmbiguityPolicy, // How categorical answers matching multiple tags are handled
+    pub min_liquidity_add: Balance, // Minimum amount of collateral accepted by `add_liquidity`, 0 disables the minimum
+    pub lp_lockup_ms: Option<u64>, // Optional period after an LP's latest liquidity addition during which they can't exit the pool
+    pub price_tick: Option<Balance>, // Optional tick size that spot prices are rounded to, denominated in the collateral denomination
+    pub notification_endpoint: Option<String>, // Optional endpoint off-chain services can notify of resolution events, never called by the contract
+    pub max_pool_collateral: Option<Balance>, // Optional cap on the collateral locked in the market that `add_liquidity` can't exceed
+    pub start_time: Option<Timestamp>, // Optional time in ms before which the market can't be traded or receive liquidity
+    pub cancelled: bool, // If true the market was cancelled by `gov` and finalized as invalid
+    pub created_at: Timestamp, // Time in ms when the market was created
+    pub fee_schedule: Option<FeeSchedule>, // Optional swap fee that moves linearly from `start_fee` at creation to `end_fee` at `end_time`
+    pub min_bet: Option<Balance>, // Optional minimum amount of collateral a single `buy` has to spend
+    pub max_bet: Option<Balance>, // Optional maximum amount of collateral a single `buy` can spend
+    pub trader_allowlist: Option<LookupSet<AccountId>>, // Optional set of accounts that can trade and add liquidity, `None` if the market is public
+    pub allowlist_nonce: u32, // Number of allowlists set on the market, part of the allowlist's storage prefix so every allowlist starts empty
+    pub challenge_period: u64, // Challenge period in ns requested from the oracle, also the time a proposed outcome can be challenged
+    pub proposed_outcome: Option<ProposedOutcome>, // Outcome awaiting its challenge period while optimistic resolution is enabled
+    pub claims_started: bool, // If true earnings have been claimed from the finalized market, its outcome can no longer be overridden
+}
+
+/**
+ * @notice swap fee that changes linearly over a market's trading period, denominated like `swap_fee`
+ */
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct FeeSchedule {
+    pub start_fee: U128, // Swap fee at the market's creation
+    pub end_fee: U128, // Swap fee at the market's `end_time`
+}
+
+/**
+ * @notice how a categorical answer that matches more than one outcome tag is resolved
+ */
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum AmbiguityPolicy {
+    FirstMatch, // The first matching outcome wins
+    RejectAmbiguous, // Resolution fails with `ERR_AMBIGUOUS_ANSWER`
+}
+
+/**
+ * @notice result of a single entry in `set_outcomes_batch`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct SetOutcomeReport {
+    pub market_id: Option<U64>, // Market the entry referenced, `None` if the entry had no tags
+    pub success: bool, // If true the market was finalized
+    pub error: Option<String>, // Reason the entry was skipped
+}
+
+/**
+ * @notice result of `calc_add_liquidity_preview`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct AddLiquidityPreview {
+    pub lp_tokens: WrappedBalance, // Amount of LP tokens that would be minted
+    pub outcome_shares: Vec<WrappedBalance>, // Outcome shares credited to the provider, each index corresponds to an outcome
+}
+
+/**
+ * @notice result of `calc_exit_pool_preview`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct ExitPoolPreview {
+    pub fees: WrappedBalance, // Accrued fees that are paid out on exit
+    pub collateral: WrappedBalance, // Total collateral transferred on exit, LP positions are returned as outcome shares so this equals `fees`
+    pub outcome_shares: Vec<WrappedBalance>, // Outcome shares returned to the LP, each index corresponds to an outcome
+}
+
+/**
+ * @notice result of `calc_buy_price_impact`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct PriceImpact {
+    pub spot_price: WrappedBalance, // Fee-inclusive spot price of the outcome before the trade
+    pub effective_price: WrappedBalance, // Average price paid per share, `collateral_in / shares_out`
+    pub impact_bps: U64, // Difference between `effective_price` and `spot_price` in basis points of `spot_price`
+}
+
+/**
+ * @notice result of `get_pool_info`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct PoolInfo {
+    pub outcomes: u16, // Number of outcomes in the pool
+    pub collateral_denomination: WrappedBalance, // 10^decimals of the collateral token
+    pub pool_balances: Vec<WrappedBalance>, // The pool's balance of every outcome token
+    pub pool_token_total_supply: WrappedBalance, // LP token total supply
+    pub fee_pool_weight: WrappedBalance, // Weight that dictates fee payouts
+    pub swap_fee: WrappedBalance, // Fee taken from every swap, denominated in `collateral_denomination`
+}
+
+/**
+ * @notice result of `get_user_market_position`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct UserPosition {
+    pub share_balances: Vec<WrappedBalance>, // The account's balance of every outcome token
+    pub pool_token_balance: WrappedBalance, // The account's LP token balance
+    pub fees_withdrawable: WrappedBalance, // Fees the account can currently withdraw as an LP
+}
+
+/**
+ * @notice result of `get_collateral_invariant`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct InvariantReport {
+    pub collateral_held: WrappedBalance, // Collateral the pool received minus what it paid out, excluding protocol fees
+    pub outstanding_shares: WrappedBalance, // Complete sets of outcome shares held by LPs through the pool and by traders, each backed by one unit of collateral
+    pub accrued_fees: WrappedBalance, // LP fees that haven't been withdrawn yet
+    pub escrowed: WrappedBalance, // Collateral withheld from sells and redemptions into the resolution escrow
+    pub obligations: WrappedBalance, // Sum of `outstanding_shares`, `accrued_fees` and `escrowed`
+    pub difference: I128, // `collateral_held` minus `obligations`, anything beyond rounding dust indicates accounting drift
+}
+
+/**
+ * @notice result of `calc_buy_amount_with_fee`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct BuyQuote {
+    pub shares_out: WrappedBalance, // Shares the buy would mint to the buyer
+    pub fee_paid: WrappedBalance, // Part of `collateral_in` taken as swap fee
+    pub collateral_after_fee: WrappedBalance, // Part of `collateral_in` that's used to buy shares
+}
+
+/**
+ * @notice result of `calc_sell_collateral_out_with_fee`
+ */
+#[derive(Serialize, Deserialize)]
+pub struct SellQuote {
+    pub shares_in: WrappedBalance, // Shares the seller has to transfer in
+    pub fee_paid: WrappedBalance, // Swap fee taken on top of `collateral_out`
+    pub collateral_out: WrappedBalance, // Collateral the seller receives
+}
+
+/**
+ * @notice resolution progress of a market, only distinguishes unresolved markets by `end_time`
+ * Unlike `MarketState` it ignores `enabled`, `start_time` and `resolution_time`, a disabled or not yet started market before
+ * `end_time` is still `Trading`, so resolution services can find markets through `get_markets_by_state` regardless of their configuration
+ */
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub enum ResolutionState {
+    Trading, // Before `end_time` and not finalized
+    AwaitingOracle, // Past `end_time` and waiting for a resolution
+    Finalized, // Resolved with a payout numerator
+    Invalid, // Resolved as invalid, no payout numerator
+}
+
+/**
+ * @notice single lifecycle status of a market, derived from its flags, times and the block timestamp
+ * Reports whether the market can currently be interacted with, `ResolutionState` only tracks its resolution
+ */
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub enum MarketState {
+    Disabled, // Not finalized and not (yet) enabled for interaction
+    NotStarted, // Enabled but before `start_time`, trading and adding liquidity are rejected
+    Trading, // Enabled, past `start_time` and before `end_time`
+    Closed, // Past `end_time` and before `resolution_time`
+    AwaitingResolution, // Past `resolution_time` and waiting for the oracle
+    Resolved, // Finalized with a payout numerator
+    Invalid, // Finalized as invalid, no payout numerator
+}
+
+/**
+ * @notice serializable representation of a `Market` without the `Pool` internals
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MarketView {
+    pub id: U64, // Index of the market
+    pub end_time: WrappedTimestamp, // Time when trading is halted
+    pub resolution_time: WrappedTimestamp, // Time when the market can be resoluted
+    pub outcomes: u16, // Number of outcomes in the market
+    pub outcome_tags: Vec<String>, // Tags describing outcomes
+    pub collateral_token_id: AccountId, // `AccountId` of the collateral token used in the market
+    pub payout_numerator: Option<Vec<U128>>, // Payout distribution, `None` if the market is unresolved or invalid
+    pub finalized: bool, // If true the market has an outcome
+    pub enabled: bool, // If false the market is disabled for interaction
+    pub is_scalar: bool, // If true the market is scalar, false for categorical
+    pub min_lp_count_to_trade: Option<U64>, // Minimum number of distinct LPs required before trading
+    pub categories: Vec<String>, // Categories for filtering and curation
+    pub ambiguity_policy: AmbiguityPolicy, // How categorical answers matching multiple tags are handled
+    pub min_liquidity_add: WrappedBalance, // Minimum amount of collateral accepted by `add_liquidity`
+    pub lp_lockup_ms: Option<WrappedTimestamp>, // Period after an LP's latest liquidity addition during which they can't exit the pool
+    pub price_tick: Option<WrappedBalance>, // Tick size that spot prices are rounded to, `None` for continuous prices
+    pub notification_endpoint: Option<String>, // Endpoint off-chain services can notify of resolution events
+    pub max_pool_collateral: Option<WrappedBalance>, // Cap on the collateral locked in the market that `add_liquidity` can't exceed
+    pub start_time: Option<WrappedTimestamp>, // Time before which the market can't be traded or receive liquidity
+    pub cancelled: bool, // If true the market was cancelled by `gov` and finalized as invalid
+    pub pricing_mode: PricingMode, // How the pool prices outcomes
+    pub created_at: WrappedTimestamp, // Time when the market was created
+    pub fee_schedule: Option<FeeSchedule>, // Swap fee that moves from `start_fee` at creation to `end_fee` at `end_time`
+    pub min_bet: Option<WrappedBalance>, // Minimum amount of collateral a single `buy` has to spend
+    pub max_bet: Option<WrappedBalance>, // Maximum amount of collateral a single `buy` can spend
+    pub has_trader_allowlist: bool, // If true only allowlisted accounts can trade and add liquidity
+    pub proposed_outcome: Option<ProposedOutcome>, // Outcome awaiting its challenge period, if any
 }
 
 #[near_bindgen]
 impl AMMContract {
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the market's metadata and resolution state
+     */
+    pub fn get_market(&self, market_id: U64) -> MarketView {
+        let market = self.get_market_expect(market_id);
+        market.to_view()
+    }
+
+    /**
+     * @notice lists markets in order of creation
+     * @param from_index is the index of the first market to return
+     * @param limit is the maximum number of markets to return
+     * @returns a list of market views, empty if `from_index` is past the last market
+     */
+    pub fn get_markets(&self, from_index: U64, limit: U64) -> Vec<MarketView> {
+        let from_index: u64 = from_index.into();
+        let to_index = std::cmp::min(from_index.saturating_add(limit.into()), self.markets.len());
+
+        (from_index..to_index)
+            .map(|market_id| self.markets.get(market_id).expect("ERR_NO_MARKET").to_view())
+            .collect()
+    }
+
+    /**
+     * @returns a tuple of the wrapped total, finalized and invalid market counts, finalized includes invalid markets
+     */
+    pub fn get_market_counts(&self) -> (U64, U64, U64) {
+        (U64(self.markets.len()), U64(self.finalized_market_count), U64(self.invalid_market_count))
+    }
+
+    /**
+     * @notice scans markets in order of creation and returns those that are tagged with `category`
+     * @param category is the category to filter by, matching is exact and case-sensitive
+     * @param from_index is the index of the first market to scan
+     * @param limit is the maximum number of markets to scan
+     * @returns a list of wrapped market ids within the scanned range that have `category`
+     */
+    pub fn get_markets_by_category(&self, category: String, from_index: U64, limit: U64) -> Vec<U64> {
+        let from_index: u64 = from_index.into();
+        let to_index = std::cmp::min(from_index.saturating_add(limit.into()), self.markets.len());
+
+        (from_index..to_index)
+            .filter(|market_id| {
+                let market = self.markets.get(*market_id).expect("ERR_NO_MARKET");
+                market.categories.contains(&category)
+            })
+            .map(U64)
+            .collect()
+    }
+
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the current `ResolutionState` of the market
+     */
+    pub fn get_resolution_state(&self, market_id: U64) -> ResolutionState {
+        self.get_market_expect(market_id).resolution_state()
+    }
+
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the current `MarketState` of the market
+     */
+    pub fn get_market_state(&self, market_id: U64) -> MarketState {
+        self.get_market_expect(market_id).market_state()
+    }
+
+    /**
+     * @notice scans markets in order of creation and returns those that are currently in `state`
+     * @param state is the `ResolutionState` to filter by
+     * @param from_index is the index of the first market to scan
+     * @param limit is the maximum number of markets to scan
+     * @returns a list of wrapped market ids within the scanned range that are in `state`
+     */
+    pub fn get_markets_by_state(&self, state: ResolutionState, from_index: U64, limit: U64) -> Vec<U64> {
+        let from_index: u64 = from_index.into();
+        let to_index = std::cmp::min(from_index.saturating_add(limit.into()), self.markets.len());
+
+        (from_index..to_index)
+            .filter(|market_id| {
+                let market = self.markets.get(*market_id).expect("ERR_NO_MARKET");
+                market.resolution_state() == state
+            })
+            .map(U64)
+            .collect()
+    }
+
     /**
      * @param market_id is the index of the market to retrieve data from
      * @returns the fee percentage denominated in 1e4 e.g. 1 = 0.01%
@@ -41,6 +323,15 @@ impl AMMContract {
         U128(market.pool.pool_token.total_supply())
     }
 
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the number of distinct accounts that currently provide liquidity to the pool
+     */
+    pub fn get_lp_count(&self, market_id: U64) -> U64 {
+        let market = self.get_market_expect(market_id);
+        U64(market.pool.lp_count)
+    }
+
     /**
      * @param market_id is the index of the market to retrieve data from
      * @returns all of the outcome balances for a specific pool
@@ -53,6 +344,43 @@ impl AMMContract {
         market.pool.get_pool_balances().into_iter().map(|b| b.into()).collect()
     }
 
+    /**
+     * @notice combines `get_pool_balances`, `get_pool_token_total_supply`, `get_fee_pool_weight` and `get_pool_swap_fee` in a single call
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the pool's state
+     */
+    pub fn get_pool_info(&self, market_id: U64) -> PoolInfo {
+        let pool = self.get_market_expect(market_id).pool;
+        PoolInfo {
+            outcomes: pool.outcomes,
+            collateral_denomination: U128(pool.collateral_denomination),
+            pool_balances: pool.get_pool_balances().into_iter().map(U128).collect(),
+            pool_token_total_supply: U128(pool.pool_token.total_supply()),
+            fee_pool_weight: U128(pool.fee_pool_weight),
+            swap_fee: U128(pool.get_swap_fee()),
+        }
+    }
+
+    /**
+     * @notice compares the collateral a pool holds to what it owes, meant to be polled by monitoring to detect accounting drift
+     * Only holds until the market is finalized, claims clear balances without burning the outstanding shares
+     * @param market_id is the index of the market to check
+     * @returns the pool's collateral, its obligations and their difference
+     */
+    pub fn get_collateral_invariant(&self, market_id: U64) -> InvariantReport {
+        let pool = self.get_market_expect(market_id).pool;
+        let (outstanding_shares, accrued_fees, escrowed) = pool.get_collateral_obligations();
+        let obligations = outstanding_shares + accrued_fees + escrowed;
+        InvariantReport {
+            collateral_held: U128(pool.collateral_balance),
+            outstanding_shares: U128(outstanding_shares),
+            accrued_fees: U128(accrued_fees),
+            escrowed: U128(escrowed),
+            obligations: U128(obligations),
+            difference: I128(pool.collateral_balance as i128 - obligations as i128),
+        }
+    }
+
     /**
      * @param market_id is the index of the market to retrieve data from
      * @param account_id the `AccountId` to retrieve data from
@@ -71,7 +399,7 @@ impl AMMContract {
      * @notice returns the current spot price of an outcome without taking a fee into account
      * @param market_id is the index of the market to retrieve data from
      * @param outcome is the outcome to get the current spot price fpr
-     * @returns a wrapped price of the outcome at current state
+     * @returns a wrapped price of the outcome at current state, rounded to the nearest `price_tick` if the market has one
      */
     pub fn get_spot_price_sans_fee(
         &self,
@@ -79,14 +407,14 @@ impl AMMContract {
         outcome: u16
     ) -> WrappedBalance {
         let market = self.get_market_expect(market_id);
-        market.pool.get_spot_price_sans_fee(outcome).into()
+        market.round_to_tick(market.pool.get_spot_price_sans_fee(outcome)).into()
     }
 
     /**
      * @notice returns the current spot price of an outcome without taking a fee into account
      * @param market_id is the index of the market to retrieve data from
      * @param outcome is the outcome to get the current spot price fpr
-     * @returns a wrapped price of the outcome at current state
+     * @returns a wrapped price of the outcome at current state, rounded to the nearest `price_tick` if the market has one
      */
     pub fn get_spot_price(
         &self,
@@ -94,7 +422,65 @@ impl AMMContract {
         outcome: u16
     ) -> WrappedBalance {
         let market = self.get_market_expect(market_id);
-        market.pool.get_spot_price(outcome).into()
+        market.round_to_tick(market.pool.get_spot_price(outcome)).into()
+    }
+
+    /**
+     * @notice returns the total minted shares per outcome including the pool's inventory, used to verify that complete sets are backed by collateral
+     * @param market_id is the index of the market to retrieve data from
+     * @returns a list of wrapped share supplies where each index corresponds to an outcome
+     */
+    pub fn get_total_shares(&self, market_id: U64) -> Vec<WrappedBalance> {
+        let market = self.get_market_expect(market_id);
+        market.pool.get_total_shares().into_iter().map(U128).collect()
+    }
+
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the total collateral that has been traded in the market, the sum of collateral paid into buys and out of sells
+     */
+    pub fn get_market_volume(&self, market_id: U64) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.cumulative_volume)
+    }
+
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @returns the total swap fees the market has collected, including the protocol's part and fees that have since been withdrawn
+     */
+    pub fn get_total_fees_collected(&self, market_id: U64) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.total_fees_collected)
+    }
+
+    /**
+     * @notice returns the current spot price of every outcome without taking a fee into account
+     * @param market_id is the index of the market to retrieve data from
+     * @returns a list of wrapped prices where each index corresponds to an outcome, rounded like `get_spot_price_sans_fee`
+     */
+    pub fn get_all_spot_prices_sans_fee(
+        &self,
+        market_id: U64
+    ) -> Vec<WrappedBalance> {
+        let market = self.get_market_expect(market_id);
+        (0..market.pool.outcomes)
+            .map(|outcome| market.round_to_tick(market.pool.get_spot_price_sans_fee(outcome)).into())
+            .collect()
+    }
+
+    /**
+     * @notice returns the current spot price of every outcome
+     * @param market_id is the index of the market to retrieve data from
+     * @returns a list of wrapped prices where each index corresponds to an outcome, rounded like `get_spot_price`
+     */
+    pub fn get_all_spot_prices(
+        &self,
+        market_id: U64
+    ) -> Vec<WrappedBalance> {
+        let market = self.get_market_expect(market_id);
+        (0..market.pool.outcomes)
+            .map(|outcome| market.round_to_tick(market.pool.get_spot_price(outcome)).into())
+            .collect()
     }
 
     /**
@@ -114,6 +500,156 @@ impl AMMContract {
         U128(market.pool.calc_buy_amount(collateral_in.into(), outcome_target))
     }
 
+    /**
+     * @notice calculates a buy with and without the swap fee so the fee can be shown explicitly
+     * @param market_id is the index of the market to retrieve data from
+     * @param collateral_in is the amount of collateral to be used to calculate amount of shares out
+     * @param outcome_target is the outcome that is to be purchased
+     * @returns a tuple of wrapped (shares out with fee, shares out without fee, fee paid in collateral)
+     */
+    pub fn calc_buy_amount_detailed(
+        &self,
+        market_id: U64,
+        collateral_in: WrappedBalance,
+        outcome_target: u16
+    ) -> (WrappedBalance, WrappedBalance, WrappedBalance) {
+        let market = self.get_market_expect(market_id);
+        let (shares_out, shares_out_sans_fee, fee) = market.pool.calc_buy_amount_detailed(collateral_in.into(), outcome_target);
+        (U128(shares_out), U128(shares_out_sans_fee), U128(fee))
+    }
+
+    /**
+     * @notice calculates the shares out for several buy sizes against the current pool state, e.g. to draw a depth chart
+     * @param market_id is the index of the market to retrieve data from
+     * @param outcome_target is the outcome that is to be purchased
+     * @param amounts the amounts of collateral to calculate the shares out for, at most `MAX_CURVE_SAMPLES`
+     * @returns the wrapped shares out for every amount, in the same order as `amounts`
+     */
+    pub fn sample_buy_curve(
+        &self,
+        market_id: U64,
+        outcome_target: u16,
+        amounts: Vec<WrappedBalance>
+    ) -> Vec<WrappedBalance> {
+        assert!(amounts.len() <= constants::MAX_CURVE_SAMPLES, "ERR_TOO_MANY_SAMPLES");
+        let market = self.get_market_expect(market_id);
+        amounts
+            .into_iter()
+            .map(|amount| U128(market.pool.calc_buy_amount(amount.into(), outcome_target)))
+            .collect()
+    }
+
+    /**
+     * @notice calculates a buy and splits `collateral_in` into the swap fee and the collateral that's used to buy shares, the fee is computed as in `buy`
+     * @param market_id is the index of the market to retrieve data from
+     * @param collateral_in is the amount of collateral to be used to calculate amount of shares out
+     * @param outcome_target is the outcome that is to be purchased
+     * @returns a `BuyQuote` where `fee_paid + collateral_after_fee == collateral_in`
+     */
+    pub fn calc_buy_amount_with_fee(
+        &self,
+        market_id: U64,
+        collateral_in: WrappedBalance,
+        outcome_target: u16
+    ) -> BuyQuote {
+        let market = self.get_market_expect(market_id);
+        let collateral_in: u128 = collateral_in.into();
+        let fee_paid = market.pool.calc_swap_fee(collateral_in);
+        BuyQuote {
+            shares_out: U128(market.pool.calc_buy_amount(collateral_in, outcome_target)),
+            fee_paid: U128(fee_paid),
+            collateral_after_fee: U128(collateral_in - fee_paid),
+        }
+    }
+
+    /**
+     * @notice calculates the amount of collateral a user has to put in to buy exactly `shares_out`, the inverse of `calc_buy_amount`
+     * @param market_id is the index of the market to retrieve data from
+     * @param shares_out is the amount of `outcome_shares` the user wants to buy
+     * @param outcome_target is the outcome that is to be purchased
+     * @returns a wrapped amount of collateral, including fees, required to buy `shares_out`
+     */
+    pub fn calc_buy_collateral_in(
+        &self,
+        market_id: U64,
+        shares_out: WrappedBalance,
+        outcome_target: u16
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.calc_buy_collateral_in(shares_out.into(), outcome_target))
+    }
+
+    /**
+     * @notice calculates the realistic cost of a position: the collateral lost to fees and price impact when buying and immediately selling all shares back
+     * @param market_id is the index of the market to retrieve data from
+     * @param collateral_in is the amount of collateral the position is bought with
+     * @param outcome_target is the outcome that is bought and sold
+     * @returns a wrapped amount of collateral lost over the round trip
+     */
+    pub fn calc_round_trip_cost(
+        &self,
+        market_id: U64,
+        collateral_in: WrappedBalance,
+        outcome_target: u16
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.calc_round_trip_cost(collateral_in.into(), outcome_target))
+    }
+
+    /**
+     * @notice calculates the average price per share a user pays when buying with `collateral_in`, as opposed to the spot price this takes the trade size into account
+     * @param market_id is the index of the market to retrieve data from
+     * @param collateral_in is the amount of collateral to be used to calculate the average price
+     * @param outcome_target is the outcome that is to be purchased
+     * @returns a wrapped average execution price denominated in the collateral token
+     * @panics with `ERR_NO_SHARES_OUT` if `collateral_in` is too small to buy any shares
+     */
+    pub fn get_avg_execution_price(
+        &self,
+        market_id: U64,
+        collateral_in: WrappedBalance,
+        outcome_target: u16
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        let collateral_in: u128 = collateral_in.into();
+        let shares_out = market.pool.calc_buy_amount(collateral_in, outcome_target);
+        assert!(shares_out > 0, "ERR_NO_SHARES_OUT");
+        U128(math::complex_div_u128(market.pool.collateral_denomination, collateral_in, shares_out))
+    }
+
+    /**
+     * @notice calculates how far a buy moves away from the current price, for slippage warnings
+     * @param market_id is the index of the market to retrieve data from
+     * @param collateral_in is the amount of collateral the user would buy with
+     * @param outcome_target is the outcome that is to be purchased
+     * @returns a `PriceImpact` containing the pre-trade spot price, the effective price and their difference in basis points
+     * @panics with `ERR_NO_LIQUIDITY` for unseeded pools, `ERR_ZERO_SPOT_PRICE` if the spot price rounds to 0
+     *      and `ERR_NO_SHARES_OUT` if `collateral_in` is too small to buy any shares
+     */
+    pub fn calc_buy_price_impact(
+        &self,
+        market_id: U64,
+        collateral_in: WrappedBalance,
+        outcome_target: u16
+    ) -> PriceImpact {
+        let market = self.get_market_expect(market_id);
+        let collateral_in: u128 = collateral_in.into();
+        market.pool.assert_has_liquidity();
+        let spot_price = market.pool.get_spot_price(outcome_target);
+        // The impact is relative to the spot price, low decimal collateral or skewed pools can round it to 0
+        assert!(spot_price > 0, "ERR_ZERO_SPOT_PRICE");
+        let shares_out = market.pool.calc_buy_amount(collateral_in, outcome_target);
+        assert!(shares_out > 0, "ERR_NO_SHARES_OUT");
+        let effective_price = math::complex_div_u128(market.pool.collateral_denomination, collateral_in, shares_out);
+        let impact_bps = effective_price.saturating_sub(spot_price) * 10_000 / spot_price;
+
+        PriceImpact {
+            spot_price: U128(spot_price),
+            effective_price: U128(effective_price),
+            impact_bps: U64(impact_bps as u64),
+        }
+    }
+
     /**
      * @notice calculates the amount of shares a user has to put in in order to get `collateral_out`
      * @param market_id is the index of the market to retrieve data from
@@ -132,90 +668,332 @@ impl AMMContract {
     }
 
     /**
-     * @param account_id is the `AccountId` to retrieve the `outcome_shares` for
+     * @notice calculates a sell together with the swap fee that's taken on top of `collateral_out`, the fee is computed as in `sell`
      * @param market_id is the index of the market to retrieve data from
-     * @param outcome is the `outcome_shares` to get the balance from
-     * @returns wrapped balance of `outcome_shares`
+     * @param collateral_out is the amount of collateral that a user wants to get out of a position
+     * @param outcome_target is the outcome that the amount of shares a user wants to sell
+     * @returns a `SellQuote` with the shares to transfer in and the fee paid
      */
-    pub fn get_share_balance(
-        &self, 
-        account_id: &AccountId, 
-        market_id: U64, 
-        outcome: u16
-    ) -> WrappedBalance {
+    pub fn calc_sell_collateral_out_with_fee(
+        &self,
+        market_id: U64,
+        collateral_out: WrappedBalance,
+        outcome_target: u16
+    ) -> SellQuote {
         let market = self.get_market_expect(market_id);
-        U128(market.pool.get_share_balance(account_id, outcome))
+        let collateral_out: u128 = collateral_out.into();
+        SellQuote {
+            shares_in: U128(market.pool.calc_sell_collateral_out(collateral_out, outcome_target)),
+            fee_paid: U128(market.pool.calc_swap_fee(collateral_out)),
+            collateral_out: U128(collateral_out),
+        }
     }
 
     /**
+     * @notice calculates the minimum amount of LP tokens a provider should accept for `total_in`, useful as a slippage guard
      * @param market_id is the index of the market to retrieve data from
-     * @param account_id is the account id to retrieve the accrued fees for
-     * @returns wrapped amount of fees withdrawable for `account_id`
+     * @param total_in is the amount of collateral that's to be added as liquidity
+     * @param weight_indication token weights, only expected if the pool hasn't been seeded yet
+     * @param slippage_bps is the tolerated slippage denominated in 1e4 e.g. 100 = 1%
+     * @returns a wrapped amount of LP tokens that would be minted minus the slippage tolerance
      */
-    pub fn get_fees_withdrawable(
-        &self, 
-        market_id: U64, 
-        account_id: &AccountId
+    pub fn calc_min_lp_out(
+        &self,
+        market_id: U64,
+        total_in: WrappedBalance,
+        weight_indication: Option<Vec<U128>>,
+        slippage_bps: u16
     ) -> WrappedBalance {
+        assert!(slippage_bps <= 10_000, "ERR_INVALID_SLIPPAGE");
         let market = self.get_market_expect(market_id);
-        U128(market.pool.get_fees_withdrawable(account_id))
+        let weights_u128: Option<Vec<u128>> = weight_indication.map(|weights| {
+            weights.iter().map(|weight| u128::from(*weight)).collect()
+        });
+
+        let (to_mint, _) = market.pool.calc_add_liquidity(total_in.into(), &weights_u128);
+        let tolerance = math::simple_mul_u128(10_000, to_mint, u128::from(slippage_bps));
+        U128(to_mint - tolerance)
     }
 
     /**
-     * @notice sell `outcome_shares` for collateral
-     * @param market_id references the market to sell shares from 
-     * @param collateral_out is the amount of collateral that is expected to be transferred to the sender after selling
-     * @param outcome_target is which `outcome_share` to sell
-     * @param max_shares_in is the maximum amount of `outcome_shares` to transfer in, in return for `collateral_out` this is prevent sandwich attacks and unwanted `slippage`
-     * @returns a promise referencing the collateral token transaction
+     * @notice previews the result of `add_liquidity` without mutating state
+     * @param market_id is the index of the market to retrieve data from
+     * @param total_in is the amount of collateral that's to be added as liquidity
+     * @param weight_indication token weights, only expected if the pool hasn't been seeded yet
+     * @returns the LP tokens that would be minted and the outcome shares that would be credited to the provider
      */
-    #[payable]
-    pub fn sell(
-        &mut self,
+    pub fn calc_add_liquidity_preview(
+        &self,
         market_id: U64,
-        collateral_out: WrappedBalance,
-        outcome_target: u16,
-        max_shares_in: WrappedBalance
-    ) -> Promise {
-        self.assert_unpaused();
-        let initial_storage = env::storage_usage();
-        let collateral_out: u128 = collateral_out.into();
-        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
-        assert!(market.enabled, "ERR_DISABLED_MARKET");
-        assert!(!market.finalized, "ERR_FINALIZED_MARKET");
-        assert!(market.end_time > ns_to_ms(env::block_timestamp()), "ERR_MARKET_ENDED");
-        let escrowed = market.pool.sell(
-            &env::predecessor_account_id(),
-            collateral_out,
-            outcome_target,
-            max_shares_in.into()
-        );
-
-        self.markets.replace(market_id.into(), &market);
-        helper::refund_storage(initial_storage, env::predecessor_account_id());
+        total_in: WrappedBalance,
+        weight_indication: Option<Vec<U128>>
+    ) -> AddLiquidityPreview {
+        let market = self.get_market_expect(market_id);
+        let weights_u128: Option<Vec<u128>> = weight_indication.map(|weights| {
+            weights.iter().map(|weight| u128::from(*weight)).collect()
+        });
 
-        collateral_token::ft_transfer(
-            env::predecessor_account_id(), 
-            U128(collateral_out - escrowed),
-            None,
-            &market.pool.collateral_token_id,
-            1,
-            GAS_BASE_COMPUTE
-        )
+        let (to_mint, outcome_shares) = market.pool.calc_add_liquidity(total_in.into(), &weights_u128);
+        AddLiquidityPreview {
+            lp_tokens: U128(to_mint),
+            outcome_shares: outcome_shares.into_iter().map(U128).collect(),
+        }
     }
 
     /**
-     * @notice Allows senders who hold tokens in all outcomes to redeem the lowest common denominator of shares for an equal amount of collateral
-     * @param market_id references the market to redeem
-     * @param total_in is the amount outcome tokens to redeem
-     * @returns a transfer `Promise` or a boolean representing a collateral transfer
+     * @notice previews the result of `exit_pool` without mutating state
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id is the LP that's exiting
+     * @param total_in is the amount of LP tokens to redeem
+     * @returns the fees, collateral and outcome shares that would be transferred to the LP
      */
-    #[payable]
-    pub fn burn_outcome_tokens_redeem_collateral(
+    pub fn calc_exit_pool_preview(
+        &self,
+        market_id: U64,
+        account_id: &AccountId,
+        total_in: WrappedBalance
+    ) -> ExitPoolPreview {
+        let market = self.get_market_expect(market_id);
+        let (fees, outcome_shares) = market.pool.calc_exit_pool(account_id, total_in.into());
+
+        ExitPoolPreview {
+            fees: U128(fees),
+            collateral: U128(fees),
+            outcome_shares: outcome_shares.into_iter().map(U128).collect(),
+        }
+    }
+
+    /**
+     * @notice calculates the amount `claim_earnings` would pay out to `account_id` without transferring anything
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id is the account to calculate the claimable amount for
+     * @returns wrapped amount of collateral claimable, 0 if the market isn't finalized or there's nothing to claim
+     */
+    pub fn get_claimable(
+        &self,
+        market_id: U64,
+        account_id: &AccountId
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        if !market.enabled || !market.finalized {
+            return U128(0);
+        }
+
+        U128(market.pool.calc_payout(account_id, &market.payout_numerator))
+    }
+
+    /**
+     * @notice values an LP position at current spot prices
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id is the LP to value the position of
+     * @returns wrapped collateral value of `account_id`'s share of the pool plus their withdrawable fees
+     */
+    pub fn get_pool_token_value(
+        &self,
+        market_id: U64,
+        account_id: &AccountId
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.get_pool_token_value(account_id))
+    }
+
+    /**
+     * @notice calculates the resolution price per outcome at which an LP breaks even versus having held their collateral
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id is the LP to calculate the break-even prices for
+     * @returns a list of wrapped prices where each index corresponds to an outcome, `u128::MAX` if the LP holds no shares of that outcome
+     */
+    pub fn calc_lp_breakeven(
+        &self,
+        market_id: U64,
+        account_id: &AccountId
+    ) -> Vec<WrappedBalance> {
+        let market = self.get_market_expect(market_id);
+        market.pool.calc_lp_breakeven(account_id).into_iter().map(U128).collect()
+    }
+
+    /**
+     * @notice recommends an amount of prepaid gas for common operations on a market, including cross-contract overhead
+     * this is a rough estimate with headroom, not a measured cost and not a guarantee that the call will succeed
+     * @param operation one of `buy`, `sell`, `add_liquidity`, `exit_pool`, `redeem` or `claim_earnings`
+     * @param market_id is the index of the market the operation will be executed on
+     * @returns wrapped amount of gas to attach
+     */
+    pub fn estimate_operation_gas(
+        &self,
+        operation: String,
+        market_id: U64
+    ) -> U64 {
+        let market = self.get_market_expect(market_id);
+        let outcome_gas = constants::GAS_ESTIMATE_PER_OUTCOME * u64::from(market.pool.outcomes);
+
+        let gas = match operation.as_str() {
+            // Entered through `ft_transfer_call` on the collateral token
+            "buy" => constants::GAS_ESTIMATE_BUY + constants::GAS_ESTIMATE_FT_TRANSFER_CALL,
+            "add_liquidity" => constants::GAS_ESTIMATE_ADD_LIQUIDITY + constants::GAS_ESTIMATE_FT_TRANSFER_CALL,
+            // Called directly, pays out collateral through `ft_transfer`
+            "sell" => constants::GAS_ESTIMATE_SELL + constants::GAS_ESTIMATE_PAYOUT_TRANSFER,
+            "exit_pool" => constants::GAS_ESTIMATE_EXIT_POOL + constants::GAS_ESTIMATE_PAYOUT_TRANSFER,
+            "redeem" => constants::GAS_ESTIMATE_REDEEM + constants::GAS_ESTIMATE_PAYOUT_TRANSFER,
+            "claim_earnings" => constants::GAS_ESTIMATE_CLAIM + constants::GAS_ESTIMATE_PAYOUT_TRANSFER,
+            _ => panic!("ERR_UNKNOWN_OPERATION")
+        };
+
+        U64(gas + outcome_gas)
+    }
+
+    /**
+     * @param account_id is the `AccountId` to retrieve the `outcome_shares` for
+     * @param market_id is the index of the market to retrieve data from
+     * @param outcome is the `outcome_shares` to get the balance from
+     * @returns wrapped balance of `outcome_shares`
+     */
+    pub fn get_share_balance(
+        &self, 
+        account_id: &AccountId, 
+        market_id: U64, 
+        outcome: u16
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.get_share_balance(account_id, outcome))
+    }
+
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id is the account id to retrieve the accrued fees for
+     * @returns wrapped amount of fees withdrawable for `account_id`
+     */
+    pub fn get_fees_withdrawable(
+        &self, 
+        market_id: U64, 
+        account_id: &AccountId
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.get_fees_withdrawable(account_id))
+    }
+
+    /**
+     * @notice combines an account's outcome share balances, LP token balance and withdrawable fees in a single call
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id the `AccountId` to retrieve data from
+     * @returns the account's position in the market, zeroed if it has none
+     */
+    pub fn get_user_market_position(
+        &self,
+        market_id: U64,
+        account_id: &AccountId
+    ) -> UserPosition {
+        let pool = self.get_market_expect(market_id).pool;
+        UserPosition {
+            share_balances: (0..pool.outcomes).map(|outcome| U128(pool.get_share_balance(account_id, outcome))).collect(),
+            pool_token_balance: U128(pool.get_pool_token_balance(account_id)),
+            fees_withdrawable: U128(pool.get_fees_withdrawable(account_id)),
+        }
+    }
+
+    /**
+     * @notice sums the fees withdrawable by `account_id` over a range of markets denominated in `token_id`
+     * @param account_id is the account id to retrieve the accrued fees for
+     * @param token_id is the collateral token to sum fees for
+     * @param from_index is the index of the first market to scan
+     * @param limit is the maximum number of markets to scan
+     * @returns wrapped amount of fees withdrawable for `account_id` within the scanned range
+     */
+    pub fn get_total_withdrawable_fees(
+        &self,
+        account_id: &AccountId,
+        token_id: AccountId,
+        from_index: U64,
+        limit: U64
+    ) -> WrappedBalance {
+        let from_index: u64 = from_index.into();
+        let to_index = std::cmp::min(from_index.saturating_add(limit.into()), self.markets.len());
+
+        let total = (from_index..to_index)
+            .map(|market_id| self.markets.get(market_id).expect("ERR_NO_MARKET"))
+            .filter(|market| market.pool.collateral_token_id == token_id)
+            .fold(0, |total, market| total + market.pool.get_fees_withdrawable(account_id));
+
+        U128(total)
+    }
+
+    /**
+     * @notice sell `outcome_shares` for collateral, allowed after `end_time` up until the market is finalized
+     * @param market_id references the market to sell shares from 
+     * @param collateral_out is the amount of collateral that is expected to be transferred to the sender after selling
+     * @param outcome_target is which `outcome_share` to sell
+     * @param max_shares_in is the maximum amount of `outcome_shares` to transfer in, in return for `collateral_out` this is prevent sandwich attacks and unwanted `slippage`
+     * @param deadline optional timestamp in ms after which the sell should no longer execute
+     * @returns a promise referencing the collateral token transaction
+     */
+    #[payable]
+    pub fn sell(
+        &mut self,
+        market_id: U64,
+        collateral_out: WrappedBalance,
+        outcome_target: u16,
+        max_shares_in: WrappedBalance,
+        deadline: Option<WrappedTimestamp>
+    ) -> Promise {
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_unpaused();
+        assert_deadline(deadline);
+        self.lock_account_market(&env::predecessor_account_id(), market_id.into());
+        let initial_storage = env::storage_usage();
+        let collateral_out: u128 = collateral_out.into();
+        let mut market = self.get_market_expect(market_id);
+        assert!(market.enabled, "ERR_DISABLED_MARKET");
+        assert!(!market.finalized, "ERR_FINALIZED_MARKET");
+        market.assert_started();
+        market.assert_min_lp_count();
+        market.assert_allowlisted(&env::predecessor_account_id());
+        let (escrowed, protocol_fee) = market.pool.sell(
+            &env::predecessor_account_id(),
+            collateral_out,
+            outcome_target,
+            max_shares_in.into(),
+            self.protocol_fee_fraction
+        );
+
+        self.accrue_treasury(&market.pool.collateral_token_id, protocol_fee);
+        self.markets.replace(market_id.into(), &market);
+        self.refund_storage(initial_storage, env::predecessor_account_id());
+
+        self.transfer_payout_and_unlock(env::predecessor_account_id(), market_id.into(), collateral_out - escrowed, &market.pool.collateral_token_id)
+    }
+
+    /**
+     * @notice previews `burn_outcome_tokens_redeem_collateral` for `account_id`
+     * @param market_id references the market to redeem
+     * @param to_burn is the amount of each outcome token to burn
+     * @param account_id the `AccountId` that would redeem
+     * @returns the wrapped amount of collateral that would be transferred, 0 if `account_id` holds less than `to_burn` of any outcome
+     */
+    pub fn calc_redeem_collateral(
+        &self,
+        market_id: U64,
+        to_burn: WrappedBalance,
+        account_id: &AccountId
+    ) -> WrappedBalance {
+        let market = self.get_market_expect(market_id);
+        U128(market.pool.calc_redeem_collateral(account_id, to_burn.into()))
+    }
+
+    /**
+     * @notice Allows senders who hold tokens in all outcomes to redeem the lowest common denominator of shares for an equal amount of collateral
+     * @param market_id references the market to redeem
+     * @param total_in is the amount outcome tokens to redeem
+     * @param min_collateral_out optional minimum amount of collateral to receive, guards against escrow changes before execution
+     * @returns a transfer `Promise` or a boolean representing a collateral transfer
+     */
+    #[payable]
+    pub fn burn_outcome_tokens_redeem_collateral(
         &mut self,
         market_id: U64,
-        to_burn: WrappedBalance
+        to_burn: WrappedBalance,
+        min_collateral_out: Option<WrappedBalance>
     ) -> Promise {
+        self.assert_not_blacklisted(&env::predecessor_account_id());
         self.assert_unpaused();
         let initial_storage = env::storage_usage();
 
@@ -230,20 +1008,16 @@ impl AMMContract {
 
         self.markets.replace(market_id.into(), &market);
 
-        helper::refund_storage(initial_storage, env::predecessor_account_id());
+        self.refund_storage(initial_storage, env::predecessor_account_id());
 
         let payout = u128::from(to_burn) - escrowed;
+        if let Some(min_collateral_out) = min_collateral_out {
+            assert!(payout >= u128::from(min_collateral_out), "ERR_MIN_COLLATERAL_OUT");
+        }
 
         logger::log_transaction(&logger::TransactionType::Redeem, &env::predecessor_account_id(), to_burn.into(), payout, market_id, None);
 
-        collateral_token::ft_transfer(
-            env::predecessor_account_id(),
-            payout.into(),
-            None,
-            &market.pool.collateral_token_id,
-            1,
-            GAS_BASE_COMPUTE
-        )
+        self.transfer_payout(env::predecessor_account_id(), payout, &market.pool.collateral_token_id)
     }
 
     /**
@@ -258,11 +1032,14 @@ impl AMMContract {
         market_id: U64,
         total_in: WrappedBalance,
     ) -> PromiseOrValue<bool> {
-        self.assert_unpaused();
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_withdrawals_allowed();
+        self.lock_account_market(&env::predecessor_account_id(), market_id.into());
         let initial_storage = env::storage_usage();
 
         let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
         assert!(market.enabled, "ERR_DISABLED_MARKET");
+        market.assert_lp_unlocked(&env::predecessor_account_id());
 
         let fees_earned = market.pool.exit_pool(
             &env::predecessor_account_id(),
@@ -271,30 +1048,147 @@ impl AMMContract {
         
         self.markets.replace(market_id.into(), &market);
 
-        helper::refund_storage(initial_storage, env::predecessor_account_id());
+        self.refund_storage(initial_storage, env::predecessor_account_id());
 
         if fees_earned > 0 {
             PromiseOrValue::Promise(
-                collateral_token::ft_transfer(
-                    env::predecessor_account_id(), 
-                    fees_earned.into(),
-                    None,
-                    &market.pool.collateral_token_id,
-                    1,
-                    GAS_BASE_COMPUTE
-                )
+                self.transfer_payout_and_unlock(env::predecessor_account_id(), market_id.into(), fees_earned, &market.pool.collateral_token_id)
+            )
+        } else {
+            self.unlock_account_market(&env::predecessor_account_id(), market_id.into());
+            PromiseOrValue::Value(true)
+        }
+    }
+
+    /**
+     * @notice transfers outcome shares of a market to another account
+     * @param market_id references the market whose shares to transfer
+     * @param outcome the outcome of the shares to transfer
+     * @param receiver_id the account that receives the shares
+     * @param amount the amount of shares to transfer
+     */
+    #[payable]
+    pub fn outcome_token_transfer(
+        &mut self,
+        market_id: U64,
+        outcome: u16,
+        receiver_id: ValidAccountId,
+        amount: WrappedBalance
+    ) {
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_unpaused();
+        let initial_storage = env::storage_usage();
+
+        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        assert!(market.enabled, "ERR_DISABLED_MARKET");
+        assert!(outcome < market.pool.outcomes, "ERR_INVALID_OUTCOME");
+
+        market.pool.transfer_outcome_tokens(&env::predecessor_account_id(), receiver_id.as_ref(), outcome, amount.into());
+        self.markets.replace(market_id.into(), &market);
+
+        self.refund_storage(initial_storage, env::predecessor_account_id());
+    }
+
+    /**
+     * @notice transfers LP tokens of a market to another account, fees accrued by the sender up to the transfer are paid out to the sender
+     * @param market_id references the market whose LP tokens to transfer
+     * @param receiver_id the account that receives the LP tokens
+     * @param amount the amount of LP tokens to transfer
+     * @param memo optional memo, logged with the transfer
+     * @returns a transfer `Promise` of the sender's fees or a boolean representing a successful transfer
+     */
+    #[payable]
+    pub fn pool_token_transfer(
+        &mut self,
+        market_id: U64,
+        receiver_id: ValidAccountId,
+        amount: WrappedBalance,
+        memo: Option<String>
+    ) -> PromiseOrValue<bool> {
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_unpaused();
+        let initial_storage = env::storage_usage();
+        let sender = env::predecessor_account_id();
+
+        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        assert!(market.enabled, "ERR_DISABLED_MARKET");
+        market.assert_lp_unlocked(&sender);
+
+        let fees_earned = market.pool.transfer_pool_tokens(&sender, receiver_id.as_ref(), amount.into());
+        self.markets.replace(market_id.into(), &market);
+
+        if let Some(memo) = memo {
+            env::log(format!("Memo: {}", memo).as_bytes());
+        }
+
+        self.refund_storage(initial_storage, sender.to_string());
+
+        if fees_earned > 0 {
+            PromiseOrValue::Promise(
+                self.transfer_payout(sender, fees_earned, &market.pool.collateral_token_id)
             )
         } else {
             PromiseOrValue::Value(true)
         }
     }
 
+    /**
+     * @notice withdraws the fees accrued by the sender's LP position without exiting the pool
+     * @param market_id references the market to withdraw fees from
+     * @returns a transfer `Promise` of the withdrawn fees
+     */
+    #[payable]
+    pub fn withdraw_fees(
+        &mut self,
+        market_id: U64
+    ) -> Promise {
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_unpaused();
+        let initial_storage = env::storage_usage();
+
+        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        assert!(market.enabled, "ERR_DISABLED_MARKET");
+
+        let fees = market.pool.withdraw_fees(&env::predecessor_account_id());
+        assert!(fees > 0, "ERR_NO_FEES");
+
+        self.markets.replace(market_id.into(), &market);
+
+        self.refund_storage(initial_storage, env::predecessor_account_id());
+
+        self.transfer_payout(env::predecessor_account_id(), fees, &market.pool.collateral_token_id)
+    }
+
+    /**
+     * @notice resolves a market as invalid when the oracle hasn't answered within `oracle_timeout_ms` after `resolution_time`, callable by anyone
+     * Invalid markets refund traders through `claim_earnings`
+     * @param market_id references the market to invalidate
+     */
+    pub fn force_invalid(&mut self, market_id: U64) {
+        self.assert_unpaused();
+        self.assert_resolution_not_halted();
+        let oracle_timeout_ms = self.oracle_timeout_ms.expect("ERR_NO_ORACLE_TIMEOUT");
+        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        assert!(market.enabled, "ERR_DISABLED_MARKET");
+        assert!(!market.finalized, "ERR_IS_FINALIZED");
+        assert!(ns_to_ms(env::block_timestamp()) >= market.resolution_time + oracle_timeout_ms, "ERR_ORACLE_TIMEOUT_NOT_REACHED");
+        // The oracle did answer, the proposal is finalized through `finalize_after_challenge` or `resolute_market`
+        assert!(market.proposed_outcome.is_none(), "ERR_OUTCOME_PROPOSED");
+
+        market.payout_numerator = None;
+        market.finalized = true;
+        self.record_finalization(&market);
+        self.markets.replace(market_id.into(), &market);
+        logger::log_market_status(&market);
+    }
+
     /**
      * @notice sets the resolution and finalizes a market
      * @param market_id references the market to resolute 
      * @param payout_numerator optional list of numeric values that represent the relative payout value for owners of matching outcome shares
      *      share denomination with collateral token. E.g. Collateral token denomination is 1e18 means that if payout_numerators are [5e17, 5e17] 
      *      it's a 50/50 split if the payout_numerator is None it means that the market is invalid
+     * Markets with an unchallenged proposed outcome can only be resoluted once its challenge period is over
      */
     #[payable]
     pub fn resolute_market(
@@ -303,72 +1197,102 @@ impl AMMContract {
         payout_numerator: Option<Vec<U128>>
     ) {
         self.assert_gov();
+        self.assert_resolution_not_halted();
         // let initial_storage = env::storage_usage();
         let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        assert!(ns_to_ms(env::block_timestamp()) >= market.resolution_time, "ERR_RESOLUTION_TIME_NOT_REACHED");
         assert!(market.enabled, "ERR_DISABLED_MARKET");
         assert!(!market.finalized, "ERR_IS_FINALIZED");
-        match &payout_numerator {
-            Some(v) => {
-                let sum = v.iter().fold(0, |s, &n| s + u128::from(n));
-                assert_eq!(sum, market.pool.collateral_denomination, "ERR_INVALID_PAYOUT_SUM");
-                assert_eq!(v.len(), market.pool.outcomes as usize, "ERR_INVALID_NUMERATOR");
-            },
-            None => ()
-        };
+        market.assert_valid_payout_numerator(&payout_numerator);
+        // An unchallenged proposal can still be challenged, gov can't cut its challenge period short
+        if let Some(proposed_outcome) = &market.proposed_outcome {
+            let challenge_end = market.challenge_end().unwrap();
+            assert!(
+                proposed_outcome.challenge.is_some() || ns_to_ms(env::block_timestamp()) >= challenge_end,
+                "ERR_CHALLENGE_PERIOD_ACTIVE"
+            );
+        }
 
         market.payout_numerator = payout_numerator;
         market.finalized = true;
+        self.record_finalization(&market);
+        self.settle_challenge(&market);
         self.markets.replace(market_id.into(), &market);
         // helper::refund_storage(initial_storage, env::predecessor_account_id());
 
         logger::log_market_status(&market);
     }
 
+    /**
+     * @notice corrects the resolution of an already finalized market, e.g. after an erroneous oracle answer, only callable by `gov`
+     * Earnings that have already been claimed can't be clawed back, so overrides are rejected once any account claimed
+     * @param market_id references the finalized market to correct
+     * @param payout_numerator the corrected payout numerator, same format as `resolute_market`, `None` makes the market invalid
+     */
+    pub fn gov_override_outcome(
+        &mut self,
+        market_id: U64,
+        payout_numerator: Option<Vec<U128>>
+    ) {
+        self.assert_gov();
+        self.assert_resolution_not_halted();
+        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        assert!(market.finalized, "ERR_NOT_FINALIZED");
+        assert!(!market.claims_started, "ERR_CLAIMS_STARTED");
+        market.assert_valid_payout_numerator(&payout_numerator);
+
+        // Keep the invalid market counter in sync when the override changes the market's validity
+        match (market.payout_numerator.is_none(), payout_numerator.is_none()) {
+            (false, true) => self.invalid_market_count += 1,
+            (true, false) => self.invalid_market_count -= 1,
+            _ => ()
+        }
+
+        let previous_payout_numerator = market.payout_numerator.take();
+        market.payout_numerator = payout_numerator;
+        self.markets.replace(market_id.into(), &market);
+
+        logger::log_market_outcome_overridden(&market, &previous_payout_numerator);
+    }
+
     pub fn set_outcome(&mut self, requestor: AccountId, outcome: Outcome, tags: Option<Vec<U64>>) {
         self.assert_oracle();
+        self.assert_no_oracle_quorum();
+        self.assert_resolution_not_halted();
         assert_eq!(requestor, env::current_account_id(), "ERR_WRONG_REQUESTOR");
 
         // First item in the tag is our market id as defined in market_creation.rs
-        let parsed_tags = tags.unwrap();
-        let market_id = parsed_tags.get(0).unwrap();
-        let mut market = self.get_market_expect(*market_id);
-
-        match outcome {
-            Outcome::Answer(answer) => {
-                if market.is_scalar {
-                    // f64 due the uncertainty of the pointer value/bounds
-                    // It could be a decimal value or it could be an int/uint. 
-                    // f64 can handle both for now
-                    let lower_bound: f64 = market.outcome_tags.get(0).unwrap().parse().unwrap();
-                    let upper_bound: f64 = market.outcome_tags.get(1).unwrap().parse().unwrap();
-
-                    let pointer_value = clamp_f64(answer.parse().unwrap(), lower_bound, upper_bound);
-                    let range = upper_bound - lower_bound;
-                    let percentage_upper_bound = (upper_bound - pointer_value) / range;
-
-                    // Convert to string and back to u128 due to conversion errors
-                    let payout_short_str = (percentage_upper_bound * market.pool.collateral_denomination as f64).round().to_string();
-                    let payout_short: u128 = payout_short_str.parse().unwrap();
-
-                    market.payout_numerator = Some(vec![
-                        U128(payout_short),
-                        U128(market.pool.collateral_denomination - payout_short),
-                    ]);
-                } else {
-                    // Categorical market where only 1 outcome can be the winner
-                    let index = market.outcome_tags.iter().position(|tag| tag == &answer).expect("ERR_OUTCOME_NOT_IN_TAGS");
-                    let mut payout_numerator = vec![U128(0); market.outcome_tags.len()];
+        let parsed_tags = tags.expect("ERR_NO_MARKET_TAG");
+        let market_id = parsed_tags.get(0).expect("ERR_NO_MARKET_TAG");
 
-                    payout_numerator[index] = U128(market.pool.collateral_denomination);
-                    market.payout_numerator = Some(payout_numerator);
-                }
-            },
-            Outcome::Invalid => market.payout_numerator = None,
+        if let Err(err) = self.internal_set_outcome(*market_id, &outcome) {
+            panic!("{}", err);
         }
+    }
 
-        market.finalized = true;
-        self.markets.replace(market_id.0, &market);
-        logger::log_market_status(&market);
+    /**
+     * @notice sets the outcome for multiple markets at once, an entry that fails is skipped instead of aborting the batch
+     * @param outcomes list of (tags, outcome) pairs where the first tag is the market id, same as `set_outcome`
+     * @returns a report for every entry in the same order as `outcomes`
+     */
+    pub fn set_outcomes_batch(&mut self, outcomes: Vec<(Vec<U64>, Outcome)>) -> Vec<SetOutcomeReport> {
+        self.assert_oracle();
+        self.assert_no_oracle_quorum();
+        self.assert_resolution_not_halted();
+
+        outcomes.iter().map(|(tags, outcome)| {
+            let market_id = tags.get(0).copied();
+            let res = match market_id {
+                Some(market_id) => self.internal_set_outcome(market_id, outcome),
+                None => Err("ERR_NO_MARKET_TAG")
+            };
+
+            SetOutcomeReport {
+                market_id,
+                success: res.is_ok(),
+                error: res.err().map(|err| err.to_string()),
+            }
+        }).collect()
     }
 
     /**
@@ -380,16 +1304,19 @@ impl AMMContract {
         &mut self,
         market_id: U64
     ) -> Promise { 
-        self.assert_unpaused();
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_withdrawals_allowed();
+        self.lock_account_market(&env::predecessor_account_id(), market_id.into());
         let initial_storage = env::storage_usage();
         let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
         assert!(market.enabled, "ERR_DISABLED_MARKET");
         assert!(market.finalized, "ERR_NOT_FINALIZED");
 
         let payout = market.pool.payout(&env::predecessor_account_id(), &market.payout_numerator);
+        market.claims_started = market.claims_started || payout > 0;
         self.markets.replace(market_id.into(), &market);
 
-        helper::refund_storage(initial_storage, env::predecessor_account_id());
+        self.refund_storage(initial_storage, env::predecessor_account_id());
 
         logger::log_claim_earnings(
             market_id,
@@ -398,18 +1325,590 @@ impl AMMContract {
         );
 
         if payout > 0 {
-                collateral_token::ft_transfer(
-                    env::predecessor_account_id(), 
-                    payout.into(),
-                    None,
-                    &market.pool.collateral_token_id,
-                    1,
-                    GAS_BASE_COMPUTE
-                )
+            self.transfer_payout_and_unlock(env::predecessor_account_id(), market_id.into(), payout, &market.pool.collateral_token_id)
         } else {
             panic!("ERR_NO_PAYOUT");
         }
     }
+
+    /**
+     * @notice claims earnings for the sender across multiple resoluted markets, paying out once per collateral token
+     * markets that aren't finalized or have nothing to claim are skipped
+     * @param market_ids references the markets to claim earnings for, at most `MAX_CLAIM_BATCH_SIZE`
+     * @returns a joint `Promise` of one collateral transfer per distinct collateral token
+     */
+    #[payable]
+    pub fn claim_earnings_batch(
+        &mut self,
+        market_ids: Vec<U64>
+    ) -> Promise {
+        self.assert_not_blacklisted(&env::predecessor_account_id());
+        self.assert_withdrawals_allowed();
+        let initial_storage = env::storage_usage();
+
+        let transfers = self.internal_claim_earnings_batch(&env::predecessor_account_id(), &market_ids);
+
+        self.refund_storage(initial_storage, env::predecessor_account_id());
+
+        transfers.into_iter().map(|(collateral_token_id, payout, market_ids)| {
+            self.transfer_payout_and_unlock_markets(env::predecessor_account_id(), market_ids, payout, &collateral_token_id)
+        })
+        .fold(None, |joint: Option<Promise>, transfer| match joint {
+            Some(joint) => Some(joint.and(transfer)),
+            None => Some(transfer)
+        })
+        .expect("ERR_NO_PAYOUT")
+    }
+
+    /**
+     * @notice enables or disables a single market, disabled markets reject all interactions, only callable by `gov`
+     * @param market_id references the market to enable or disable
+     * @param enabled whether the market should be enabled
+     */
+    pub fn set_market_enabled(
+        &mut self,
+        market_id: U64,
+        enabled: bool
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        market.enabled = enabled;
+        self.markets.replace(market_id.into(), &market);
+        logger::log_market_status(&market);
+    }
+
+    /**
+     * @notice sets the minimum number of distinct LPs a market needs before trading is allowed, only callable by `gov`
+     * @param market_id references the market to configure
+     * @param min_lp_count the minimum number of LPs, `None` disables the requirement
+     */
+    pub fn set_min_lp_count_to_trade(
+        &mut self,
+        market_id: U64,
+        min_lp_count: Option<U64>
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        market.min_lp_count_to_trade = min_lp_count.map(u64::from);
+        self.markets.replace(market_id.into(), &market);
+    }
+
+    /**
+     * @notice sets the minimum amount of collateral `add_liquidity` accepts for a market, only callable by `gov`
+     * @param market_id references the market to configure
+     * @param min_liquidity_add the minimum amount of collateral, 0 disables the minimum
+     */
+    pub fn set_min_liquidity_add(
+        &mut self,
+        market_id: U64,
+        min_liquidity_add: WrappedBalance
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        market.min_liquidity_add = min_liquidity_add.into();
+        self.markets.replace(market_id.into(), &market);
+    }
+
+    /**
+     * @notice cancels a market that was created in error, only callable by `gov`
+     * The market is finalized as invalid which stops all trading, everyone can get back the collateral they put in through `claim_refund`
+     * @param market_id references the market to cancel
+     */
+    pub fn cancel_market(&mut self, market_id: U64) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        assert!(!market.finalized, "ERR_IS_FINALIZED");
+
+        market.cancelled = true;
+        market.payout_numerator = None;
+        market.finalized = true;
+        self.record_finalization(&market);
+        self.settle_challenge(&market);
+        self.markets.replace(market_id.into(), &market);
+        logger::log_market_status(&market);
+    }
+
+    /**
+     * @notice refunds the sender's collateral in a cancelled market, paid out the same way as `claim_earnings` on an invalid market
+     * Balances and escrow are cleared on claim so every position can only be refunded once
+     * @param market_id references the cancelled market
+     * @returns a transfer `Promise` of the refunded collateral
+     */
+    #[payable]
+    pub fn claim_refund(&mut self, market_id: U64) -> Promise {
+        let market = self.get_market_expect(market_id);
+        assert!(market.cancelled, "ERR_MARKET_NOT_CANCELLED");
+        self.claim_earnings(market_id)
+    }
+
+    /**
+     * @notice pushes out the `end_time` of a market that hasn't been finalized, e.g. when the underlying event is postponed, only callable by `gov`
+     * `resolution_time` is moved along if it would otherwise be before the new `end_time`
+     * @param market_id references the market to extend
+     * @param new_end_time the new end time in ms, has to be after the current `end_time`
+     */
+    pub fn extend_end_time(
+        &mut self,
+        market_id: U64,
+        new_end_time: WrappedTimestamp
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        let new_end_time: u64 = new_end_time.into();
+        assert!(!market.finalized, "ERR_IS_FINALIZED");
+        assert!(new_end_time > market.end_time, "ERR_END_TIME_NOT_EXTENDED");
+
+        market.end_time = new_end_time;
+        market.resolution_time = market.resolution_time.max(new_end_time);
+        self.markets.replace(market_id.into(), &market);
+        logger::log_market_status(&market);
+    }
+
+    /**
+     * @notice sets the cap on the collateral locked in a market, only callable by `gov`. Lowering the cap below the current amount only blocks new liquidity
+     * @param market_id references the market to configure
+     * @param max_pool_collateral the cap in collateral, `None` removes it
+     */
+    pub fn set_max_pool_collateral(
+        &mut self,
+        market_id: U64,
+        max_pool_collateral: Option<WrappedBalance>
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        market.max_pool_collateral = max_pool_collateral.map(u128::from);
+        self.markets.replace(market_id.into(), &market);
+    }
+
+    /**
+     * @notice makes a market private by adding `accounts` to its trader allowlist, or public again, only callable by `gov`
+     * Every call replaces the previous allowlist, accounts of an earlier allowlist aren't allowed again
+     * @param market_id references the market to configure
+     * @param accounts the accounts to allow, `None` disables the allowlist
+     */
+    #[payable]
+    pub fn set_market_allowlist(
+        &mut self,
+        market_id: U64,
+        accounts: Option<Vec<ValidAccountId>>
+    ) {
+        self.assert_gov();
+        let initial_storage = env::storage_usage();
+        let mut market = self.get_market_expect(market_id);
+
+        market.trader_allowlist = match accounts {
+            Some(accounts) => {
+                let mut allowlist = market.new_trader_allowlist();
+                for account_id in accounts {
+                    allowlist.insert(account_id.as_ref());
+                }
+                Some(allowlist)
+            },
+            None => None
+        };
+        self.markets.replace(market_id.into(), &market);
+
+        self.refund_storage(initial_storage, env::predecessor_account_id());
+    }
+
+    /**
+     * @notice removes `accounts` from a market's trader allowlist, only callable by `gov`
+     * @param market_id references the market to configure
+     * @param accounts the accounts to remove
+     */
+    pub fn remove_from_market_allowlist(
+        &mut self,
+        market_id: U64,
+        accounts: Vec<ValidAccountId>
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        let allowlist = market.trader_allowlist.as_mut().expect("ERR_NO_ALLOWLIST");
+        for account_id in accounts {
+            allowlist.remove(account_id.as_ref());
+        }
+        self.markets.replace(market_id.into(), &market);
+    }
+
+    /**
+     * @param market_id is the index of the market to retrieve data from
+     * @param account_id the `AccountId` to check
+     * @returns whether `account_id` can trade in the market, always true for public markets
+     */
+    pub fn is_allowlisted(&self, market_id: U64, account_id: ValidAccountId) -> bool {
+        self.get_market_expect(market_id).is_allowlisted(account_id.as_ref())
+    }
+
+    /**
+     * @notice sets the swap fee of a market that hasn't received any liquidity yet, only callable by `gov`
+     * Markets with a fee schedule are rejected, the schedule would overwrite the fee on the next read
+     * @param market_id references the market to configure
+     * @param swap_fee the new fee, bound by the same limits as `create_market`
+     */
+    pub fn set_swap_fee(
+        &mut self,
+        market_id: U64,
+        swap_fee: WrappedBalance
+    ) {
+        self.assert_gov();
+        let mut market = self.get_market_expect(market_id);
+        assert!(market.fee_schedule.is_none(), "ERR_FEE_SCHEDULE_SET");
+        let swap_fee: u128 = swap_fee.into();
+        self.assert_swap_fee_within_max(swap_fee, market.pool.collateral_denomination);
+        market.pool.set_swap_fee(swap_fee);
+        self.markets.replace(market_id.into(), &market);
+
+        logger::log_pool(&market.pool);
+        logger::log_market_status(&market);
+    }
+}
+
+impl Market {
+    /**
+     * @panics if `payout_numerator` is set but doesn't have an entry per outcome summing up to `collateral_denomination`
+     */
+    pub fn assert_valid_payout_numerator(&self, payout_numerator: &Option<Vec<U128>>) {
+        if let Some(v) = payout_numerator {
+            // No single outcome can pay out more than the full collateral, checked before summing to catch typos early
+            assert!(v.iter().all(|n| u128::from(*n) <= self.pool.collateral_denomination), "ERR_NUMERATOR_ELEMENT_TOO_LARGE");
+            if let Err(err) = self.check_payout_numerator_sum(v) {
+                panic!("{}", err);
+            }
+        }
+    }
+
+    /**
+     * @notice checks both numerators passed in by `gov` and computed internally, defends against rounding bugs in the payout math mispaying holders
+     * @returns `ERR_INVALID_PAYOUT_SUM` if the numerator doesn't sum up to `collateral_denomination`, `ERR_INVALID_NUMERATOR` if it doesn't have an entry per outcome
+     */
+    pub fn check_payout_numerator_sum(&self, payout_numerator: &[U128]) -> Result<(), &'static str> {
+        let sum = payout_numerator.iter().try_fold(0u128, |s, &n| s.checked_add(u128::from(n)));
+        if sum != Some(self.pool.collateral_denomination) {
+            return Err("ERR_INVALID_PAYOUT_SUM");
+        }
+        if payout_numerator.len() != self.pool.outcomes as usize {
+            return Err("ERR_INVALID_NUMERATOR");
+        }
+        Ok(())
+    }
+
+    /**
+     * @notice checks an oracle answer against the data type requested at creation, `Number` for scalar and `String` for categorical markets
+     * @returns `ERR_ANSWER_TYPE_MISMATCH` for non-numeric answers to scalar markets and numeric answers that aren't a tag of categorical markets
+     */
+    pub fn check_answer_type(&self, outcome: &Outcome) -> Result<(), &'static str> {
+        if let Outcome::Answer(answer) = outcome {
+            let is_number = answer.parse::<f64>().is_ok();
+            let type_matches = if self.is_scalar {
+                is_number
+            } else {
+                !is_number || self.outcome_tags.contains(answer)
+            };
+
+            if !type_matches {
+                return Err("ERR_ANSWER_TYPE_MISMATCH");
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * @notice computes the payout numerator that corresponds to an oracle outcome
+     * @returns the payout numerator, `None` if the outcome is invalid, or an error if the outcome can't be applied to this market
+     */
+    pub fn calc_payout_numerator(&self, outcome: &Outcome) -> Result<Option<Vec<U128>>, &'static str> {
+        match outcome {
+            Outcome::Answer(answer) => {
+                if self.is_scalar {
+                    // Tags are increasing bounds, the answer's payout is split linearly over the two bounds surrounding it
+                    let segment = self.scalar_segment(answer)?;
+                    let lower_bound = &self.outcome_tags[segment];
+                    let upper_bound = &self.outcome_tags[segment + 1];
+                    let payout_short = match self.calc_scalar_payout_short_fixed_point(lower_bound, upper_bound, answer)? {
+                        Some(payout_short) => payout_short,
+                        None => self.calc_scalar_payout_short_f64(lower_bound, upper_bound, answer)?
+                    };
+
+                    let mut payout_numerator = vec![U128(0); self.outcome_tags.len()];
+                    payout_numerator[segment] = U128(payout_short);
+                    let payout_long = self.pool.collateral_denomination.checked_sub(payout_short).ok_or("ERR_SCALAR_UNDERFLOW")?;
+                    payout_numerator[segment + 1] = U128(payout_long);
+                    self.check_payout_numerator_sum(&payout_numerator)?;
+                    Ok(Some(payout_numerator))
+                } else {
+                    // Categorical market where only 1 outcome can be the winner
+                    let index = self.outcome_tags.iter().position(|tag| tag == answer).ok_or("ERR_OUTCOME_NOT_IN_TAGS")?;
+                    if self.ambiguity_policy == AmbiguityPolicy::RejectAmbiguous && self.outcome_tags.iter().filter(|tag| *tag == answer).count() > 1 {
+                        return Err("ERR_AMBIGUOUS_ANSWER");
+                    }
+                    let mut payout_numerator = vec![U128(0); self.outcome_tags.len()];
+
+                    payout_numerator[index] = U128(self.pool.collateral_denomination);
+                    self.check_payout_numerator_sum(&payout_numerator)?;
+                    Ok(Some(payout_numerator))
+                }
+            },
+            Outcome::Invalid => Ok(None),
+        }
+    }
+
+    /**
+     * @notice finds the pair of adjacent scalar bounds `(segment, segment + 1)` the answer falls between, answers outside of the range are clamped to the first or last pair
+     * @returns the index of the lower bound of the pair
+     */
+    fn scalar_segment(&self, answer: &str) -> Result<usize, &'static str> {
+        let answer_value: f64 = answer.parse().map_err(|_| "ERR_INVALID_SCALAR_ANSWER")?;
+        if self.outcome_tags.len() < 2 {
+            return Err("ERR_INVALID_SCALAR_BOUND");
+        }
+
+        let mut segment = 0;
+        for tag in self.outcome_tags[1..self.outcome_tags.len() - 1].iter() {
+            let bound: f64 = tag.parse().map_err(|_| "ERR_INVALID_SCALAR_BOUND")?;
+            if bound < answer_value {
+                segment += 1;
+            }
+        }
+        Ok(segment)
+    }
+
+    /**
+     * @notice calculates the payout of `lower_bound`'s outcome with integer math so small ranges don't lose precision to f64 rounding
+     * @returns `None` if the bounds or the answer aren't plain decimals, in which case the f64 calculation is used
+     */
+    fn calc_scalar_payout_short_fixed_point(&self, lower_bound: &str, upper_bound: &str, answer: &str) -> Result<Option<u128>, &'static str> {
+        let decimals = decimal_places(lower_bound)
+            .max(decimal_places(upper_bound))
+            .max(decimal_places(answer));
+
+        let (lower_bound, upper_bound, answer_value) = match (
+            parse_fixed_point(lower_bound, decimals),
+            parse_fixed_point(upper_bound, decimals),
+            parse_fixed_point(answer, decimals)
+        ) {
+            (Some(lower_bound), Some(upper_bound), Some(answer_value)) => (lower_bound, upper_bound, answer_value),
+            _ => return Ok(None)
+        };
+        if upper_bound <= lower_bound {
+            return Err("ERR_INVALID_SCALAR_BOUND");
+        }
+
+        let pointer_value = answer_value.max(lower_bound).min(upper_bound);
+        let range = checked_span(lower_bound, upper_bound)?;
+        let distance_to_upper = checked_span(pointer_value, upper_bound)?;
+        Ok(Some(math::complex_div_u128(self.pool.collateral_denomination, distance_to_upper, range)))
+    }
+
+    /**
+     * @notice calculates the payout of `lower_bound`'s outcome with f64 math, for bounds or answers that aren't plain decimals
+     */
+    fn calc_scalar_payout_short_f64(&self, lower_bound: &str, upper_bound: &str, answer: &str) -> Result<u128, &'static str> {
+        // f64 due the uncertainty of the pointer value/bounds
+        // It could be a decimal value or it could be an int/uint. 
+        // f64 can handle both for now
+        let lower_bound: f64 = lower_bound.parse().map_err(|_| "ERR_INVALID_SCALAR_BOUND")?;
+        let upper_bound: f64 = upper_bound.parse().map_err(|_| "ERR_INVALID_SCALAR_BOUND")?;
+        let answer_value: f64 = answer.parse().map_err(|_| "ERR_INVALID_SCALAR_ANSWER")?;
+
+        let pointer_value = clamp_f64(answer_value, lower_bound, upper_bound);
+        let range = upper_bound - lower_bound;
+        let percentage_upper_bound = (upper_bound - pointer_value) / range;
+
+        // Convert to string and back to u128 due to conversion errors
+        let payout_short_str = (percentage_upper_bound * self.pool.collateral_denomination as f64).round().to_string();
+        payout_short_str.parse().map_err(|_| "ERR_INVALID_SCALAR_BOUND")
+    }
+
+    /**
+     * @returns a serializable view of the market
+     */
+    pub fn to_view(&self) -> MarketView {
+        MarketView {
+            id: U64(self.pool.id),
+            end_time: U64(self.end_time),
+            resolution_time: U64(self.resolution_time),
+            outcomes: self.pool.outcomes,
+            outcome_tags: self.outcome_tags.clone(),
+            collateral_token_id: self.pool.collateral_token_id.to_string(),
+            payout_numerator: self.payout_numerator.clone(),
+            finalized: self.finalized,
+            enabled: self.enabled,
+            is_scalar: self.is_scalar,
+            min_lp_count_to_trade: self.min_lp_count_to_trade.map(U64),
+            categories: self.categories.clone(),
+            ambiguity_policy: self.ambiguity_policy,
+            min_liquidity_add: U128(self.min_liquidity_add),
+            lp_lockup_ms: self.lp_lockup_ms.map(U64),
+            price_tick: self.price_tick.map(U128),
+            notification_endpoint: self.notification_endpoint.clone(),
+            max_pool_collateral: self.max_pool_collateral.map(U128),
+            start_time: self.start_time.map(U64),
+            cancelled: self.cancelled,
+            pricing_mode: self.pool.pricing_mode,
+            created_at: U64(self.created_at),
+            fee_schedule: self.fee_schedule,
+            min_bet: self.min_bet.map(U128),
+            max_bet: self.max_bet.map(U128),
+            has_trader_allowlist: self.trader_allowlist.is_some(),
+            proposed_outcome: self.proposed_outcome.clone(),
+        }
+    }
+
+    /**
+     * @notice the oracle's challenge window isn't visible to the AMM, markets in it are reported as `AwaitingOracle`
+     * @returns the current `ResolutionState` of the market
+     */
+    pub fn resolution_state(&self) -> ResolutionState {
+        if self.finalized {
+            match self.payout_numerator {
+                Some(_) => ResolutionState::Finalized,
+                None => ResolutionState::Invalid,
+            }
+        } else if ns_to_ms(env::block_timestamp()) < self.end_time {
+            ResolutionState::Trading
+        } else {
+            ResolutionState::AwaitingOracle
+        }
+    }
+
+    /**
+     * @notice finalized markets report their outcome even if they're disabled, the oracle's finalization is tracked through `finalized`
+     * @returns the current `MarketState` of the market
+     */
+    pub fn market_state(&self) -> MarketState {
+        let now = ns_to_ms(env::block_timestamp());
+        if self.finalized {
+            match self.payout_numerator {
+                Some(_) => MarketState::Resolved,
+                None => MarketState::Invalid,
+            }
+        } else if !self.enabled {
+            MarketState::Disabled
+        } else if !self.has_started() {
+            MarketState::NotStarted
+        } else if now < self.end_time {
+            MarketState::Trading
+        } else if now < self.resolution_time {
+            MarketState::Closed
+        } else {
+            MarketState::AwaitingResolution
+        }
+    }
+
+    /**
+     * @returns `price` rounded to the nearest multiple of `price_tick`, or `price` unchanged if the market has no tick size
+     */
+    pub fn round_to_tick(&self, price: Balance) -> Balance {
+        match self.price_tick {
+            Some(tick) => (price + tick / 2) / tick * tick,
+            None => price
+        }
+    }
+
+    /**
+     * @returns the swap fee at the current block time, interpolated between the schedule's fees or the pool's fixed fee without a schedule
+     */
+    pub fn get_scheduled_swap_fee(&self) -> Balance {
+        let fee_schedule = match self.fee_schedule {
+            Some(fee_schedule) => fee_schedule,
+            None => return self.pool.swap_fee
+        };
+        let start_fee: u128 = fee_schedule.start_fee.into();
+        let end_fee: u128 = fee_schedule.end_fee.into();
+        let duration = self.end_time.saturating_sub(self.created_at);
+        let elapsed = ns_to_ms(env::block_timestamp()).saturating_sub(self.created_at).min(duration);
+        if duration == 0 {
+            return end_fee;
+        }
+
+        if end_fee >= start_fee {
+            start_fee + math::simple_mul_u128(duration as u128, end_fee - start_fee, elapsed as u128)
+        } else {
+            start_fee - math::simple_mul_u128(duration as u128, start_fee - end_fee, elapsed as u128)
+        }
+    }
+
+    /**
+     * @notice sets the pool's swap fee to the scheduled fee so all pool calculations use it, no-op for markets without a schedule
+     */
+    pub fn apply_fee_schedule(&mut self) {
+        if self.fee_schedule.is_some() {
+            self.pool.swap_fee = self.get_scheduled_swap_fee();
+        }
+    }
+
+    /**
+     * @returns empty storage for a new trader allowlist, a `LookupSet` can't be cleared so every allowlist gets its own prefix
+     */
+    fn new_trader_allowlist(&mut self) -> LookupSet<AccountId> {
+        self.allowlist_nonce += 1;
+        LookupSet::new(format!("m{}tal{}", self.pool.id, self.allowlist_nonce).as_bytes().to_vec())
+    }
+
+    /**
+     * @returns whether `account_id` can trade in the market, always true for public markets
+     */
+    pub fn is_allowlisted(&self, account_id: &AccountId) -> bool {
+        match &self.trader_allowlist {
+            Some(allowlist) => allowlist.contains(account_id),
+            None => true
+        }
+    }
+
+    /**
+     * @panics if the market has a trader allowlist that doesn't contain `account_id`
+     */
+    pub fn assert_allowlisted(&self, account_id: &AccountId) {
+        assert!(self.is_allowlisted(account_id), "ERR_NOT_ALLOWLISTED");
+    }
+
+    /**
+     * @panics if `collateral_in` is outside of the market's `min_bet` and `max_bet`
+     */
+    pub fn assert_bet_within_bounds(&self, collateral_in: Balance) {
+        if let Some(min_bet) = self.min_bet {
+            assert!(collateral_in >= min_bet, "ERR_BET_TOO_SMALL");
+        }
+        if let Some(max_bet) = self.max_bet {
+            assert!(collateral_in <= max_bet, "ERR_BET_TOO_LARGE");
+        }
+    }
+
+    /**
+     * @returns whether the market's `start_time` has been reached, always true for markets without one
+     */
+    pub fn has_started(&self) -> bool {
+        match self.start_time {
+            Some(start_time) => ns_to_ms(env::block_timestamp()) >= start_time,
+            None => true
+        }
+    }
+
+    /**
+     * @panics if the market has a `start_time` that hasn't been reached yet
+     */
+    pub fn assert_started(&self) {
+        assert!(self.has_started(), "ERR_MARKET_NOT_STARTED");
+    }
+
+    /**
+     * @panics if the market has a lockup period and `account_id` added liquidity too recently
+     */
+    pub fn assert_lp_unlocked(&self, account_id: &AccountId) {
+        if let Some(lp_lockup_ms) = self.lp_lockup_ms {
+            let added_at = self.pool.lp_added_at.get(account_id).unwrap_or(0);
+            assert!(ns_to_ms(env::block_timestamp()) >= added_at + lp_lockup_ms, "ERR_LP_LOCKED");
+        }
+    }
+
+    /**
+     * @panics if the market requires more distinct LPs than it currently has
+     */
+    pub fn assert_min_lp_count(&self) {
+        if let Some(min_lp_count) = self.min_lp_count_to_trade {
+            assert!(self.pool.lp_count >= min_lp_count, "ERR_NOT_ENOUGH_LPS");
+        }
+    }
 }
 
 impl AMMContract {
@@ -418,21 +1917,119 @@ impl AMMContract {
      * @returns the market
      */
     pub fn get_market_expect(&self, market_id: U64) -> Market {
-        self.markets.get(market_id.into()).expect("ERR_NO_MARKET")
+        let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+        market.apply_fee_schedule();
+        market
     }
 
     /**
-     * @notice add liquidity to a pool
-     * @param sender the sender of the original transfer_call
-     * @param total_in total amount of collateral to add to the market
-     * @param json string of `AddLiquidity` args
+     * @notice pays out `account_id`'s positions in `market_ids` and sums the payouts per collateral token
+     * `account_id` is locked on every market with a payout, same as `claim_earnings`, the transfer's callback releases the locks
+     * @returns a list of collateral token ids, the total payout in that token and the markets it was paid out of, in order of first appearance
      */
-    pub fn add_liquidity(
+    fn internal_claim_earnings_batch(&mut self, account_id: &AccountId, market_ids: &Vec<U64>) -> Vec<(AccountId, Balance, Vec<U64>)> {
+        assert!(market_ids.len() <= constants::MAX_CLAIM_BATCH_SIZE, "ERR_BATCH_TOO_LARGE");
+        let mut transfers: Vec<(AccountId, Balance, Vec<U64>)> = vec![];
+
+        for market_id in market_ids.iter().copied() {
+            let mut market = self.markets.get(market_id.into()).expect("ERR_NO_MARKET");
+            if !market.enabled || !market.finalized {
+                continue;
+            }
+
+            self.lock_account_market(account_id, market_id.into());
+            let payout = market.pool.payout(account_id, &market.payout_numerator);
+            market.claims_started = market.claims_started || payout > 0;
+            self.markets.replace(market_id.into(), &market);
+            if payout == 0 {
+                self.unlock_account_market(account_id, market_id.into());
+                continue;
+            }
+
+            logger::log_claim_earnings(market_id, account_id.to_string(), payout);
+
+            match transfers.iter_mut().find(|(collateral_token_id, _, _)| *collateral_token_id == market.pool.collateral_token_id) {
+                Some((_, total, paid_market_ids)) => {
+                    *total += payout;
+                    paid_market_ids.push(market_id);
+                },
+                None => transfers.push((market.pool.collateral_token_id.to_string(), payout, vec![market_id]))
+            }
+        }
+
+        transfers
+    }
+
+    /**
+     * @notice updates the finalized and invalid market counters for a market that has just been finalized
+     */
+    pub fn record_finalization(&mut self, market: &Market) {
+        self.remove_active_collateral_market(&market.pool.collateral_token_id);
+        self.finalized_market_count += 1;
+        if market.payout_numerator.is_none() {
+            self.invalid_market_count += 1;
+        }
+    }
+
+    /**
+     * @notice computes the payout for `outcome` and finalizes the market
+     * @returns an error instead of panicking so callers can decide how to handle failures
+     */
+    pub fn internal_set_outcome(&mut self, market_id: U64, outcome: &Outcome) -> Result<(), &'static str> {
+        let mut market = self.markets.get(market_id.into()).ok_or("ERR_NO_MARKET")?;
+        // A second answer would recount the finalization, gov can only resolve markets the oracle hasn't
+        if market.finalized {
+            return Err("ERR_OUTCOME_ALREADY_SET");
+        }
+        market.check_answer_type(outcome)?;
+        if ns_to_ms(env::block_timestamp()) < market.resolution_time {
+            return Err("ERR_RESOLUTION_TIME_NOT_REACHED");
+        }
+
+        if self.optimistic_resolution {
+            if market.proposed_outcome.is_some() {
+                return Err("ERR_OUTCOME_ALREADY_PROPOSED");
+            }
+            market.proposed_outcome = Some(ProposedOutcome {
+                proposer: env::predecessor_account_id(),
+                payout_numerator: market.calc_payout_numerator(outcome)?,
+                proposed_at: U64(ns_to_ms(env::block_timestamp())),
+                challenge: None,
+            });
+            self.markets.replace(market_id.into(), &market);
+            logger::log_outcome_proposed(&market);
+            return Ok(());
+        }
+
+        // Optimistic resolution was turned off after this outcome was proposed, the proposal still has to go through
+        // `finalize_after_challenge` or, if challenged, `resolute_market` so the bond gets settled
+        if market.proposed_outcome.is_some() {
+            return Err("ERR_OUTCOME_ALREADY_PROPOSED");
+        }
+
+        market.payout_numerator = market.calc_payout_numerator(outcome)?;
+        market.finalized = true;
+        self.record_finalization(&market);
+        self.markets.replace(market_id.into(), &market);
+        logger::log_market_status(&market);
+        Ok(())
+    }
+
+    /**
+     * @notice add liquidity to a pool
+     * @param sender the sender of the original transfer_call
+     * @param total_in total amount of collateral to add to the market
+     * @param json string of `AddLiquidity` args
+     * @returns the amount of LP tokens minted for `sender`
+     */
+    pub fn add_liquidity(
         &mut self,
         sender: &AccountId,
         total_in: u128,
         args: AddLiquidityArgs,
     ) -> PromiseOrValue<U128> {
+        self.assert_not_blacklisted(sender);
+        assert_deadline(args.deadline);
         let weights_u128: Option<Vec<u128>> = match args.weight_indication {
             Some(weight_indication) => {
                 Some(weight_indication
@@ -448,549 +2045,4012 @@ impl AMMContract {
         assert!(market.enabled, "ERR_DISABLED_MARKET");
         assert!(!market.finalized, "ERR_FINALIZED_MARKET");
         assert!(market.end_time > ns_to_ms(env::block_timestamp()), "ERR_MARKET_ENDED");
+        market.assert_started();
+        market.assert_allowlisted(sender);
         assert_collateral_token(&market.pool.collateral_token_id);
+        assert!(total_in >= market.min_liquidity_add, "ERR_LIQUIDITY_TOO_SMALL");
+        if let Some(weights) = &weights_u128 {
+            assert!(market.pool.pool_token.total_supply() == 0, "ERR_WEIGHTS_AFTER_SEED");
+            market.pool.assert_valid_weights(weights);
+        }
+        if market.pool.pool_token.total_supply() == 0 {
+            assert!(total_in >= self.min_initial_liquidity, "ERR_INSUFFICIENT_INITIAL_LIQUIDITY");
+        }
+        // Additions that would exceed the cap are rejected entirely rather than partially filled
+        if let Some(max_pool_collateral) = market.max_pool_collateral {
+            assert!(market.pool.get_collateral_locked() + total_in <= max_pool_collateral, "ERR_POOL_CAP_EXCEEDED");
+        }
         
-        market.pool.add_liquidity(
+        let minted = market.pool.add_liquidity(
             &sender,
             total_in,
             weights_u128
         );
+        if let Some(min_lp_tokens_out) = args.min_lp_tokens_out {
+            assert!(minted >= u128::from(min_lp_tokens_out), "ERR_MIN_LP_OUT");
+        }
+
+        self.markets.replace(args.market_id.into(), &market);
+        PromiseOrValue::Value(U128(minted))
+    }
+
+
+    /**
+     * @notice buy an outcome token
+     * @param sender the sender of the original transfer_call
+     * @param total_in total amount of collateral to use for purchasing
+     * @param json string of `AddLiquidity` args
+     * @returns the amount of outcome shares bought by `sender`
+     */
+    pub fn buy(
+        &mut self,
+        sender: &AccountId,
+        collateral_in: u128, 
+        args: BuyArgs,
+    ) -> PromiseOrValue<U128> {
+        self.assert_not_blacklisted(sender);
+        assert_deadline(args.deadline);
+        let mut market = self.get_market_expect(args.market_id);
+        assert!(market.enabled, "ERR_DISABLED_MARKET");
+        assert!(!market.finalized, "ERR_FINALIZED_MARKET");
+        assert!(market.end_time > ns_to_ms(env::block_timestamp()), "ERR_MARKET_ENDED");
+        market.assert_started();
+        assert_collateral_token(&market.pool.collateral_token_id);
+        market.assert_min_lp_count();
+        market.assert_allowlisted(sender);
+        market.assert_bet_within_bounds(collateral_in);
+        
+        let (shares_out, protocol_fee) = market.pool.buy(
+            &sender,
+            collateral_in,
+            args.outcome_target,
+            args.min_shares_out.into(),
+            self.protocol_fee_fraction
+        );
+
+        self.accrue_treasury(&market.pool.collateral_token_id, protocol_fee);
         self.markets.replace(args.market_id.into(), &market);
-        PromiseOrValue::Value(0.into())
+        PromiseOrValue::Value(U128(shares_out))
+    }
+
+    /**
+     * @notice buy an exact amount of outcome tokens
+     * @param sender the sender of the original transfer_call
+     * @param total_in total amount of collateral transferred, any collateral that isn't needed is returned
+     * @param json string of `BuyExactSharesArgs` args
+     * @returns the amount of collateral that wasn't used
+     */
+    pub fn buy_exact_shares(
+        &mut self,
+        sender: &AccountId,
+        total_in: u128,
+        args: BuyExactSharesArgs,
+    ) -> PromiseOrValue<U128> {
+        let market = self.get_market_expect(args.market_id);
+        let collateral_in = market.pool.calc_buy_collateral_in(args.shares_out.into(), args.outcome_target);
+        assert!(collateral_in <= u128::from(args.max_collateral_in), "ERR_MAX_COLLATERAL_IN");
+        assert!(collateral_in <= total_in, "ERR_INSUFFICIENT_COLLATERAL");
+
+        self.buy(
+            sender,
+            collateral_in,
+            BuyArgs {
+                market_id: args.market_id,
+                outcome_target: args.outcome_target,
+                min_shares_out: args.shares_out,
+                deadline: args.deadline
+            }
+        );
+
+        PromiseOrValue::Value(U128(total_in - collateral_in))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod market_basic_tests {
+    use std::convert::TryInto;
+    use near_sdk::{ MockedBlockchain };
+    use near_sdk::{ testing_env };
+    use super::*;
+    use crate::test_utils::*;
+    use crate::storage_manager::StorageManager;
+
+    #[test]
+    fn basic_create_market() {
+        testing_env!(get_context(alice(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_ENDED")]
+    fn add_liquidity_after_resolution() {
+        testing_env!(get_context(alice(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+
+        let market_id = contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        let mut market = contract.get_market_expect(U64(0));
+        market.enabled = true;
+        contract.markets.replace(0, &market);
+
+        testing_env!(get_context(token(), ms_to_ns(1619882574000)));
+
+        let add_liquidity_args = AddLiquidityArgs {
+            market_id,
+            weight_indication: Some(vec![U128(2), U128(1)]),
+            min_lp_tokens_out: None,
+            deadline: None
+        };
+
+        contract.add_liquidity(
+            &alice(), // sender
+            10000000000000000000, // total_in
+            add_liquidity_args
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_RESOLUTION_TIME")]
+    fn invalid_resolution_time() {
+        testing_env!(get_context(alice(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                resolution_time: 1609951265965.into(), // resolution_time (~1 day after end_time)
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_outcome() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, None, "Numerator should be None");
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_MARKET_TAG")]
+    fn set_outcome_without_market_tag() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        contract.create_market(&default_market_args());
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![]));
+    }
+
+    #[test]
+    fn valid_categorical_outcome() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["YES".to_string(), "NO".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("NO".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(1000000000000000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_large_range() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["50000000000".to_string(), "150000000000".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("70369216342".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(796307836580000000000000), U128(203692163420000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_complex_floating_answer() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["0".to_string(), "10".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("2.68".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(732000000000000000000000), U128(268000000000000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_floating_answer() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["0".to_string(), "5".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("2.5".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(500000000000000000000000), U128(500000000000000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_outcome_price_over_lower_bound() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["0".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("-44".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_outcome_price_over_upper_bound() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["0".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("55".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(1000000000000000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_negative_bounds_answer_at_lower_bound() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["-50".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("-50".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_negative_bounds_answer_just_below_lower_bound() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["-50".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("-50.000001".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_negative_bounds_answer_far_below_lower_bound() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["-50".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("-5000".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_negative_bounds_answer_at_zero() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["-50".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(500000000000000000000000), U128(500000000000000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    fn valid_scalar_negative_bounds_answer_at_upper_bound() {
+        testing_env!(get_context(oracle(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+        
+        contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                outcomes: 2, // outcomes
+                outcome_tags: vec!["-50".to_string(), "50".to_string()], // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: true, // is_scalar,
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("50".to_string()), Some(vec![U64(0)]));
+
+        let market = contract.get_market_expect(U64(0));
+        assert!(market.finalized, "Market should be finalized");
+        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(1000000000000000000000000)]), "Numerator should be set");
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESOLUTION_TIME_NOT_REACHED")]
+    fn resolute_before_resolution_time() {
+        testing_env!(get_context(alice(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+
+        let market_id = contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(token(), 0));
+
+        let mut market = contract.get_market_expect(U64(0));
+        market.enabled = true;
+        contract.markets.replace(0, &market);
+
+        let add_liquidity_args = AddLiquidityArgs {
+            market_id,
+            weight_indication: Some(vec![U128(2), U128(1)]),
+            min_lp_tokens_out: None,
+            deadline: None
+        };
+
+        contract.add_liquidity(
+            &alice(), // sender
+            10000000000000000000, // total_in
+            add_liquidity_args
+        );
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882573999)));
+
+        contract.resolute_market(
+            market_id,
+            Some(vec![U128(1000000000000000000000000), U128(0)]) // payout_numerator
+        );
+    }
+
+    #[test]
+    fn resolute_after_resolution_time() {
+        testing_env!(get_context(alice(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+
+        let market_id = contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(token(), 0));
+
+        let mut market = contract.get_market_expect(U64(0));
+        market.enabled = true;
+        contract.markets.replace(0, &market);
+
+        let add_liquidity_args = AddLiquidityArgs {
+            market_id,
+            weight_indication: Some(vec![U128(2), U128(1)]),
+            min_lp_tokens_out: None,
+            deadline: None
+        };
+
+        contract.add_liquidity(
+            &alice(), // sender
+            10000000000000000000, // total_in
+            add_liquidity_args
+        );
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+
+        contract.resolute_market(
+            market_id,
+            Some(vec![U128(1000000000000000000000000), U128(0)]) // payout_numerator
+        );
+    }
+
+    #[test]
+    fn calc_min_lp_out_applies_slippage() {
+        testing_env!(get_context(alice(), 0));
+
+        let mut contract = AMMContract::init(
+            bob().try_into().unwrap(),
+            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+            oracle().try_into().unwrap()
+        );
+
+        let market_id = contract.create_market(
+            &CreateMarketArgs {
+                description: empty_string(), // market description
+                extra_info: empty_string(), // extra info
+                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+                outcomes: 2, // outcomes
+                outcome_tags: outcome_tags(2), // outcome tags
+                categories: empty_string_vec(2), // categories
+                end_time: 1609951265967.into(), // end_time
+                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+                collateral_token_id: token(), // collateral_token_id
+                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+                challenge_period: U64(1),
+                is_scalar: false, // is_scalar
+                ambiguity_policy: None, // ambiguity_policy
+                lp_lockup_ms: None, // lp_lockup_ms
+                price_tick: None, // price_tick
+                notification_endpoint: None, // notification_endpoint
+                max_pool_collateral: None, // max_pool_collateral
+                start_time: None, // start_time
+                pricing_mode: None, // pricing_mode
+                fee_schedule: None, // fee_schedule
+                min_bet: None, // min_bet
+                max_bet: None, // max_bet
+            }
+        );
+
+        testing_env!(get_context(token(), 0));
+
+        let mut market = contract.get_market_expect(U64(0));
+        market.enabled = true;
+        contract.markets.replace(0, &market);
+
+        contract.add_liquidity(
+            &alice(), // sender
+            10000000000000000000, // total_in
+            AddLiquidityArgs {
+                market_id,
+                weight_indication: Some(vec![U128(2), U128(1)]),
+                min_lp_tokens_out: None,
+                deadline: None
+            }
+        );
+
+        let total_in = 10000000000000000000;
+        let market = contract.get_market_expect(market_id);
+        let (exact_lp_out, _) = market.pool.calc_add_liquidity(total_in, &None);
+
+        let min_lp_out: u128 = contract.calc_min_lp_out(market_id, U128(total_in), None, 100).into();
+        assert_eq!(min_lp_out, exact_lp_out - exact_lp_out / 100);
+        assert!(min_lp_out < exact_lp_out);
+
+        let no_slippage: u128 = contract.calc_min_lp_out(market_id, U128(total_in), None, 0).into();
+        assert_eq!(no_slippage, exact_lp_out);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_LPS")]
+    fn buy_blocked_below_min_lp_count() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_min_lp_count_to_trade(market_id, Some(U64(2)));
+
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        assert_eq!(contract.get_lp_count(market_id), U64(1));
+
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
+            }
+        );
+    }
+
+    #[test]
+    fn buy_allowed_at_min_lp_count() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_min_lp_count_to_trade(market_id, Some(U64(2)));
+
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(24), None);
+        assert_eq!(contract.get_lp_count(market_id), U64(2));
+
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
+            }
+        );
+
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 > 0);
+    }
+
+    #[test]
+    fn get_market_returns_metadata() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string()];
+        let market_id = contract.create_market(&args);
+
+        let view = contract.get_market(market_id);
+        assert_eq!(view.id, market_id);
+        assert_eq!(view.end_time, U64(1609951265967));
+        assert_eq!(view.resolution_time, U64(1619882574000));
+        assert_eq!(view.outcomes, 2);
+        assert_eq!(view.outcome_tags, vec!["YES".to_string(), "NO".to_string()]);
+        assert_eq!(view.collateral_token_id, token());
+        assert_eq!(view.payout_numerator, None);
+        assert!(!view.finalized);
+        assert!(!view.enabled);
+        assert!(!view.is_scalar);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("YES".to_string()), Some(vec![market_id]));
+
+        let view = contract.get_market(market_id);
+        assert!(view.finalized);
+        assert_eq!(view.payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]));
+    }
+
+    #[test]
+    fn calc_lp_breakeven_balanced_entry() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        seed_market(&mut contract, market_id, carol(), 100 * 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        // A balanced LP holds an equal claim on every outcome so it only breaks even if the outcome pays out in full
+        let breakeven = contract.calc_lp_breakeven(market_id, &carol());
+        assert_eq!(breakeven, vec![U128(10_u128.pow(24)), U128(10_u128.pow(24))]);
+
+        // An account without a position has nothing to recoup
+        let breakeven = contract.calc_lp_breakeven(market_id, &bob());
+        assert_eq!(breakeven, vec![U128(0), U128(0)]);
+    }
+
+    #[test]
+    fn get_markets_paginates() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        for _ in 0..3 {
+            contract.create_market(&default_market_args());
+        }
+
+        let markets = contract.get_markets(U64(0), U64(2));
+        assert_eq!(markets.len(), 2);
+        assert_eq!(markets[0].id, U64(0));
+        assert_eq!(markets[1].id, U64(1));
+
+        let markets = contract.get_markets(U64(1), U64(10));
+        assert_eq!(markets.len(), 2);
+        assert_eq!(markets[1].id, U64(2));
+
+        assert!(contract.get_markets(U64(3), U64(10)).is_empty());
+        assert!(contract.get_markets(U64(u64::MAX), U64(u64::MAX)).is_empty());
+    }
+
+    #[test]
+    fn get_markets_by_category_filters() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+
+        let mut args = default_market_args();
+        args.categories = vec!["sports".to_string()];
+        contract.create_market(&args);
+        args.categories = vec!["crypto".to_string(), "sports".to_string()];
+        contract.create_market(&args);
+        args.categories = vec!["crypto".to_string()];
+        contract.create_market(&args);
+
+        assert_eq!(contract.get_market(U64(1)).categories, vec!["crypto".to_string(), "sports".to_string()]);
+        assert_eq!(contract.get_markets_by_category("sports".to_string(), U64(0), U64(10)), vec![U64(0), U64(1)]);
+        assert_eq!(contract.get_markets_by_category("crypto".to_string(), U64(0), U64(10)), vec![U64(1), U64(2)]);
+        assert_eq!(contract.get_markets_by_category("crypto".to_string(), U64(2), U64(10)), vec![U64(2)]);
+        assert!(contract.get_markets_by_category("Sports".to_string(), U64(0), U64(10)).is_empty());
+    }
+
+    #[test]
+    fn avg_execution_price_worsens_with_size() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 100 * 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        let spot_price: u128 = contract.get_spot_price(market_id, 0).into();
+        let small: u128 = contract.get_avg_execution_price(market_id, U128(10_u128.pow(24)), 0).into();
+        let medium: u128 = contract.get_avg_execution_price(market_id, U128(10 * 10_u128.pow(24)), 0).into();
+        let large: u128 = contract.get_avg_execution_price(market_id, U128(50 * 10_u128.pow(24)), 0).into();
+
+        assert!(spot_price < small);
+        assert!(small < medium);
+        assert!(medium < large);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_SHARES_OUT")]
+    fn avg_execution_price_without_shares_out() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        contract.get_avg_execution_price(market_id, U128(0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_LP_OUT")]
+    fn add_liquidity_below_min_lp_tokens_out() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(token(), 0));
+        contract.add_liquidity(
+            &alice(),
+            10_u128.pow(24),
+            AddLiquidityArgs {
+                market_id,
+                weight_indication: Some(vec![U128(1), U128(1)]),
+                min_lp_tokens_out: Some(U128(10_u128.pow(24) + 1)),
+                deadline: None
+            }
+        );
+    }
+
+    #[test]
+    fn add_liquidity_returns_minted_lp_tokens() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(token(), 0));
+        let res = contract.add_liquidity(
+            &alice(),
+            10_u128.pow(24),
+            AddLiquidityArgs {
+                market_id,
+                weight_indication: Some(vec![U128(1), U128(1)]),
+                min_lp_tokens_out: Some(U128(10_u128.pow(24))),
+                deadline: None
+            }
+        );
+
+        match res {
+            PromiseOrValue::Value(minted) => assert_eq!(minted, contract.get_pool_token_balance(market_id, &alice())),
+            PromiseOrValue::Promise(_) => panic!("expected a value")
+        }
+    }
+
+    #[test]
+    fn set_outcomes_batch_skips_failed_entries() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string()];
+        for _ in 0..3 {
+            contract.create_market(&args);
+        }
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        let report = contract.set_outcomes_batch(vec![
+            (vec![U64(0)], Outcome::Answer("YES".to_string())),
+            (vec![U64(1)], Outcome::Answer("MAYBE".to_string())),
+            (vec![U64(1)], Outcome::Invalid),
+            (vec![U64(2)], Outcome::Answer("NO".to_string())),
+        ]);
+
+        assert_eq!(report.iter().map(|r| r.success).collect::<Vec<bool>>(), vec![true, false, true, true]);
+        assert_eq!(report[1].error, Some("ERR_OUTCOME_NOT_IN_TAGS".to_string()));
+
+        assert_eq!(contract.get_market(U64(0)).payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        assert!(contract.get_market(U64(1)).finalized);
+        assert_eq!(contract.get_market(U64(1)).payout_numerator, None);
+        assert_eq!(contract.get_market(U64(2)).payout_numerator, Some(vec![U128(0), U128(1000000000000000000000000)]));
+    }
+
+    #[test]
+    fn buy_returns_shares_out() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        let expected_shares_out = contract.calc_buy_amount(market_id, U128(10_u128.pow(23)), 1);
+        let res = contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 1,
+                min_shares_out: U128(0),
+                deadline: None
+            }
+        );
+
+        match res {
+            PromiseOrValue::Value(shares_out) => {
+                assert_eq!(shares_out, expected_shares_out);
+                assert_eq!(shares_out, contract.get_share_balance(&bob(), market_id, 1));
+            },
+            PromiseOrValue::Promise(_) => panic!("expected a value")
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NUMERATOR_ELEMENT_TOO_LARGE")]
+    fn resolute_market_numerator_element_too_large() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+
+        contract.resolute_market(
+            market_id,
+            Some(vec![U128(10_u128.pow(25)), U128(0)])
+        );
+    }
+
+    #[test]
+    fn resolute_at_resolution_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, None);
+
+        assert!(contract.get_market(market_id).finalized);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESOLUTION_TIME_NOT_REACHED")]
+    fn set_outcome_before_resolution_time() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882573999)));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![market_id]));
+    }
+
+    #[test]
+    fn estimate_operation_gas_in_sane_range() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        let max_prepaid_gas: u64 = 300_000_000_000_000;
+        let buy_gas: u64 = contract.estimate_operation_gas("buy".to_string(), market_id).into();
+        let claim_gas: u64 = contract.estimate_operation_gas("claim_earnings".to_string(), market_id).into();
+
+        assert!(buy_gas > GAS_BASE_COMPUTE && buy_gas < max_prepaid_gas);
+        assert!(claim_gas > GAS_BASE_COMPUTE && claim_gas < max_prepaid_gas);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UNKNOWN_OPERATION")]
+    fn estimate_operation_gas_unknown_operation() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        contract.estimate_operation_gas("swap".to_string(), market_id);
+    }
+
+    #[test]
+    fn get_markets_by_state_filters() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string()];
+        for _ in 0..3 {
+            contract.create_market(&args);
+        }
+        args.end_time = 1619882574001.into();
+        args.resolution_time = 1619882574001.into();
+        contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("YES".to_string()), Some(vec![U64(1)]));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![U64(2)]));
+
+        assert_eq!(contract.get_markets_by_state(ResolutionState::AwaitingOracle, U64(0), U64(10)), vec![U64(0)]);
+        assert_eq!(contract.get_markets_by_state(ResolutionState::Finalized, U64(0), U64(10)), vec![U64(1)]);
+        assert_eq!(contract.get_markets_by_state(ResolutionState::Invalid, U64(0), U64(10)), vec![U64(2)]);
+        assert_eq!(contract.get_markets_by_state(ResolutionState::Trading, U64(0), U64(10)), vec![U64(3)]);
+        assert_eq!(contract.get_markets_by_state(ResolutionState::Trading, U64(0), U64(3)), Vec::<U64>::new());
+        assert_eq!(contract.get_resolution_state(U64(3)), ResolutionState::Trading);
+    }
+
+    #[test]
+    fn ambiguous_answer_first_match() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcomes = 3;
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string(), "YES".to_string()];
+        args.ambiguity_policy = Some(AmbiguityPolicy::FirstMatch);
+        let market_id = contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("YES".to_string()), Some(vec![market_id]));
+
+        assert_eq!(contract.get_market(market_id).payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0), U128(0)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMBIGUOUS_ANSWER")]
+    fn ambiguous_answer_rejected() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcomes = 3;
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string(), "YES".to_string()];
+        args.ambiguity_policy = Some(AmbiguityPolicy::RejectAmbiguous);
+        let market_id = contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("YES".to_string()), Some(vec![market_id]));
+    }
+
+    #[test]
+    fn calc_add_liquidity_preview_matches_add_liquidity() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        let seed_preview = contract.calc_add_liquidity_preview(market_id, U128(10_u128.pow(24)), Some(vec![U128(3), U128(1)]));
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(3), U128(1)]));
+
+        assert_eq!(seed_preview.lp_tokens, contract.get_pool_token_balance(market_id, &carol()));
+        assert_eq!(seed_preview.outcome_shares[0], contract.get_share_balance(&carol(), market_id, 0));
+        assert_eq!(seed_preview.outcome_shares[1], contract.get_share_balance(&carol(), market_id, 1));
+
+        let preview = contract.calc_add_liquidity_preview(market_id, U128(10_u128.pow(23)), None);
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(23), None);
+
+        assert_eq!(preview.lp_tokens, contract.get_pool_token_balance(market_id, &bob()));
+        assert_eq!(preview.outcome_shares[0], contract.get_share_balance(&bob(), market_id, 0));
+        assert_eq!(preview.outcome_shares[1], contract.get_share_balance(&bob(), market_id, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UNEXPECTED_WEIGHT_INDICATION")]
+    fn calc_add_liquidity_preview_rejects_weights_after_seed() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(3), U128(1)]));
+
+        contract.calc_add_liquidity_preview(market_id, U128(10_u128.pow(23)), Some(vec![U128(1), U128(1)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_WEIGHTS_LENGTH")]
+    fn add_liquidity_wrong_weights_length() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1), U128(1)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ZERO_WEIGHT")]
+    fn add_liquidity_zero_weight() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(0)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_WEIGHTS_AFTER_SEED")]
+    fn add_liquidity_weights_after_seed() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(3), U128(1)]));
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(23), Some(vec![U128(1), U128(1)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LIQUIDITY_TOO_SMALL")]
+    fn add_liquidity_below_min_liquidity_add() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_min_liquidity_add(market_id, U128(10_u128.pow(24)));
+
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24) - 1, Some(vec![U128(1), U128(1)]));
+    }
+
+    #[test]
+    fn add_liquidity_meets_min_liquidity_add() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_min_liquidity_add(market_id, U128(10_u128.pow(24)));
+
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        assert_eq!(contract.get_pool_token_balance(market_id, &alice()), U128(10_u128.pow(24)));
+    }
+
+    #[test]
+    fn market_collateral_removed_from_whitelist() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        assert!(contract.is_market_collateral_whitelisted(market_id));
+
+        testing_env!(get_context(bob(), 0));
+        contract.remove_from_collateral_whitelist(token());
+
+        assert!(!contract.is_market_collateral_whitelisted(market_id));
+    }
+
+    #[test]
+    fn calc_exit_pool_preview_matches_exit_pool() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol());
+        let balance_before = vec![contract.get_share_balance(&carol(), market_id, 0).0, contract.get_share_balance(&carol(), market_id, 1).0];
+        let preview = contract.calc_exit_pool_preview(market_id, &carol(), lp_tokens);
+        assert!(preview.fees.0 > 0);
+        assert_eq!(preview.fees, preview.collateral);
+
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, lp_tokens);
+
+        assert_eq!(contract.get_share_balance(&carol(), market_id, 0).0 - balance_before[0], preview.outcome_shares[0].0);
+        assert_eq!(contract.get_share_balance(&carol(), market_id, 1).0 - balance_before[1], preview.outcome_shares[1].0);
+    }
+
+    #[test]
+    fn withdraw_fees_then_exit() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol());
+
+        testing_env!(get_context(carol(), 0));
+        contract.withdraw_fees(market_id);
+
+        assert_eq!(contract.get_pool_token_balance(market_id, &carol()), lp_tokens);
+        assert_eq!(contract.calc_exit_pool_preview(market_id, &carol(), lp_tokens).fees, U128(0));
+
+        match contract.exit_pool(market_id, lp_tokens) {
+            PromiseOrValue::Value(exited) => assert!(exited),
+            PromiseOrValue::Promise(_) => panic!("fees were paid out twice")
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_FEES")]
+    fn withdraw_fees_twice() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(carol(), 0));
+        contract.withdraw_fees(market_id);
+        contract.withdraw_fees(market_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LP_LOCKED")]
+    fn exit_pool_before_lockup_expires() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.lp_lockup_ms = Some(U64(1000));
+        let market_id = contract.create_market(&args);
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        testing_env!(get_context(alice(), ms_to_ns(999)));
+        contract.exit_pool(market_id, U128(10_u128.pow(24)));
+    }
+
+    #[test]
+    fn exit_pool_after_lockup_expires() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.lp_lockup_ms = Some(U64(1000));
+        let market_id = contract.create_market(&args);
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, alice(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        testing_env!(get_context(alice(), ms_to_ns(1000)));
+        contract.exit_pool(market_id, U128(10_u128.pow(24)));
+        assert_eq!(contract.get_pool_token_balance(market_id, &alice()), U128(0));
+    }
+
+    #[test]
+    fn sell_after_end_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares_before = contract.get_share_balance(&bob(), market_id, 0);
+
+        testing_env!(get_context(bob(), ms_to_ns(1609951265967 + 1)));
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares_before, None);
+
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 < shares_before.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_ENDED")]
+    fn buy_after_end_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(token(), ms_to_ns(1609951265967 + 1)));
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
+            }
+        );
+    }
+
+    #[test]
+    fn market_counts() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string()];
+        for _ in 0..3 {
+            contract.create_market(&args);
+        }
+        enable_market(&mut contract, U64(0));
+        assert_eq!(contract.get_market_counts(), (U64(3), U64(0), U64(0)));
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(U64(0), Some(vec![U128(1000000000000000000000000), U128(0)]));
+        assert_eq!(contract.get_market_counts(), (U64(3), U64(1), U64(0)));
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![U64(1)]));
+        assert_eq!(contract.get_market_counts(), (U64(3), U64(2), U64(1)));
+    }
+
+    #[test]
+    fn get_claimable_valid_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(0));
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+
+        assert_eq!(contract.get_claimable(market_id, &bob()), contract.get_share_balance(&bob(), market_id, 0));
+        assert_eq!(contract.get_claimable(market_id, &oracle()), U128(0));
+        assert!(contract.get_claimable(market_id, &carol()).0 > 0);
+    }
+
+    #[test]
+    fn get_claimable_invalid_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, None);
+
+        let claimable = contract.get_claimable(market_id, &bob());
+        assert!(claimable.0 > 0 && claimable.0 <= 10_u128.pow(23));
+
+        contract.claim_earnings(market_id);
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(0));
+    }
+
+    #[test]
+    fn spot_price_rounded_to_tick() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let price_tick = 10_u128.pow(22); // 0.01
+        let mut args = default_market_args();
+        args.price_tick = Some(U128(price_tick));
+        let market_id = contract.create_market(&args);
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(3), U128(1)]));
+
+        for outcome in 0..2 {
+            assert_eq!(u128::from(contract.get_spot_price(market_id, outcome)) % price_tick, 0);
+            assert_eq!(u128::from(contract.get_spot_price_sans_fee(market_id, outcome)) % price_tick, 0);
+        }
+        assert_eq!(contract.get_spot_price_sans_fee(market_id, 0), U128(25 * price_tick));
+    }
+
+    #[test]
+    fn claim_earnings_batch_single_transfer_per_token() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_ids: Vec<U64> = (0..3).map(|_| seed_market_with_fees(&mut contract)).collect();
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        for market_id in market_ids.iter() {
+            contract.resolute_market(*market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        }
+        let expected_payout = market_ids.iter().fold(0, |sum, market_id| sum + u128::from(contract.get_claimable(*market_id, &bob())));
+
+        let transfers = contract.internal_claim_earnings_batch(&bob(), &market_ids);
+
+        assert_eq!(transfers, vec![(token(), expected_payout, market_ids.clone())]);
+        for market_id in market_ids.iter() {
+            assert_eq!(contract.get_claimable(*market_id, &bob()), U128(0));
+        }
+    }
+
+    #[test]
+    fn claim_earnings_batch_skips_empty_payouts() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+
+        assert_eq!(contract.internal_claim_earnings_batch(&oracle(), &vec![market_id]), Vec::<(AccountId, Balance, Vec<U64>)>::new());
+        assert!(!contract.reentrancy_locks.contains(&(oracle(), market_id.into())));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_REENTRANT")]
+    fn claim_earnings_batch_while_claim_pending() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        contract.claim_earnings(market_id);
+        contract.internal_claim_earnings_batch(&bob(), &vec![market_id]);
+    }
+
+    #[test]
+    fn claim_earnings_batch_locks_paid_markets() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        contract.internal_claim_earnings_batch(&bob(), &vec![market_id]);
+
+        assert!(contract.reentrancy_locks.contains(&(bob(), market_id.into())));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BATCH_TOO_LARGE")]
+    fn claim_earnings_batch_too_large() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.claim_earnings_batch(vec![U64(0); constants::MAX_CLAIM_BATCH_SIZE + 1]);
+    }
+
+    #[test]
+    fn notification_endpoint_round_trip() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.notification_endpoint = Some("https://example.com/resolutions".to_string());
+        let market_id = contract.create_market(&args);
+
+        assert_eq!(contract.get_market(market_id).notification_endpoint, Some("https://example.com/resolutions".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOTIFICATION_ENDPOINT_TOO_LONG")]
+    fn notification_endpoint_too_long() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.notification_endpoint = Some("a".repeat(constants::MAX_NOTIFICATION_ENDPOINT_LENGTH + 1));
+        contract.create_market(&args);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DISABLED_MARKET")]
+    fn buy_disabled_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_market_enabled(market_id, false);
+
+        testing_env!(get_context(token(), 0));
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DISABLED_MARKET")]
+    fn sell_disabled_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_market_enabled(market_id, false);
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, None);
+    }
+
+    #[test]
+    fn re_enabled_market_trades() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_market_enabled(market_id, false);
+        contract.set_market_enabled(market_id, true);
+        assert!(contract.get_market(market_id).enabled);
+
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, None);
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 < shares.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_GOVERNANCE_ADDRESS")]
+    fn set_market_enabled_not_gov() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        contract.set_market_enabled(market_id, true);
+    }
+
+    #[test]
+    fn pool_token_value_of_single_balanced_lp() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        assert_eq!(contract.get_pool_token_value(market_id, &carol()), U128(10_u128.pow(24)));
+        assert_eq!(contract.get_pool_token_value(market_id, &bob()), U128(0));
+    }
+
+    #[test]
+    fn pool_token_values_sum_to_pool_value() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(23), None);
+
+        let market = contract.get_market_expect(market_id);
+        let fees = market.pool.get_fees_withdrawable(&carol()) + market.pool.get_fees_withdrawable(&bob());
+        let expected = market.pool.get_pool_value() + fees;
+        let total = u128::from(contract.get_pool_token_value(market_id, &carol())) + u128::from(contract.get_pool_token_value(market_id, &bob()));
+
+        // Allow for rounding in the per LP share and fee calculations
+        let diff = if expected > total { expected - total } else { total - expected };
+        assert!(diff <= 2, "LP values {} don't add up to the pool value {}", total, expected);
+    }
+
+    #[test]
+    fn create_market_with_added_collateral() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        let usdc = "usdc".to_string();
+        contract.add_to_collateral_whitelist(collateral_whitelist::Token{account_id: usdc.to_string(), decimals: 6});
+
+        let whitelist = contract.get_collateral_whitelist();
+        assert!(whitelist.contains(&(token(), 24)));
+        assert!(whitelist.contains(&(usdc.to_string(), 6)));
+
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.collateral_token_id = usdc.to_string();
+        args.swap_fee = (10_u128.pow(6) / 50).into();
+        let market_id = contract.create_market(&args);
+
+        let market = contract.get_market_expect(market_id);
+        assert_eq!(market.pool.collateral_token_id, usdc);
+        assert_eq!(market.pool.collateral_denomination, 10_u128.pow(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_COLLATERAL")]
+    fn create_market_with_removed_collateral() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.remove_from_collateral_whitelist(token());
+        assert_eq!(contract.get_collateral_whitelist(), Vec::<(AccountId, u32)>::new());
+
+        testing_env!(get_context(alice(), 0));
+        contract.create_market(&default_market_args());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_GOVERNANCE_ADDRESS")]
+    fn add_collateral_not_gov() {
+        testing_env!(get_context(carol(), 0));
+        let mut contract = init_contract();
+        contract.add_to_collateral_whitelist(collateral_whitelist::Token{account_id: "usdc".to_string(), decimals: 6});
+    }
+
+    #[test]
+    fn total_withdrawable_fees_across_markets() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let first_market = seed_market_with_fees(&mut contract);
+        let second_market = seed_market_with_fees(&mut contract);
+
+        let first_fees: u128 = contract.get_fees_withdrawable(first_market, &carol()).into();
+        let second_fees: u128 = contract.get_fees_withdrawable(second_market, &carol()).into();
+        assert!(first_fees > 0 && second_fees > 0);
+
+        assert_eq!(contract.get_total_withdrawable_fees(&carol(), token(), U64(0), U64(10)), U128(first_fees + second_fees));
+        assert_eq!(contract.get_total_withdrawable_fees(&carol(), token(), U64(1), U64(10)), U128(second_fees));
+        assert_eq!(contract.get_total_withdrawable_fees(&carol(), "usdc".to_string(), U64(0), U64(10)), U128(0));
+    }
+
+    #[test]
+    fn all_spot_prices_balanced_pool() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcomes = 3;
+        args.outcome_tags = outcome_tags(3);
+        let market_id = contract.create_market(&args);
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1), U128(1)]));
+
+        let prices = contract.get_all_spot_prices_sans_fee(market_id);
+        assert_eq!(prices.len(), 3);
+        for (outcome, price) in prices.iter().enumerate() {
+            assert_eq!(*price, contract.get_spot_price_sans_fee(market_id, outcome as u16));
+        }
+
+        let sum: u128 = prices.iter().map(|price| u128::from(*price)).sum();
+        let denomination = 10_u128.pow(24);
+        assert!(sum <= denomination + 10 && sum + 10 >= denomination);
+
+        let prices_with_fee = contract.get_all_spot_prices(market_id);
+        for (outcome, price) in prices_with_fee.iter().enumerate() {
+            assert_eq!(*price, contract.get_spot_price(market_id, outcome as u16));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESOLUTION_HALTED")]
+    fn resolute_market_resolution_halted() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.halt_resolution();
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESOLUTION_HALTED")]
+    fn set_outcome_resolution_halted() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.halt_resolution();
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![market_id]));
+    }
+
+    #[test]
+    fn claim_earnings_during_resolution_halt() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        contract.halt_resolution();
+        assert!(u128::from(contract.get_claimable(market_id, &bob())) > 0);
+
+        contract.claim_earnings(market_id);
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(0));
+
+        contract.resume_resolution();
+        let second_market_id = seed_market_with_fees(&mut contract);
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(second_market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        assert!(contract.get_market_expect(second_market_id).finalized);
+    }
+
+    #[test]
+    fn buy_price_impact() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        let small = contract.calc_buy_price_impact(market_id, U128(10_u128.pow(18)), 1);
+        let large = contract.calc_buy_price_impact(market_id, U128(10_u128.pow(24)), 1);
+
+        let spot_price: u128 = small.spot_price.into();
+        assert_eq!(spot_price, contract.get_market_expect(market_id).pool.get_spot_price(1));
+        assert_eq!(large.spot_price, small.spot_price);
+        assert_eq!(large.effective_price, contract.get_avg_execution_price(market_id, U128(10_u128.pow(24)), 1));
+
+        assert!(u128::from(large.effective_price) > spot_price);
+        assert!(u64::from(large.impact_bps) > u64::from(small.impact_bps));
+        assert!(u64::from(small.impact_bps) <= 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_SHARES_OUT")]
+    fn buy_price_impact_without_shares_out() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        contract.calc_buy_price_impact(market_id, U128(0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_LIQUIDITY")]
+    fn buy_price_impact_unseeded_pool() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        contract.calc_buy_price_impact(market_id, U128(10_u128.pow(23)), 0);
+    }
+
+    #[test]
+    fn buy_before_deadline() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares_before = contract.get_share_balance(&bob(), market_id, 1);
+
+        testing_env!(get_context(token(), ms_to_ns(1000)));
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 1,
+                min_shares_out: U128(0),
+                deadline: Some(U64(1000))
+            }
+        );
+        assert!(contract.get_share_balance(&bob(), market_id, 1).0 > shares_before.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEADLINE_PASSED")]
+    fn buy_past_deadline() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(token(), ms_to_ns(1001)));
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 1,
+                min_shares_out: U128(0),
+                deadline: Some(U64(1000))
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEADLINE_PASSED")]
+    fn sell_past_deadline() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+
+        testing_env!(get_context(bob(), ms_to_ns(1001)));
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, Some(U64(1000)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DEADLINE_PASSED")]
+    fn add_liquidity_past_deadline() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(token(), ms_to_ns(1001)));
+        contract.add_liquidity(
+            &carol(),
+            10_u128.pow(24),
+            AddLiquidityArgs {
+                market_id,
+                weight_indication: None,
+                min_lp_tokens_out: None,
+                deadline: Some(U64(1000))
+            }
+        );
+    }
+
+    #[test]
+    fn calc_buy_amount_detailed_fee() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let collateral_in = 10_u128.pow(23);
+
+        let (shares_out, shares_out_sans_fee, fee) = contract.calc_buy_amount_detailed(market_id, U128(collateral_in), 1);
+
+        // 2% swap fee
+        assert_eq!(fee, U128(collateral_in / 50));
+        assert_eq!(shares_out, contract.calc_buy_amount(market_id, U128(collateral_in), 1));
+        assert!(shares_out_sans_fee.0 > shares_out.0);
+
+        // The extra shares are the fee swapped at the end of the trade, priced between the fee-free average price and 1
+        let avg_price = math::complex_div_u128(10_u128.pow(24), collateral_in, shares_out_sans_fee.0);
+        let fee_in_shares = math::complex_div_u128(10_u128.pow(24), fee.0, avg_price);
+        let diff = shares_out_sans_fee.0 - shares_out.0;
+        assert!(diff > fee.0 && diff < fee_in_shares);
+    }
+
+    #[test]
+    fn calc_buy_collateral_in_round_trip() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        for collateral_in in [10_u128.pow(20), 10_u128.pow(23), 5 * 10_u128.pow(24)].iter() {
+            let shares_out = contract.calc_buy_amount(market_id, U128(*collateral_in), 1);
+            let required = contract.calc_buy_collateral_in(market_id, shares_out, 1).0;
+
+            assert!(required <= *collateral_in);
+            assert!(contract.calc_buy_amount(market_id, U128(required), 1).0 >= shares_out.0);
+            assert!(contract.calc_buy_amount(market_id, U128(required - 1), 1).0 < shares_out.0);
+        }
+    }
+
+    #[test]
+    fn buy_exact_shares_refunds_unused_collateral() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares_out = U128(10_u128.pow(23));
+        let required = contract.calc_buy_collateral_in(market_id, shares_out, 1).0;
+
+        let res = contract.buy_exact_shares(
+            &bob(),
+            10_u128.pow(24),
+            BuyExactSharesArgs {
+                market_id,
+                outcome_target: 1,
+                shares_out,
+                max_collateral_in: U128(required),
+                deadline: None
+            }
+        );
+
+        match res {
+            PromiseOrValue::Value(unused) => assert_eq!(unused, U128(10_u128.pow(24) - required)),
+            PromiseOrValue::Promise(_) => panic!("expected a value")
+        }
+        assert!(contract.get_share_balance(&bob(), market_id, 1).0 >= shares_out.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MAX_COLLATERAL_IN")]
+    fn buy_exact_shares_above_max_collateral_in() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares_out = U128(10_u128.pow(23));
+        let required = contract.calc_buy_collateral_in(market_id, shares_out, 1).0;
+
+        contract.buy_exact_shares(
+            &bob(),
+            10_u128.pow(24),
+            BuyExactSharesArgs {
+                market_id,
+                outcome_target: 1,
+                shares_out,
+                max_collateral_in: U128(required - 1),
+                deadline: None
+            }
+        );
+    }
+
+    #[test]
+    fn force_invalid_after_oracle_timeout() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_oracle_timeout_ms(Some(U64(1000)));
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 1000)));
+        contract.force_invalid(market_id);
+
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, None);
+        assert_eq!(contract.get_resolution_state(market_id), ResolutionState::Invalid);
+
+        // Invalid markets refund the collateral bob spent
+        let claimable = contract.get_claimable(market_id, &bob());
+        assert!(claimable.0 > 0 && claimable.0 <= 10_u128.pow(23));
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 1000)));
+        contract.claim_earnings(market_id);
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ORACLE_TIMEOUT_NOT_REACHED")]
+    fn force_invalid_before_oracle_timeout() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_oracle_timeout_ms(Some(U64(1000)));
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 999)));
+        contract.force_invalid(market_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_ORACLE_TIMEOUT")]
+    fn force_invalid_without_oracle_timeout() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 1000)));
+        contract.force_invalid(market_id);
+    }
+
+    #[test]
+    fn active_collateral_tokens() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        assert_eq!(contract.get_active_collateral_tokens(), Vec::<AccountId>::new());
+
+        let first_market = seed_market_with_fees(&mut contract);
+        let second_market = seed_market_with_fees(&mut contract);
+        assert_eq!(contract.get_active_collateral_tokens(), vec![token()]);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(first_market, None);
+        assert_eq!(contract.get_active_collateral_tokens(), vec![token()]);
+
+        contract.resolute_market(second_market, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        assert_eq!(contract.get_active_collateral_tokens(), Vec::<AccountId>::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOO_FEW_OUTCOMES")]
+    fn create_market_single_outcome() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcomes = 1;
+        args.outcome_tags = vec!["YES".to_string()];
+        contract.create_market(&args);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DUPLICATE_TAG")]
+    fn create_market_duplicate_tags() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "YES".to_string()];
+        contract.create_market(&args);
+    }
+
+    #[test]
+    fn valid_scalar_tiny_range_answer() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.is_scalar = true;
+        args.outcome_tags = vec!["0".to_string(), "0.000000003".to_string()];
+        let market_id = contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0.000000001".to_string()), Some(vec![market_id]));
+
+        // 2/3 of the denomination, exact to the unit where f64 would be off by ~1e8
+        let market = contract.get_market_expect(market_id);
+        assert_eq!(market.payout_numerator, Some(vec![U128(666666666666666666666667), U128(333333333333333333333333)]));
+    }
+
+    #[test]
+    fn parse_fixed_point_decimals() {
+        assert_eq!(parse_fixed_point("-2.68", 3), Some(-2680));
+        assert_eq!(parse_fixed_point("10", 2), Some(1000));
+        assert_eq!(parse_fixed_point(".5", 1), Some(5));
+        assert_eq!(parse_fixed_point("2.68", 1), None);
+        assert_eq!(parse_fixed_point("1e5", 0), None);
+        assert_eq!(parse_fixed_point("-", 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FEE_TOO_HIGH")]
+    fn create_market_full_fee() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.swap_fee = U128(10_u128.pow(24));
+        contract.create_market(&args);
+    }
+
+    #[test]
+    fn create_market_at_max_fee() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_max_swap_fee_bps(3000);
+        assert_eq!(contract.get_max_swap_fee_bps(), 3000);
+
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.swap_fee = U128(3 * 10_u128.pow(23));
+        let market_id = contract.create_market(&args);
+
+        assert_eq!(contract.get_pool_swap_fee(market_id), U128(3 * 10_u128.pow(23)));
+    }
+
+    #[test]
+    fn round_trip_cost_grows_with_trade_size() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        let small_cost = contract.calc_round_trip_cost(market_id, U128(10_u128.pow(22)), 1).0;
+        let large_cost = contract.calc_round_trip_cost(market_id, U128(10_u128.pow(23)), 1).0;
+
+        // At least the 2% fee is paid on the way in
+        assert!(small_cost >= 10_u128.pow(22) / 50);
+        assert!(large_cost >= 10_u128.pow(23) / 50);
+        assert!(large_cost > small_cost);
+        assert!(large_cost < 10_u128.pow(23));
+    }
+
+    #[test]
+    fn set_swap_fee_before_seed() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_swap_fee(market_id, U128(10_u128.pow(24) / 100));
+
+        assert_eq!(contract.get_pool_swap_fee(market_id), U128(10_u128.pow(24) / 100));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_POOL_ALREADY_SEEDED")]
+    fn set_swap_fee_after_seed() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_swap_fee(market_id, U128(10_u128.pow(24) / 100));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FEE_TOO_HIGH")]
+    fn set_swap_fee_above_max() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_swap_fee(market_id, U128(10_u128.pow(24)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ANSWER_TYPE_MISMATCH")]
+    fn string_answer_scalar_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.is_scalar = true;
+        args.outcome_tags = vec!["0".to_string(), "10".to_string()];
+        let market_id = contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("YES".to_string()), Some(vec![market_id]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ANSWER_TYPE_MISMATCH")]
+    fn number_answer_categorical_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string()];
+        let market_id = contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("5".to_string()), Some(vec![market_id]));
+    }
+
+    #[test]
+    fn total_shares_reconcile_with_balances() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(23), None);
+
+        let total_shares = contract.get_total_shares(market_id);
+        let pool_balances = contract.get_market_expect(market_id).pool.get_pool_balances();
+        assert_eq!(total_shares.len(), 2);
+
+        for (outcome, total) in total_shares.iter().enumerate() {
+            let held: u128 = [bob(), carol()].iter()
+                .map(|account_id| contract.get_share_balance(account_id, market_id, outcome as u16).0)
+                .sum();
+            assert_eq!(total.0, pool_balances[outcome] + held);
+        }
+    }
+
+    fn create_bucketed_scalar_market(contract: &mut AMMContract) -> U64 {
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.is_scalar = true;
+        args.outcomes = 4;
+        args.outcome_tags = vec!["0".to_string(), "10".to_string(), "20".to_string(), "30".to_string()];
+        contract.create_market(&args)
+    }
+
+    #[test]
+    fn bucketed_scalar_answer_inside_segment() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_bucketed_scalar_market(&mut contract);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("12.5".to_string()), Some(vec![market_id]));
+
+        assert_eq!(contract.get_market_expect(market_id).payout_numerator, Some(vec![
+            U128(0),
+            U128(750000000000000000000000),
+            U128(250000000000000000000000),
+            U128(0)
+        ]));
+    }
+
+    #[test]
+    fn bucketed_scalar_answer_at_boundary() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_bucketed_scalar_market(&mut contract);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("20".to_string()), Some(vec![market_id]));
+
+        let payout_numerator = contract.get_market_expect(market_id).payout_numerator.unwrap();
+        let payout_numerator: Vec<u128> = payout_numerator.into_iter().map(u128::from).collect();
+        assert_eq!(payout_numerator, vec![0, 0, 1000000000000000000000000, 0]);
+    }
+
+    #[test]
+    fn bucketed_scalar_answer_out_of_range() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_bucketed_scalar_market(&mut contract);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("45".to_string()), Some(vec![market_id]));
+
+        let payout_numerator = contract.get_market_expect(market_id).payout_numerator.unwrap();
+        assert_eq!(payout_numerator[3], U128(1000000000000000000000000));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SCALAR_BOUNDS_NOT_INCREASING")]
+    fn create_scalar_market_decreasing_bounds() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.is_scalar = true;
+        args.outcomes = 3;
+        args.outcome_tags = vec!["0".to_string(), "20".to_string(), "10".to_string()];
+        contract.create_market(&args);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_POOL_CAP_EXCEEDED")]
+    fn add_liquidity_above_pool_cap() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.max_pool_collateral = Some(U128(15 * 10_u128.pow(23)));
+        let market_id = contract.create_market(&args);
+        enable_market(&mut contract, market_id);
+
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        assert_eq!(contract.get_market_expect(market_id).pool.get_collateral_locked(), 10_u128.pow(24));
+
+        // Up to the cap is accepted
+        seed_market(&mut contract, market_id, bob(), 5 * 10_u128.pow(23), None);
+        seed_market(&mut contract, market_id, bob(), 1, None);
+    }
+
+    #[test]
+    fn checked_span_extremes() {
+        assert_eq!(checked_span(i128::MIN, i128::MAX), Ok(u128::MAX));
+        assert_eq!(checked_span(-5, 5), Ok(10));
+        assert_eq!(checked_span(5, -5), Err("ERR_SCALAR_UNDERFLOW"));
+    }
+
+    #[test]
+    fn valid_scalar_bounds_spanning_i128() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.is_scalar = true;
+        args.outcome_tags = vec![
+            "-170141183460469231731687303715884105727".to_string(),
+            "170141183460469231731687303715884105727".to_string()
+        ];
+        let market_id = contract.create_market(&args);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![market_id]));
+
+        let market = contract.get_market_expect(market_id);
+        assert_eq!(market.payout_numerator, Some(vec![U128(5 * 10_u128.pow(23)), U128(5 * 10_u128.pow(23))]));
+    }
+
+    #[test]
+    fn valid_scalar_bounds_at_i128_max_over_multiplier() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.is_scalar = true;
+        // i128::MAX / 1e11 with 11 decimals, scaled by the 1e11 multiplier the upper bound is exactly i128::MAX
+        args.outcome_tags = vec!["0".to_string(), "1701411834604692317316873037.15884105727".to_string()];
+        let market_id = contract.create_market(&args);
+
+        // A quarter of the range, i128::MAX / 4 once scaled
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("425352958651173079329218259.28971026431".to_string()), Some(vec![market_id]));
+
+        let market = contract.get_market_expect(market_id);
+        assert_eq!(market.payout_numerator, Some(vec![U128(75 * 10_u128.pow(22)), U128(25 * 10_u128.pow(22))]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OUTCOME_ALREADY_SET")]
+    fn set_outcome_twice() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![market_id]));
+        assert_eq!(contract.get_market_counts().1, U64(1));
+        contract.set_outcome(alice(), Outcome::Answer("1".to_string()), Some(vec![market_id]));
+    }
+
+    #[test]
+    fn gov_override_categorical_outcome() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_resolved_market(&mut contract);
+
+        contract.gov_override_outcome(market_id, Some(vec![U128(0), U128(10_u128.pow(24))]));
+        let market = contract.get_market_expect(market_id);
+        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(10_u128.pow(24))]));
+        assert!(market.finalized);
+
+        contract.gov_override_outcome(market_id, None);
+        assert_eq!(contract.get_market_expect(market_id).payout_numerator, None);
+        assert_eq!(contract.get_market_counts(), (U64(1), U64(1), U64(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PAYOUT_SUM")]
+    fn gov_override_invalid_sum() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_resolved_market(&mut contract);
+        contract.gov_override_outcome(market_id, Some(vec![U128(1), U128(10_u128.pow(24))]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_NUMERATOR")]
+    fn gov_override_invalid_length() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_resolved_market(&mut contract);
+        contract.gov_override_outcome(market_id, Some(vec![U128(0), U128(0), U128(10_u128.pow(24))]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CLAIMS_STARTED")]
+    fn gov_override_after_claim() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        contract.claim_earnings(market_id);
+
+        contract.gov_override_outcome(market_id, Some(vec![U128(0), U128(1000000000000000000000000)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CLAIMS_STARTED")]
+    fn gov_override_after_batch_claim() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, Some(vec![U128(1000000000000000000000000), U128(0)]));
+        contract.internal_claim_earnings_batch(&bob(), &vec![market_id]);
+
+        contract.gov_override_outcome(market_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESOLUTION_HALTED")]
+    fn gov_override_during_resolution_halt() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_resolved_market(&mut contract);
+        contract.halt_resolution();
+        contract.gov_override_outcome(market_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_FINALIZED")]
+    fn gov_override_unresolved_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(bob(), 0));
+        contract.gov_override_outcome(market_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_GOVERNANCE_ADDRESS")]
+    fn gov_override_not_gov() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_resolved_market(&mut contract);
+
+        testing_env!(get_context(alice(), 0));
+        contract.gov_override_outcome(market_id, None);
+    }
+
+    #[test]
+    fn claim_invalid_market_refunds_spent_collateral() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, None);
+
+        // bob bought into a single outcome with 1e23 at a 2% fee, the fee went to the LP
+        let bob_claimable = contract.get_claimable(market_id, &bob()).0;
+        assert_eq!(bob_claimable, 10_u128.pow(23) - 10_u128.pow(23) / 50);
+        let carol_claimable = contract.get_claimable(market_id, &carol()).0;
+        assert!(bob_claimable + carol_claimable <= 10_u128.pow(24) + 10_u128.pow(23));
+
+        contract.claim_earnings(market_id);
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(0));
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000)));
+        contract.claim_earnings(market_id);
+        assert_eq!(contract.get_claimable(market_id, &carol()), U128(0));
+    }
+
+    #[test]
+    fn get_pool_info_matches_individual_views() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        let info = contract.get_pool_info(market_id);
+        assert_eq!(info.outcomes, 2);
+        assert_eq!(info.collateral_denomination, U128(10_u128.pow(24)));
+        assert_eq!(info.pool_balances, contract.get_pool_balances(market_id));
+        assert_eq!(info.pool_token_total_supply, contract.get_pool_token_total_supply(market_id));
+        assert_eq!(info.fee_pool_weight, contract.get_fee_pool_weight(market_id));
+        assert_eq!(info.swap_fee, contract.get_pool_swap_fee(market_id));
+    }
+
+    #[test]
+    fn get_user_market_position_aggregates_balances() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(24), None);
+
+        for outcome_target in 0..2 {
+            contract.buy(
+                &bob(),
+                10_u128.pow(23),
+                BuyArgs {
+                    market_id,
+                    outcome_target,
+                    min_shares_out: U128(0),
+                    deadline: None
+                }
+            );
+        }
+
+        let position = contract.get_user_market_position(market_id, &bob());
+        assert_eq!(position.share_balances, vec![
+            contract.get_share_balance(&bob(), market_id, 0),
+            contract.get_share_balance(&bob(), market_id, 1)
+        ]);
+        assert!(position.share_balances.iter().all(|balance| balance.0 > 0));
+        assert_eq!(position.pool_token_balance, contract.get_pool_token_balance(market_id, &bob()));
+        assert!(position.pool_token_balance.0 > 0);
+        assert_eq!(position.fees_withdrawable, contract.get_fees_withdrawable(market_id, &bob()));
+        assert!(position.fees_withdrawable.0 > 0);
+
+        let empty_position = contract.get_user_market_position(market_id, &oracle());
+        assert_eq!(empty_position.share_balances, vec![U128(0), U128(0)]);
+        assert_eq!(empty_position.pool_token_balance, U128(0));
+        assert_eq!(empty_position.fees_withdrawable, U128(0));
+    }
+
+    fn buy_outcomes(contract: &mut AMMContract, market_id: U64, outcomes: &[u16]) {
+        for outcome_target in outcomes {
+            contract.buy(
+                &bob(),
+                10_u128.pow(23),
+                BuyArgs {
+                    market_id,
+                    outcome_target: *outcome_target,
+                    min_shares_out: U128(0),
+                    deadline: None
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn calc_redeem_collateral_matches_redemption() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        buy_outcomes(&mut contract, market_id, &[1]);
+
+        let to_burn = contract.get_share_balance(&bob(), market_id, 0).0.min(contract.get_share_balance(&bob(), market_id, 1).0);
+        let preview = contract.calc_redeem_collateral(market_id, U128(to_burn), &bob());
+        assert!(preview.0 > 0 && preview.0 <= to_burn);
+
+        let mut market = contract.get_market_expect(market_id);
+        let escrowed = market.pool.burn_outcome_tokens_redeem_collateral(&bob(), to_burn);
+        assert_eq!(preview, U128(to_burn - escrowed));
+    }
+
+    #[test]
+    fn calc_redeem_collateral_short_on_outcome() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        // bob only holds outcome 0
+        let balance = contract.get_share_balance(&bob(), market_id, 0).0;
+        assert_eq!(contract.calc_redeem_collateral(market_id, U128(balance), &bob()), U128(0));
+
+        buy_outcomes(&mut contract, market_id, &[1]);
+        let short_balance = contract.get_share_balance(&bob(), market_id, 1).0.min(balance);
+        assert_eq!(contract.calc_redeem_collateral(market_id, U128(short_balance + 1), &bob()), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_COLLATERAL_OUT")]
+    fn redeem_collateral_below_minimum() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        buy_outcomes(&mut contract, market_id, &[1]);
+
+        let to_burn = contract.get_share_balance(&bob(), market_id, 0).0.min(contract.get_share_balance(&bob(), market_id, 1).0);
+        let preview = contract.calc_redeem_collateral(market_id, U128(to_burn), &bob());
+
+        testing_env!(get_context(bob(), 0));
+        contract.burn_outcome_tokens_redeem_collateral(market_id, U128(to_burn), Some(U128(preview.0 + 1)));
+    }
+
+    #[test]
+    fn market_volume_counts_buys_and_sells() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        assert_eq!(contract.get_market_volume(market_id), U128(10_u128.pow(23)));
+
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+        testing_env!(get_context(bob(), 0));
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, None);
+        assert_eq!(contract.get_market_volume(market_id), U128(11 * 10_u128.pow(22)));
+    }
+
+    #[test]
+    fn total_fees_collected_sums_swap_fees() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        buy_outcomes(&mut contract, market_id, &[1]);
+
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+        testing_env!(get_context(bob(), 0));
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, None);
+
+        let swap_fee = contract.get_pool_swap_fee(market_id).0;
+        let denomination = 10_u128.pow(24);
+        let expected_fees = [10_u128.pow(23), 10_u128.pow(23), 10_u128.pow(22)]
+            .iter()
+            .fold(0, |sum, amount| sum + math::complex_mul_u128(denomination, *amount, swap_fee));
+        assert_eq!(contract.get_total_fees_collected(market_id), U128(expected_fees));
+
+        // Withdrawing fees doesn't affect the lifetime total
+        testing_env!(get_context(carol(), 0));
+        contract.withdraw_fees(market_id);
+        assert_eq!(contract.get_total_fees_collected(market_id), U128(expected_fees));
+    }
+
+    #[test]
+    fn full_exit_pays_out_all_accrued_fees() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        let fees_withdrawable = contract.get_fees_withdrawable(market_id, &carol());
+        assert_eq!(fees_withdrawable, U128(10_u128.pow(23) / 50));
+
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol()).0;
+        let mut market = contract.get_market_expect(market_id);
+        let fees_earned = market.pool.exit_pool(&carol(), lp_tokens);
+        contract.markets.replace(market_id.into(), &market);
+        assert_eq!(fees_earned, fees_withdrawable.0);
+
+        // Nothing is left behind and the views don't divide by the now empty LP token supply
+        assert_eq!(contract.get_fees_withdrawable(market_id, &carol()), U128(0));
+        assert_eq!(contract.get_user_market_position(market_id, &carol()).fees_withdrawable, U128(0));
+        assert_eq!(contract.get_pool_token_total_supply(market_id), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_FEES")]
+    fn withdraw_fees_after_full_exit() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol());
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, lp_tokens);
+        contract.withdraw_fees(market_id);
+    }
+
+    #[test]
+    fn exit_pool_while_paused_with_withdrawals_allowed() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.pause();
+        contract.set_allow_withdrawals_while_paused(true);
+
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol());
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, lp_tokens);
+        assert_eq!(contract.get_pool_token_balance(market_id, &carol()), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROTCOL_PAUSED")]
+    fn buy_while_paused_with_withdrawals_allowed() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.pause();
+        contract.set_allow_withdrawals_while_paused(true);
+
+        testing_env!(get_context(token(), 0));
+        let msg = serde_json::json!({
+            "BuyArgs": {
+                "market_id": market_id,
+                "outcome_target": 1,
+                "min_shares_out": U128(0)
+            }
+        });
+        contract.ft_on_transfer(bob(), U128(10_u128.pow(23)), msg.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROTCOL_PAUSED")]
+    fn exit_pool_while_paused() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.pause();
+
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol());
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, lp_tokens);
+    }
+
+    fn create_scheduled_market(contract: &mut AMMContract, start_time: u64) -> U64 {
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.start_time = Some(U64(start_time));
+        let market_id = contract.create_market(&args);
+        enable_market(contract, market_id);
+        market_id
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_NOT_STARTED")]
+    fn add_liquidity_before_start_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_scheduled_market(&mut contract, 1000);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+    }
+
+    #[test]
+    fn trade_after_start_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_scheduled_market(&mut contract, 1000);
+
+        testing_env!(get_context(token(), ms_to_ns(1000)));
+        contract.add_liquidity(
+            &carol(),
+            10_u128.pow(24),
+            AddLiquidityArgs {
+                market_id,
+                weight_indication: Some(vec![U128(1), U128(1)]),
+                min_lp_tokens_out: None,
+                deadline: None
+            }
+        );
+        contract.buy(
+            &bob(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
+            }
+        );
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_NOT_STARTED")]
+    fn sell_before_start_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        // Scheduling an already traded market isn't possible through the API, this only checks `sell` respects the start time
+        let mut market = contract.get_market_expect(market_id);
+        market.start_time = Some(1000);
+        contract.markets.replace(market_id.into(), &market);
+
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+        testing_env!(get_context(bob(), 0));
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_START_TIME")]
+    fn create_market_start_after_end() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        create_scheduled_market(&mut contract, 1609951265967);
+    }
+
+    #[test]
+    fn extend_end_time_moves_resolution_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(bob(), 0));
+        contract.extend_end_time(market_id, U64(1619882574000 + 1000));
+
+        let market = contract.get_market_expect(market_id);
+        assert_eq!(market.end_time, 1619882574000 + 1000);
+        assert_eq!(market.resolution_time, 1619882574000 + 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_END_TIME_NOT_EXTENDED")]
+    fn extend_end_time_shortening() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(bob(), 0));
+        contract.extend_end_time(market_id, U64(1609951265967 - 1));
+    }
+
+    #[test]
+    fn cancel_market_refunds_once() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.cancel_market(market_id);
+        let market = contract.get_market(market_id);
+        assert!(market.cancelled && market.finalized);
+        assert_eq!(market.payout_numerator, None);
+
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(10_u128.pow(23) - 10_u128.pow(23) / 50));
+        contract.claim_refund(market_id);
+        assert_eq!(contract.get_claimable(market_id, &bob()), U128(0));
+
+        testing_env!(get_context(carol(), 0));
+        assert!(contract.get_claimable(market_id, &carol()).0 > 0);
+        contract.claim_refund(market_id);
+        assert_eq!(contract.get_claimable(market_id, &carol()), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_PAYOUT")]
+    fn claim_refund_twice() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.cancel_market(market_id);
+        contract.claim_refund(market_id);
+        // The refund's transfer resolves and releases the payout lock
+        contract.unlock_account_market(&bob(), market_id.into());
+        contract.claim_refund(market_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FINALIZED_MARKET")]
+    fn buy_after_cancel() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.cancel_market(market_id);
+        buy_outcomes(&mut contract, market_id, &[1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MARKET_NOT_CANCELLED")]
+    fn claim_refund_not_cancelled() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+        contract.resolute_market(market_id, None);
+        contract.claim_refund(market_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_LIQUIDITY")]
+    fn buy_unseeded_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+
+        testing_env!(get_context(token(), 0));
+        buy_outcomes(&mut contract, market_id, &[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_LIQUIDITY")]
+    fn calc_buy_amount_unseeded_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        contract.calc_buy_amount(market_id, U128(10_u128.pow(23)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_INITIAL_LIQUIDITY")]
+    fn seed_below_min_initial_liquidity() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_min_initial_liquidity(U128(10_u128.pow(24)));
+
+        testing_env!(get_context(alice(), 0));
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24) - 1, Some(vec![U128(1), U128(1)]));
+    }
+
+    #[test]
+    fn seed_at_min_initial_liquidity() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_min_initial_liquidity(U128(10_u128.pow(24)));
+        assert_eq!(contract.get_min_initial_liquidity(), U128(10_u128.pow(24)));
+
+        testing_env!(get_context(alice(), 0));
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        // Only the first addition is constrained
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(20), None);
+        assert!(contract.get_pool_token_balance(market_id, &bob()).0 > 0);
+    }
+
+    #[test]
+    fn pool_token_transfer_moves_future_fees() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol()).0;
+
+        // carol's fees from bob's first buy are settled by the transfer, the deposit covers the storage of bob's new LP position
+        let mut context = get_context(carol(), 0);
+        context.attached_deposit = 10_u128.pow(23);
+        testing_env!(context);
+        contract.pool_token_transfer(market_id, bob().try_into().unwrap(), U128(lp_tokens / 2), Some("half".to_string()));
+        assert_eq!(contract.get_pool_token_balance(market_id, &bob()), U128(lp_tokens / 2));
+        assert_eq!(contract.get_fees_withdrawable(market_id, &carol()), U128(0));
+        assert_eq!(contract.get_fees_withdrawable(market_id, &bob()), U128(0));
+        assert_eq!(contract.get_lp_count(market_id), U64(2));
+
+        // Fees of the next 2% swap are split evenly between both halves of the position
+        testing_env!(get_context(token(), 0));
+        buy_outcomes(&mut contract, market_id, &[1]);
+        let fee = 10_u128.pow(23) / 50;
+        let carol_fees = contract.get_fees_withdrawable(market_id, &carol()).0;
+        let bob_fees = contract.get_fees_withdrawable(market_id, &bob()).0;
+        assert_eq!(carol_fees, bob_fees);
+        assert!(fee - (carol_fees + bob_fees) <= 2);
     }
 
+    #[test]
+    #[should_panic(expected = "ERR_SELF_TRANSFER")]
+    fn pool_token_transfer_to_self() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
 
-    /**
-     * @notice buy an outcome token
-     * @param sender the sender of the original transfer_call
-     * @param total_in total amount of collateral to use for purchasing
-     * @param json string of `AddLiquidity` args
-     */
-    pub fn buy(
-        &mut self,
-        sender: &AccountId,
-        collateral_in: u128, 
-        args: BuyArgs,
-    ) -> PromiseOrValue<U128> {
-        let mut market = self.markets.get(args.market_id.into()).expect("ERR_NO_MARKET");
-        assert!(market.enabled, "ERR_DISABLED_MARKET");
-        assert!(!market.finalized, "ERR_FINALIZED_MARKET");
-        assert!(market.end_time > ns_to_ms(env::block_timestamp()), "ERR_MARKET_ENDED");
-        assert_collateral_token(&market.pool.collateral_token_id);
-        
-        market.pool.buy(
-            &sender,
-            collateral_in,
-            args.outcome_target,
-            args.min_shares_out.into()
-        );
+        testing_env!(get_context(carol(), 0));
+        contract.pool_token_transfer(market_id, carol().try_into().unwrap(), U128(1), None);
+    }
 
-        self.markets.replace(args.market_id.into(), &market);
-        PromiseOrValue::Value(0.into())
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_BALANCE")]
+    fn pool_token_transfer_more_than_balance() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol()).0;
+
+        testing_env!(get_context(carol(), 0));
+        contract.pool_token_transfer(market_id, bob().try_into().unwrap(), U128(lp_tokens + 1), None);
     }
-}
 
-#[cfg(not(target_arch = "wasm32"))]
-#[cfg(test)]
-mod market_basic_tests {
-    use std::convert::TryInto;
-    use near_sdk::{ MockedBlockchain };
-    use near_sdk::{ testing_env, VMContext };
-    use super::*;
+    #[test]
+    fn outcome_token_transfer_moves_shares() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares = contract.get_share_balance(&bob(), market_id, 0).0;
 
-    fn alice() -> AccountId {
-        "alice.near".to_string()
+        testing_env!(get_context(bob(), 0));
+        contract.outcome_token_transfer(market_id, 0, carol().try_into().unwrap(), U128(shares / 2));
+        assert_eq!(contract.get_share_balance(&bob(), market_id, 0), U128(shares - shares / 2));
+        assert_eq!(contract.get_share_balance(&carol(), market_id, 0), U128(shares / 2));
+
+        // The receiver can sell the shares it received
+        testing_env!(get_context(carol(), 0));
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, U128(shares / 2), None);
+        assert!(contract.get_share_balance(&carol(), market_id, 0).0 < shares / 2);
     }
 
-    fn bob() -> AccountId {
-        "bob.near".to_string()
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_SHARES")]
+    fn outcome_token_transfer_more_than_balance() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares = contract.get_share_balance(&bob(), market_id, 0).0;
+
+        testing_env!(get_context(bob(), 0));
+        contract.outcome_token_transfer(market_id, 0, carol().try_into().unwrap(), U128(shares + 1));
     }
 
-    fn token() -> AccountId {
-        "token.near".to_string()
+    #[test]
+    fn registered_account_pays_storage_from_balance() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let lp_tokens = contract.get_pool_token_balance(market_id, &carol()).0;
+        let deposit = 10_u128.pow(23);
+
+        let mut context = get_context(carol(), 0);
+        context.attached_deposit = deposit;
+        testing_env!(context);
+        contract.storage_deposit(None);
+
+        // bob's new LP accounting is paid for from carol's storage balance, no deposit is attached
+        let mut context = get_context(carol(), 0);
+        context.attached_deposit = 0;
+        testing_env!(context);
+        let initial_storage = env::storage_usage();
+        contract.pool_token_transfer(market_id, bob().try_into().unwrap(), U128(lp_tokens / 2), None);
+        let storage_cost = u128::from(env::storage_usage() - initial_storage) * crate::storage_manager::STORAGE_PRICE_PER_BYTE;
+
+        let balance = contract.storage_balance_of(carol().try_into().unwrap()).unwrap();
+        assert_eq!(balance.total, U128(deposit));
+        assert_eq!(balance.available, U128(deposit - storage_cost));
     }
 
-    fn oracle() -> AccountId {
-        "oracle.near".to_string()
+    #[test]
+    fn registered_account_pays_trade_storage_from_balance() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let deposit = 10_u128.pow(23);
+
+        let mut context = get_context(carol(), 0);
+        context.attached_deposit = deposit;
+        testing_env!(context);
+        contract.storage_deposit(None);
+
+        // carol's first buy creates her share balance and escrow account
+        testing_env!(get_context(token(), 0));
+        let initial_storage = env::storage_usage();
+        let msg = serde_json::json!({
+            "BuyArgs": {
+                "market_id": market_id,
+                "outcome_target": 1,
+                "min_shares_out": U128(0)
+            }
+        });
+        contract.ft_on_transfer(carol(), U128(10_u128.pow(23)), msg.to_string());
+        let storage_cost = u128::from(env::storage_usage() - initial_storage) * crate::storage_manager::STORAGE_PRICE_PER_BYTE;
+
+        let balance = contract.storage_balance_of(carol().try_into().unwrap()).unwrap();
+        assert_eq!(balance.total, U128(deposit));
+        assert_eq!(balance.available, U128(deposit - storage_cost));
     }
 
-    fn empty_string() -> String {
-        "".to_string()
+    #[test]
+    fn calc_trade_quotes_with_fee() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let collateral = 10_u128.pow(23);
+
+        let buy_quote = contract.calc_buy_amount_with_fee(market_id, U128(collateral), 1);
+        assert_eq!(buy_quote.fee_paid.0 + buy_quote.collateral_after_fee.0, collateral);
+        assert_eq!(buy_quote.fee_paid, U128(collateral / 50));
+        assert_eq!(buy_quote.shares_out, contract.calc_buy_amount(market_id, U128(collateral), 1));
+
+        let sell_quote = contract.calc_sell_collateral_out_with_fee(market_id, U128(collateral / 10), 0);
+        assert_eq!(sell_quote.fee_paid, U128(collateral / 500));
+        assert_eq!(sell_quote.collateral_out, U128(collateral / 10));
+        assert_eq!(sell_quote.shares_in, contract.calc_sell_collateral_out(market_id, U128(collateral / 10), 0));
     }
 
-    fn empty_string_vec(len: u16) -> Vec<String> {
-        let mut tags: Vec<String> = vec![];
-        for _i in 0..len {
-            tags.push(empty_string());
+    #[test]
+    fn sample_buy_curve_is_increasing_and_concave() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let step = 10_u128.pow(23);
+        let amounts: Vec<WrappedBalance> = (1..=4).map(|i| U128(step * i)).collect();
+
+        let shares: Vec<u128> = contract.sample_buy_curve(market_id, 1, amounts.clone())
+            .into_iter()
+            .map(u128::from)
+            .collect();
+        assert_eq!(shares[0], contract.calc_buy_amount(market_id, amounts[0], 1).0);
+
+        // Every equal step in collateral buys more, but fewer additional, shares than the previous one
+        for i in 1..shares.len() {
+            assert!(shares[i] > shares[i - 1]);
+        }
+        for i in 2..shares.len() {
+            assert!(shares[i] - shares[i - 1] < shares[i - 1] - shares[i - 2]);
         }
-        tags
     }
 
-    fn get_context(predecessor_account_id: AccountId, timestamp: u64) -> VMContext {
-        VMContext {
-            current_account_id: alice(),
-            signer_account_id: alice(),
-            signer_account_pk: vec![0, 1, 2],
-            predecessor_account_id,
-            input: vec![],
-            block_index: 0,
-            block_timestamp: timestamp,
-            account_balance: 1000 * 10u128.pow(24),
-            account_locked_balance: 0,
-            storage_usage: 10u64.pow(6),
-            attached_deposit: 33400000000000000000000,
-            prepaid_gas: 10u64.pow(18),
-            random_seed: vec![0, 1, 2],
-            is_view: false,
-            output_data_receivers: vec![],
-            epoch_height: 0,
-        }
+    #[test]
+    #[should_panic(expected = "ERR_TOO_MANY_SAMPLES")]
+    fn sample_buy_curve_too_many_amounts() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        contract.sample_buy_curve(market_id, 1, vec![U128(10_u128.pow(20)); constants::MAX_CURVE_SAMPLES + 1]);
+    }
+
+    fn create_seeded_market_with_pricing(contract: &mut AMMContract, pricing_mode: PricingMode, weights: Vec<U128>) -> U64 {
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.pricing_mode = Some(pricing_mode);
+        let market_id = contract.create_market(&args);
+        enable_market(contract, market_id);
+        seed_market(contract, market_id, carol(), 10_u128.pow(24), Some(weights));
+        market_id
     }
 
     #[test]
-    fn basic_create_market() {
+    fn lmsr_spot_price_compared_to_constant_product() {
         testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let lmsr = PricingMode::Lmsr { b: U128(10_u128.pow(24)) };
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
+        // Identical balances price identically in both modes
+        let even_cp = create_seeded_market_with_pricing(&mut contract, PricingMode::ConstantProduct, vec![U128(1), U128(1)]);
+        let even_lmsr = create_seeded_market_with_pricing(&mut contract, lmsr, vec![U128(1), U128(1)]);
+        assert_eq!(contract.get_spot_price_sans_fee(even_cp, 0), U128(10_u128.pow(24) / 2));
+        assert_eq!(contract.get_spot_price_sans_fee(even_lmsr, 0), U128(10_u128.pow(24) / 2));
 
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: empty_string_vec(2), // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: false, // is_scalar,
-            }
-        );
+        // Pool balances of [0.5, 1]: 2/3 under constant product and 1 / (1 + e^-0.5) under LMSR
+        let uneven_cp = create_seeded_market_with_pricing(&mut contract, PricingMode::ConstantProduct, vec![U128(1), U128(2)]);
+        let uneven_lmsr = create_seeded_market_with_pricing(&mut contract, lmsr, vec![U128(1), U128(2)]);
+        assert_eq!(contract.get_pool_balances(uneven_cp), contract.get_pool_balances(uneven_lmsr));
+
+        let cp_price = contract.get_spot_price_sans_fee(uneven_cp, 0).0;
+        let lmsr_price = contract.get_spot_price_sans_fee(uneven_lmsr, 0).0;
+        let expected_lmsr_price = (1e24 / (1.0 + (-0.5_f64).exp())) as u128;
+        assert!(cp_price > lmsr_price);
+        assert!(cp_price.max(2 * 10_u128.pow(24) / 3) - cp_price.min(2 * 10_u128.pow(24) / 3) <= 1);
+        assert!(lmsr_price.max(expected_lmsr_price) - lmsr_price.min(expected_lmsr_price) < 10_u128.pow(12));
+        let lmsr_sum = lmsr_price + contract.get_spot_price_sans_fee(uneven_lmsr, 1).0;
+        assert!(lmsr_sum.max(10_u128.pow(24)) - lmsr_sum.min(10_u128.pow(24)) <= 2);
     }
 
     #[test]
-    #[should_panic(expected = "ERR_MARKET_ENDED")]
-    fn add_liquidity_after_resolution() {
+    fn lmsr_buy_and_sell() {
         testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let b = 10_u128.pow(24);
+        let market_id = create_seeded_market_with_pricing(&mut contract, PricingMode::Lmsr { b: U128(b) }, vec![U128(1), U128(1)]);
+        let price_before = contract.get_spot_price_sans_fee(market_id, 0).0;
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
+        // Buying 0.098 after the 2% fee from even odds: shares_out = b * ln(1 + 2 * (e^(0.098) - 1))
+        let collateral_in = 10_u128.pow(23);
+        let expected_shares_out = (1e24 * (1.0 + 2.0 * (0.098_f64.exp() - 1.0)).ln()) as u128;
+        let shares_out = contract.calc_buy_amount(market_id, U128(collateral_in), 0).0;
+        assert!(shares_out.max(expected_shares_out) - shares_out.min(expected_shares_out) < 10_u128.pow(12));
 
-        let market_id = contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: empty_string_vec(2), // outcome tags
-                categories: empty_string_vec(2), // categories
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: false // is_scalar
+        testing_env!(get_context(token(), 0));
+        contract.buy(
+            &bob(),
+            collateral_in,
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
             }
         );
+        assert_eq!(contract.get_share_balance(&bob(), market_id, 0), U128(shares_out));
+        assert!(contract.get_spot_price_sans_fee(market_id, 0).0 > price_before);
 
-        let mut market = contract.get_market_expect(U64(0));
-        market.enabled = true;
-        contract.markets.replace(0, &market);
+        // Selling back part of the position costs fewer shares than were bought
+        testing_env!(get_context(bob(), 0));
+        contract.sell(market_id, U128(collateral_in / 2), 0, U128(shares_out), None);
+        let shares_left = contract.get_share_balance(&bob(), market_id, 0).0;
+        assert!(shares_left > 0 && shares_left < shares_out);
+    }
 
-        testing_env!(get_context(token(), ms_to_ns(1619882574000)));
+    fn create_market_with_fee_schedule(contract: &mut AMMContract, start_fee: u128, end_fee: u128) -> U64 {
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.fee_schedule = Some(FeeSchedule {
+            start_fee: U128(start_fee),
+            end_fee: U128(end_fee)
+        });
+        let market_id = contract.create_market(&args);
+        enable_market(contract, market_id);
+        market_id
+    }
 
-        let add_liquidity_args = AddLiquidityArgs {
-            market_id,
-            weight_indication: Some(vec![U128(2), U128(1)])
-        };
+    #[test]
+    fn fee_schedule_interpolates_toward_end_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let start_fee = 10_u128.pow(24) / 100;
+        let end_fee = 10_u128.pow(24) / 25;
+        let market_id = create_market_with_fee_schedule(&mut contract, start_fee, end_fee);
+        let end_time = contract.get_market(market_id).end_time.0;
 
-        contract.add_liquidity(
-            &alice(), // sender
-            10000000000000000000, // total_in
-            add_liquidity_args
-        );
+        assert_eq!(contract.get_pool_swap_fee(market_id), U128(start_fee));
+
+        testing_env!(get_context(alice(), ms_to_ns(end_time / 2)));
+        let fee_halfway = contract.get_pool_swap_fee(market_id).0;
+        let expected_fee_halfway = start_fee + (end_fee - start_fee) / 2;
+        assert!(fee_halfway.max(expected_fee_halfway) - fee_halfway.min(expected_fee_halfway) < 10_u128.pow(12));
+
+        testing_env!(get_context(alice(), ms_to_ns(end_time - 1)));
+        let fee_near_end = contract.get_pool_swap_fee(market_id).0;
+        assert!(fee_near_end < end_fee && end_fee - fee_near_end < 10_u128.pow(12));
+
+        testing_env!(get_context(alice(), ms_to_ns(end_time)));
+        assert_eq!(contract.get_pool_swap_fee(market_id), U128(end_fee));
     }
 
     #[test]
-    #[should_panic(expected = "ERR_INVALID_RESOLUTION_TIME")]
-    fn invalid_resolution_time() {
+    fn fee_schedule_applies_to_buys() {
         testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let start_fee = 10_u128.pow(24) / 100;
+        let end_fee = 10_u128.pow(24) / 25;
+        let market_id = create_market_with_fee_schedule(&mut contract, start_fee, end_fee);
+        let end_time = contract.get_market(market_id).end_time.0;
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
+        // Halfway through trading the fee is about 2.5%
+        let collateral_in = 10_u128.pow(23);
+        testing_env!(get_context(token(), ms_to_ns(end_time / 2)));
+        contract.buy(
+            &bob(),
+            collateral_in,
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
+            }
         );
+        let fees_collected = contract.get_total_fees_collected(market_id).0;
+        assert!(fees_collected.max(collateral_in / 40) - fees_collected.min(collateral_in / 40) < 10_u128.pow(12));
+    }
 
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: empty_string_vec(2), // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                resolution_time: 1609951265965.into(), // resolution_time (~1 day after end_time)
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: false // is_scalar
+    #[test]
+    #[should_panic(expected = "ERR_FEE_SCHEDULE_SET")]
+    fn set_swap_fee_with_fee_schedule() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_market_with_fee_schedule(&mut contract, 10_u128.pow(24) / 100, 10_u128.pow(24) / 25);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_swap_fee(market_id, U128(10_u128.pow(24) / 50));
+    }
+
+    fn create_market_with_bet_bounds(contract: &mut AMMContract) -> U64 {
+        testing_env!(get_context(alice(), 0));
+        let mut args = default_market_args();
+        args.min_bet = Some(U128(10_u128.pow(22)));
+        args.max_bet = Some(U128(10_u128.pow(23)));
+        let market_id = contract.create_market(&args);
+        enable_market(contract, market_id);
+        // Bet bounds don't apply to liquidity
+        seed_market(contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        market_id
+    }
+
+    fn buy_outcome_zero(contract: &mut AMMContract, market_id: U64, collateral_in: u128) {
+        testing_env!(get_context(token(), 0));
+        contract.buy(
+            &bob(),
+            collateral_in,
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
             }
         );
     }
 
     #[test]
-    fn invalid_outcome() {
-        testing_env!(get_context(oracle(), 0));
+    fn buy_within_bet_bounds() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_market_with_bet_bounds(&mut contract);
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(22));
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(23));
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 > 0);
+    }
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: empty_string_vec(2), // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: false, // is_scalar,
+    #[test]
+    #[should_panic(expected = "ERR_BET_TOO_SMALL")]
+    fn buy_below_min_bet() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_market_with_bet_bounds(&mut contract);
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(22) - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BET_TOO_LARGE")]
+    fn buy_above_max_bet() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_market_with_bet_bounds(&mut contract);
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(23) + 1);
+    }
+
+    fn create_private_market(contract: &mut AMMContract) -> U64 {
+        testing_env!(get_context(alice(), 0));
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(contract, market_id);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_market_allowlist(market_id, Some(vec![carol().try_into().unwrap()]));
+        market_id
+    }
+
+    #[test]
+    fn allowlisted_account_trades_private_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_private_market(&mut contract);
+        assert!(contract.get_market(market_id).has_trader_allowlist);
+        assert!(contract.is_allowlisted(market_id, carol().try_into().unwrap()));
+        assert!(!contract.is_allowlisted(market_id, bob().try_into().unwrap()));
+
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        testing_env!(get_context(token(), 0));
+        contract.buy(
+            &carol(),
+            10_u128.pow(23),
+            BuyArgs {
+                market_id,
+                outcome_target: 0,
+                min_shares_out: U128(0),
+                deadline: None
             }
         );
+        assert!(contract.get_share_balance(&carol(), market_id, 0).0 > 0);
 
-        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![U64(0)]));
+        // Making the market public again lets anyone trade
+        testing_env!(get_context(bob(), 0));
+        contract.set_market_allowlist(market_id, None);
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(23));
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 > 0);
+    }
 
-        let market = contract.get_market_expect(U64(0));
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWLISTED")]
+    fn stranger_buys_private_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_private_market(&mut contract);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(23));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWLISTED")]
+    fn stranger_adds_liquidity_to_private_market() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_private_market(&mut contract);
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+    }
+
+    #[test]
+    fn replaced_allowlist_drops_previous_accounts() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_private_market(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_market_allowlist(market_id, Some(vec![alice().try_into().unwrap()]));
+        assert!(contract.is_allowlisted(market_id, alice().try_into().unwrap()));
+        assert!(!contract.is_allowlisted(market_id, carol().try_into().unwrap()));
+
+        contract.set_market_allowlist(market_id, None);
+        contract.set_market_allowlist(market_id, Some(vec![bob().try_into().unwrap()]));
+        assert!(contract.is_allowlisted(market_id, bob().try_into().unwrap()));
+        assert!(!contract.is_allowlisted(market_id, alice().try_into().unwrap()));
+        assert!(!contract.is_allowlisted(market_id, carol().try_into().unwrap()));
+    }
+
+    fn blacklist_account(contract: &mut AMMContract, account_id: AccountId) {
+        testing_env!(get_context(bob(), 0));
+        contract.add_to_blacklist(account_id.try_into().unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_buy() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        blacklist_account(&mut contract, bob());
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(23));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_sell() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        let shares = contract.get_share_balance(&bob(), market_id, 0);
+        blacklist_account(&mut contract, bob());
+        contract.sell(market_id, U128(10_u128.pow(22)), 0, shares, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_add_liquidity() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        blacklist_account(&mut contract, bob());
+        seed_market(&mut contract, market_id, bob(), 10_u128.pow(24), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_exit_pool() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        blacklist_account(&mut contract, carol());
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, U128(10_u128.pow(23)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_redeem() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        blacklist_account(&mut contract, carol());
+        testing_env!(get_context(carol(), 0));
+        contract.burn_outcome_tokens_redeem_collateral(market_id, U128(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_withdraw_fees() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        blacklist_account(&mut contract, carol());
+        testing_env!(get_context(carol(), 0));
+        contract.withdraw_fees(market_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLACKLISTED")]
+    fn blacklisted_claim_earnings() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_resolved_market(&mut contract);
+        contract.add_to_blacklist(carol().try_into().unwrap());
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000)));
+        contract.claim_earnings(market_id);
+    }
+
+    #[test]
+    fn removed_from_blacklist_regains_access() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        blacklist_account(&mut contract, bob());
+        assert!(contract.is_blacklisted(bob().try_into().unwrap()));
 
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, None, "Numerator should be None");
+        contract.remove_from_blacklist(bob().try_into().unwrap());
+        assert!(!contract.is_blacklisted(bob().try_into().unwrap()));
+        let shares_before = contract.get_share_balance(&bob(), market_id, 0).0;
+        buy_outcome_zero(&mut contract, market_id, 10_u128.pow(23));
+        assert!(contract.get_share_balance(&bob(), market_id, 0).0 > shares_before);
     }
 
     #[test]
-    fn valid_categorical_outcome() {
+    fn scalar_payout_numerator_sums_to_denomination() {
         testing_env!(get_context(oracle(), 0));
-
         let mut contract = AMMContract::init(
             bob().try_into().unwrap(),
             vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
             oracle().try_into().unwrap()
         );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: vec!["YES".to_string(), "NO".to_string()], // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: false, // is_scalar,
-            }
-        );
-
-        contract.set_outcome(alice(), Outcome::Answer("NO".to_string()), Some(vec![U64(0)]));
 
+        let mut args = default_market_args();
+        args.outcomes = 3;
+        args.outcome_tags = vec!["-7".to_string(), "3".to_string(), "1000".to_string()];
+        args.categories = empty_string_vec(3);
+        args.is_scalar = true;
+        contract.create_market(&args);
         let market = contract.get_market_expect(U64(0));
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(1000000000000000000000000)]), "Numerator should be set");
+
+        for answer in &["-7", "-6.999999", "0.333333333", "3", "641.123456789", "1000", "99999"] {
+            let payout_numerator = market.calc_payout_numerator(&Outcome::Answer(answer.to_string())).unwrap().unwrap();
+            let sum = payout_numerator.iter().fold(0, |s, &n| s + u128::from(n));
+            assert_eq!(sum, market.pool.collateral_denomination, "numerator of {} doesn't sum to the denomination", answer);
+            assert_eq!(market.check_payout_numerator_sum(&payout_numerator), Ok(()));
+        }
     }
 
     #[test]
-    fn valid_scalar_large_range() {
+    fn invalid_payout_numerator_sum_is_rejected() {
         testing_env!(get_context(oracle(), 0));
-
         let mut contract = AMMContract::init(
             bob().try_into().unwrap(),
             vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
             oracle().try_into().unwrap()
         );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: vec!["50000000000".to_string(), "150000000000".to_string()], // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: true, // is_scalar,
-            }
-        );
+        contract.create_market(&default_market_args());
+        let market = contract.get_market_expect(U64(0));
+        let denomination = market.pool.collateral_denomination;
 
-        contract.set_outcome(alice(), Outcome::Answer("70369216342".to_string()), Some(vec![U64(0)]));
+        assert_eq!(market.check_payout_numerator_sum(&[U128(denomination / 2), U128(denomination / 2 - 1)]), Err("ERR_INVALID_PAYOUT_SUM"));
+        assert_eq!(market.check_payout_numerator_sum(&[U128(denomination)]), Err("ERR_INVALID_NUMERATOR"));
+        assert_eq!(market.check_payout_numerator_sum(&[U128(u128::MAX), U128(denomination)]), Err("ERR_INVALID_PAYOUT_SUM"));
+        assert_eq!(market.check_payout_numerator_sum(&[U128(0), U128(denomination)]), Ok(()));
+    }
 
-        let market = contract.get_market_expect(U64(0));
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, Some(vec![U128(796307836580000000000000), U128(203692163420000000000000)]), "Numerator should be set");
+    fn create_optimistic_market(contract: &mut AMMContract) -> U64 {
+        testing_env!(get_context(bob(), 0));
+        contract.set_optimistic_resolution(true);
+        let mut args = default_market_args();
+        args.challenge_period = U64(ms_to_ns(3600000));
+        let market_id = contract.create_market(&args);
+        enable_market(contract, market_id);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![market_id]));
+        market_id
     }
 
     #[test]
-    fn valid_scalar_complex_floating_answer() {
+    fn unchallenged_outcome_finalizes_after_challenge_period() {
         testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: vec!["0".to_string(), "10".to_string()], // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: true, // is_scalar,
-            }
-        );
+        let market = contract.get_market_expect(market_id);
+        assert!(!market.finalized);
+        let proposed_outcome = contract.get_proposed_outcome(market_id).unwrap();
+        assert_eq!(proposed_outcome.proposer, oracle());
+        assert_eq!(proposed_outcome.payout_numerator, Some(vec![U128(10_u128.pow(24)), U128(0)]));
+        assert_eq!(proposed_outcome.challenge, None);
 
-        contract.set_outcome(alice(), Outcome::Answer("2.68".to_string()), Some(vec![U64(0)]));
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 3600000)));
+        contract.finalize_after_challenge(market_id);
 
-        let market = contract.get_market_expect(U64(0));
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, Some(vec![U128(732000000000000000000000), U128(268000000000000000000000)]), "Numerator should be set");
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, Some(vec![U128(10_u128.pow(24)), U128(0)]));
     }
 
     #[test]
-    fn valid_scalar_floating_answer() {
+    #[should_panic(expected = "ERR_CHALLENGE_PERIOD_ACTIVE")]
+    fn finalize_after_challenge_within_challenge_period() {
         testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: vec!["0".to_string(), "5".to_string()], // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: true, // is_scalar,
-            }
-        );
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 3599999)));
+        contract.finalize_after_challenge(market_id);
+    }
 
-        contract.set_outcome(alice(), Outcome::Answer("2.5".to_string()), Some(vec![U64(0)]));
+    #[test]
+    fn successful_challenge_rejects_proposed_outcome() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
 
-        let market = contract.get_market_expect(U64(0));
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, Some(vec![U128(500000000000000000000000), U128(500000000000000000000000)]), "Numerator should be set");
+        let challenged_numerator = Some(vec![U128(0), U128(10_u128.pow(24))]);
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 1000)));
+        contract.challenge_outcome(market_id, challenged_numerator.clone());
+
+        let challenge = contract.get_proposed_outcome(market_id).unwrap().challenge.unwrap();
+        assert_eq!(challenge.challenger, carol());
+        assert_eq!(challenge.payout_numerator, challenged_numerator);
+        assert_eq!(challenge.bond, U128(33400000000000000000000));
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 2000)));
+        contract.resolute_market(market_id, challenged_numerator.clone());
+
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, challenged_numerator);
+        assert_eq!(market.challenge_settlement(), Some((carol(), 33400000000000000000000, true)));
     }
 
     #[test]
-    fn valid_scalar_outcome_price_over_lower_bound() {
+    fn failed_challenge_pays_bond_to_proposer() {
         testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: vec!["0".to_string(), "50".to_string()], // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: true, // is_scalar,
-            }
-        );
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 1000)));
+        contract.challenge_outcome(market_id, Some(vec![U128(0), U128(10_u128.pow(24))]));
 
-        contract.set_outcome(alice(), Outcome::Answer("-44".to_string()), Some(vec![U64(0)]));
+        let proposed_numerator = Some(vec![U128(10_u128.pow(24)), U128(0)]);
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 2000)));
+        contract.resolute_market(market_id, proposed_numerator.clone());
 
-        let market = contract.get_market_expect(U64(0));
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, Some(vec![U128(1000000000000000000000000), U128(0)]), "Numerator should be set");
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, proposed_numerator);
+        assert_eq!(market.challenge_settlement(), Some((oracle(), 33400000000000000000000, false)));
     }
 
     #[test]
-    fn valid_scalar_outcome_price_over_upper_bound() {
+    #[should_panic(expected = "ERR_OUTCOME_ALREADY_PROPOSED")]
+    fn challenged_proposal_survives_disabling_optimistic_resolution() {
         testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
-        
-        contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                outcomes: 2, // outcomes
-                outcome_tags: vec!["0".to_string(), "50".to_string()], // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: true, // is_scalar,
-            }
-        );
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 1000)));
+        contract.challenge_outcome(market_id, None);
 
-        contract.set_outcome(alice(), Outcome::Answer("55".to_string()), Some(vec![U64(0)]));
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 2000)));
+        contract.set_optimistic_resolution(false);
 
-        let market = contract.get_market_expect(U64(0));
-        assert!(market.finalized, "Market should be finalized");
-        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(1000000000000000000000000)]), "Numerator should be set");
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000 + 3000)));
+        contract.set_outcome(alice(), Outcome::Answer("1".to_string()), Some(vec![market_id]));
     }
 
-    // TODO: should be changed with oracle integration
-    // #[test]
-    // #[should_panic(expected = "ERR_RESOLUTION_TIME_NOT_REACHED")]
-    // fn resolute_before_resolution_time() {
-    //     testing_env!(get_context(alice(), 0));
-
-    //     let mut contract = AMMContract::init(
-    //         bob().try_into().unwrap(),
-    //         vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-    //         oracle().try_into().unwrap()
-    //     );
-
-    //     let market_id = contract.create_market(
-    //         &CreateMarketArgs {
-    //             description: empty_string(), // market description
-    //             extra_info: empty_string(), // extra info
-    //             outcomes: 2, // outcomes
-    //             outcome_tags: empty_string_vec(2), // outcome tags
-    //             categories: empty_string_vec(2), // categories
-    //             end_time: 1609951265967.into(), // end_time
-    //             resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-    //             collateral_token_id: token(), // collateral_token_id
-    //             swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-    //             is_scalar: None // is_scalar
-    //         }
-    //     );
-
-    //     testing_env!(get_context(token(), 0));
-
-    //     let mut market = contract.get_market_expect(U64(0));
-    //     market.enabled = true;
-    //     contract.markets.replace(0, &market);
-
-    //     let add_liquidity_args = AddLiquidityArgs {
-    //         market_id,
-    //         weight_indication: Some(vec![U128(2), U128(1)])
-    //     };
-
-    //     contract.add_liquidity(
-    //         &alice(), // sender
-    //         10000000000000000000, // total_in
-    //         add_liquidity_args
-    //     );
-
-    //     testing_env!(get_context(bob(), 0));
-
-    //     contract.resolute_market(
-    //         market_id,
-    //         Some(vec![U128(1000000000000000000000000), U128(0)]) // payout_numerator
-    //     );
-    // }
+    #[test]
+    fn unchallenged_proposal_finalizes_after_disabling_optimistic_resolution() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 1000)));
+        contract.set_optimistic_resolution(false);
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 3600000)));
+        contract.finalize_after_challenge(market_id);
+
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, Some(vec![U128(10_u128.pow(24)), U128(0)]));
+    }
 
     #[test]
-    fn resolute_after_resolution_time() {
+    #[should_panic(expected = "ERR_CHALLENGE_PERIOD_ACTIVE")]
+    fn resolute_market_within_unchallenged_challenge_period() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
+
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 1000)));
+        contract.resolute_market(market_id, Some(vec![U128(0), U128(10_u128.pow(24))]));
+    }
+
+    #[test]
+    fn resolute_market_after_unchallenged_challenge_period() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
+
+        let numerator = Some(vec![U128(0), U128(10_u128.pow(24))]);
+        testing_env!(get_context(bob(), ms_to_ns(1619882574000 + 3600000)));
+        contract.resolute_market(market_id, numerator.clone());
+
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, numerator);
+        assert_eq!(market.challenge_settlement(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CHALLENGED")]
+    fn challenged_outcome_cant_finalize_after_challenge_period() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 1000)));
+        contract.challenge_outcome(market_id, None);
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 3600000)));
+        contract.finalize_after_challenge(market_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CHALLENGE_PERIOD_OVER")]
+    fn challenge_outcome_after_challenge_period() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = create_optimistic_market(&mut contract);
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000 + 3600000)));
+        contract.challenge_outcome(market_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_REENTRANT")]
+    fn exit_pool_reentry_while_payout_pending() {
         testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
 
-        let mut contract = AMMContract::init(
-            bob().try_into().unwrap(),
-            vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
-            oracle().try_into().unwrap()
-        );
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, U128(10_u128.pow(23)));
+        contract.exit_pool(market_id, U128(10_u128.pow(23)));
+    }
 
-        let market_id = contract.create_market(
-            &CreateMarketArgs {
-                description: empty_string(), // market description
-                extra_info: empty_string(), // extra info
-                sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
-                outcomes: 2, // outcomes
-                outcome_tags: empty_string_vec(2), // outcome tags
-                categories: empty_string_vec(2), // categories
-                end_time: 1609951265967.into(), // end_time
-                resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
-                collateral_token_id: token(), // collateral_token_id
-                swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
-                challenge_period: U64(1),
-                is_scalar: false // is_scalar
+    #[test]
+    fn exit_pool_without_payout_releases_lock() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        enable_market(&mut contract, market_id);
+        seed_market(&mut contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, U128(10_u128.pow(23)));
+        contract.exit_pool(market_id, U128(10_u128.pow(23)));
+        assert_eq!(contract.get_pool_token_balance(market_id, &carol()), U128(8 * 10_u128.pow(23)));
+    }
+
+    #[test]
+    fn collateral_invariant_holds_after_trading() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = seed_market_with_fees(&mut contract);
+        testing_env!(get_context(bob(), 0));
+        contract.set_protocol_fee_fraction(2000);
+
+        seed_market(&mut contract, market_id, alice(), 5 * 10_u128.pow(23), None);
+        contract.buy(
+            &alice(),
+            5 * 10_u128.pow(22),
+            BuyArgs {
+                market_id,
+                outcome_target: 1,
+                min_shares_out: U128(0),
+                deadline: None
             }
         );
 
-        testing_env!(get_context(token(), 0));
+        let bob_shares = contract.get_share_balance(&bob(), market_id, 0);
+        testing_env!(get_context(bob(), 0));
+        contract.sell(market_id, U128(2 * 10_u128.pow(22)), 0, bob_shares, None);
 
-        let mut market = contract.get_market_expect(U64(0));
-        market.enabled = true;
-        contract.markets.replace(0, &market);
+        let alice_shares = contract.get_share_balance(&alice(), market_id, 1);
+        testing_env!(get_context(alice(), 0));
+        contract.sell(market_id, U128(10_u128.pow(22)), 1, alice_shares, None);
 
-        let add_liquidity_args = AddLiquidityArgs {
-            market_id,
-            weight_indication: Some(vec![U128(2), U128(1)])
-        };
+        testing_env!(get_context(carol(), 0));
+        contract.exit_pool(market_id, U128(10_u128.pow(23)));
 
-        contract.add_liquidity(
-            &alice(), // sender
-            10000000000000000000, // total_in
-            add_liquidity_args
-        );
+        let report = contract.get_collateral_invariant(market_id);
+        let obligations = u128::from(report.outstanding_shares) + u128::from(report.accrued_fees) + u128::from(report.escrowed);
+        assert_eq!(u128::from(report.obligations), obligations);
+        assert!(u128::from(report.collateral_held) > 0);
+        assert!(u128::from(report.accrued_fees) > 0);
+        assert!(i128::from(report.difference).abs() <= 100, "collateral drifted by {}", i128::from(report.difference));
+    }
 
-        testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+    #[test]
+    fn market_state_follows_lifecycle() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["YES".to_string(), "NO".to_string()];
+        let market_id = contract.create_market(&args);
+        let invalid_market_id = contract.create_market(&args);
+        assert_eq!(contract.get_market_state(market_id), MarketState::Disabled);
 
-        contract.resolute_market(
-            market_id,
-            Some(vec![U128(1000000000000000000000000), U128(0)]) // payout_numerator
-        );
+        enable_market(&mut contract, market_id);
+        enable_market(&mut contract, invalid_market_id);
+        assert_eq!(contract.get_market_state(market_id), MarketState::Trading);
+
+        testing_env!(get_context(alice(), ms_to_ns(1609951265967)));
+        assert_eq!(contract.get_market_state(market_id), MarketState::Closed);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        assert_eq!(contract.get_market_state(market_id), MarketState::AwaitingResolution);
+
+        contract.set_outcome(alice(), Outcome::Answer("YES".to_string()), Some(vec![market_id]));
+        contract.set_outcome(alice(), Outcome::Invalid, Some(vec![invalid_market_id]));
+        assert_eq!(contract.get_market_state(market_id), MarketState::Resolved);
+        assert_eq!(contract.get_market_state(invalid_market_id), MarketState::Invalid);
+    }
+
+    #[test]
+    fn market_state_disabled_past_end_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+
+        testing_env!(get_context(alice(), ms_to_ns(1619882574000)));
+        assert_eq!(contract.get_market_state(market_id), MarketState::Disabled);
     }
 
+    #[test]
+    fn market_state_not_started_before_start_time() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_id = create_scheduled_market(&mut contract, 1000);
+        assert_eq!(contract.get_market_state(market_id), MarketState::NotStarted);
+        assert_eq!(contract.get_resolution_state(market_id), ResolutionState::Trading);
+
+        testing_env!(get_context(alice(), ms_to_ns(1000)));
+        assert_eq!(contract.get_market_state(market_id), MarketState::Trading);
+    }
 }
@@ -101,29 +101,92 @@ impl AMMContract {
      * @param collateral_token_id the `account_id` of the whitelisted token that is used as collateral for trading
      * @param swap_fee the fee that's taken from every swap and paid out to LPs
      * @param is_scalar if the market is a scalar market (range)
+     * @param ambiguity_policy how categorical answers matching multiple outcome tags are resolved, duplicate tags are only accepted if set
+     * @param lp_lockup_ms optional period in ms after an LP's latest liquidity addition during which they can't exit the pool
+     * @param price_tick optional tick size, in the collateral denomination, that spot prices are rounded to
+     * @param notification_endpoint optional endpoint off-chain services can notify of resolution events, stored as metadata only
+     * @param max_pool_collateral optional cap on the collateral locked in the market, liquidity additions exceeding it are rejected
+     * @param start_time optional time in ms before which the market can't be traded or receive liquidity, has to be before `end_time`
+     * @param pricing_mode how the pool prices outcomes, defaults to `ConstantProduct`
+     * @param fee_schedule optional swap fee that moves linearly from `start_fee` at creation to `end_fee` at `end_time`, replaces `swap_fee`
+     * @param min_bet optional minimum amount of collateral a single buy has to spend
+     * @param max_bet optional maximum amount of collateral a single buy can spend, can't be below `min_bet`
      * @returns wrapped `market_id` 
      */
     pub fn create_market(&mut self, payload: &CreateMarketArgs) -> U64 {
         self.assert_unpaused();
-        let swap_fee: u128 = payload.swap_fee.into();
+        let swap_fee: u128 = match payload.fee_schedule {
+            Some(fee_schedule) => fee_schedule.start_fee.into(),
+            None => payload.swap_fee.into()
+        };
         let market_id = self.markets.len();
         let token_decimals = self.collateral_whitelist.0.get(&payload.collateral_token_id);
         let end_time: u64 = payload.end_time.into();
         let resolution_time: u64 = payload.resolution_time.into();
+        let now = ns_to_ms(env::block_timestamp());
 
         assert!(token_decimals.is_some(), "ERR_INVALID_COLLATERAL");
+        assert!(payload.outcomes >= constants::MIN_OUTCOMES, "ERR_TOO_FEW_OUTCOMES");
+        assert!(payload.outcomes <= self.max_outcomes, "ERR_TOO_MANY_OUTCOMES");
         assert!(payload.outcome_tags.len() as u16 == payload.outcomes, "ERR_INVALID_TAG_LENGTH");
-        assert!(end_time > ns_to_ms(env::block_timestamp()), "ERR_INVALID_END_TIME");
+        assert!(end_time > now, "ERR_INVALID_END_TIME");
+        assert!(end_time - now >= self.min_market_duration_ms, "ERR_MARKET_TOO_SHORT");
         assert!(resolution_time >= end_time, "ERR_INVALID_RESOLUTION_TIME");
+        if let Some(start_time) = payload.start_time {
+            assert!(u64::from(start_time) < end_time, "ERR_INVALID_START_TIME");
+        }
+        if let (Some(min_bet), Some(max_bet)) = (payload.min_bet, payload.max_bet) {
+            assert!(u128::from(min_bet) <= u128::from(max_bet), "ERR_INVALID_BET_BOUNDS");
+        }
+
+        // `set_outcome` matches answers by tag, duplicates are only allowed if the creator chose how ambiguous answers resolve
+        if !payload.is_scalar && payload.ambiguity_policy.is_none() {
+            for (i, tag) in payload.outcome_tags.iter().enumerate() {
+                assert!(!payload.outcome_tags[..i].contains(tag), "ERR_DUPLICATE_TAG");
+            }
+        }
+
+        // Scalar tags are bounds, more than two split the range into consecutive segments
+        if payload.is_scalar {
+            let mut previous_bound: Option<f64> = None;
+            for tag in payload.outcome_tags.iter() {
+                let bound: f64 = tag.parse().expect("ERR_INVALID_SCALAR_BOUND");
+                assert!(self.allow_negative_bounds || bound >= 0.0, "ERR_NEGATIVE_BOUNDS_DISABLED");
+                if let Some(previous_bound) = previous_bound {
+                    assert!(bound > previous_bound, "ERR_SCALAR_BOUNDS_NOT_INCREASING");
+                }
+                previous_bound = Some(bound);
+            }
+        }
+
+        self.assert_swap_fee_within_max(swap_fee, 10_u128.pow(token_decimals.unwrap()));
+        if let Some(fee_schedule) = payload.fee_schedule {
+            self.assert_swap_fee_within_max(fee_schedule.end_fee.into(), 10_u128.pow(token_decimals.unwrap()));
+        }
+
+        let pricing_mode = payload.pricing_mode.unwrap_or(PricingMode::ConstantProduct);
+        if let PricingMode::Lmsr { b } = pricing_mode {
+            assert!(u128::from(b) > 0, "ERR_INVALID_LIQUIDITY_PARAMETER");
+        }
 
         let pool = pool_factory::new_pool(
             market_id,
             payload.outcomes,
             payload.collateral_token_id.to_string(),
             token_decimals.unwrap(),
-            swap_fee
+            swap_fee,
+            pricing_mode
         );
 
+        if let Some(notification_endpoint) = &payload.notification_endpoint {
+            assert!(notification_endpoint.len() <= constants::MAX_NOTIFICATION_ENDPOINT_LENGTH, "ERR_NOTIFICATION_ENDPOINT_TOO_LONG");
+        }
+
+        if let Some(price_tick) = payload.price_tick {
+            let price_tick: u128 = price_tick.into();
+            assert!(price_tick > 0 && price_tick <= pool.collateral_denomination, "ERR_INVALID_PRICE_TICK");
+        }
+
         logger::log_pool(&pool);
 
         let market = Market {
@@ -136,15 +199,77 @@ impl AMMContract {
             enabled: false,
             is_scalar: payload.is_scalar,
             outcome_tags: payload.outcome_tags.clone(),
+            min_lp_count_to_trade: None,
+            categories: payload.categories.clone(),
+            ambiguity_policy: payload.ambiguity_policy.unwrap_or(AmbiguityPolicy::FirstMatch),
+            min_liquidity_add: 0,
+            lp_lockup_ms: payload.lp_lockup_ms.map(u64::from),
+            price_tick: payload.price_tick.map(u128::from),
+            notification_endpoint: payload.notification_endpoint.clone(),
+            max_pool_collateral: payload.max_pool_collateral.map(u128::from),
+            start_time: payload.start_time.map(u64::from),
+            cancelled: false,
+            created_at: now,
+            fee_schedule: payload.fee_schedule,
+            min_bet: payload.min_bet.map(u128::from),
+            max_bet: payload.max_bet.map(u128::from),
+            trader_allowlist: None,
+            allowlist_nonce: 0,
+            challenge_period: payload.challenge_period.into(),
+            proposed_outcome: None,
+            claims_started: false,
         };
 
         logger::log_create_market(&market, &payload.description, &payload.extra_info, &payload.categories);
         logger::log_market_status(&market);
 
+        self.add_active_collateral_market(&payload.collateral_token_id);
         self.markets.push(&market);
         market_id.into()
     }
 
+    /**
+     * @panics if `swap_fee` exceeds `max_swap_fee_bps` of `collateral_denomination`
+     */
+    pub fn assert_swap_fee_within_max(&self, swap_fee: Balance, collateral_denomination: Balance) {
+        let max_swap_fee = math::simple_mul_u128(
+            constants::FEE_BPS_DENOMINATION as u128,
+            collateral_denomination,
+            self.max_swap_fee_bps as u128
+        );
+        assert!(swap_fee <= max_swap_fee, "ERR_FEE_TOO_HIGH");
+    }
+
+    /**
+     * @notice registers a new non-finalized market for `token_id` in `active_collateral_tokens`
+     */
+    pub fn add_active_collateral_market(&mut self, token_id: &AccountId) {
+        let count = self.active_collateral_tokens.get(token_id).unwrap_or(0);
+        self.active_collateral_tokens.insert(token_id, &(count + 1));
+    }
+
+    /**
+     * @notice removes a finalized market for `token_id` from `active_collateral_tokens`, dropping the token once it has no active markets left
+     */
+    pub fn remove_active_collateral_market(&mut self, token_id: &AccountId) {
+        let count = self.active_collateral_tokens.get(token_id).unwrap_or(0);
+        if count <= 1 {
+            self.active_collateral_tokens.remove(token_id);
+        } else {
+            self.active_collateral_tokens.insert(token_id, &(count - 1));
+        }
+    }
+
+    /**
+     * @notice creates a market and the matching oracle data request, called from `ft_on_transfer` with the validity bond
+     * The market is created disabled, the data request is created in `proceed_datarequest_creation` using the market's
+     * `sources`, `outcome_tags`, `challenge_period` and `resolution_time`, tagged with the `market_id`.
+     * If any step of the oracle promise chain fails `proceed_market_enabling` is never reached and the market stays disabled
+     * @param sender the sender of the original transfer_call, receives any unused bond
+     * @param bond_in the amount of bond tokens sent along with the create request
+     * @param payload the `CreateMarketArgs` for the new market
+     * @returns a `Promise` that fetches the oracle config and creates the data request
+     */
     pub fn ft_create_market_callback(
         &mut self, 
         sender: &AccountId, 
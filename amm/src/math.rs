@@ -20,7 +20,7 @@ pub fn complex_mul_u128(base: u128, a: u128, b: u128) -> u128 {
     // Complex mul
     let c0 = a_u256 * b_u256;
     let c1 = c0 + (base_u256 / 2);
-    (c1 / base_u256).as_u128()
+    narrow_u256(c1 / base_u256)
 }
 
 /** 
@@ -36,7 +36,7 @@ pub fn complex_div_u128(base: u128, a: u128, b: u128) -> u128 {
     // Complex div
     let c0 = a_u256 * base_u256;
     let c1 = c0 + (b_u256 / 2);
-    (c1 / b_u256).as_u128()
+    narrow_u256(c1 / b_u256)
 }
 
 /**
@@ -48,7 +48,7 @@ pub fn simple_mul_u128(base: u128, a: u128, b: u128) -> u128 {
     let b_u256 = u256::from(b);
     let base_u256 = u256::from(base);
 
-    (a_u256 * b_u256 / base_u256).as_u128()
+    narrow_u256(a_u256 * b_u256 / base_u256)
 }
 
 /**
@@ -60,5 +60,133 @@ pub fn simple_div_u128(base: u128, a: u128, b: u128) -> u128 {
     let b_u256 = u256::from(b);
     let base_u256 = u256::from(base);
 
-    (a_u256 * base_u256 / b_u256).as_u128()
-}
\ No newline at end of file
+    narrow_u256(a_u256 * base_u256 / b_u256)
+}
+
+/**
+ * @notice narrows the result of a u256 intermediate back to u128, the product of two u128 values always fits into u256
+ *         so only the final result can be out of range
+ */
+fn narrow_u256(x: u256) -> u128 {
+    assert!(x <= u256::from(u128::MAX), "ERR_MATH_OVERFLOW");
+    x.as_u128()
+}
+
+/**
+ * @notice fixed point e^-x where `x` and the result are denominated in `base`
+ */
+pub fn complex_exp_neg_u128(base: u128, x: u128) -> u128 {
+    // e^-x = (e^-1)^whole * e^-fraction, the Taylor series of e^fraction converges quickly for fraction < 1
+    let whole = x / base;
+    let fraction = x % base;
+    let mut result = complex_div_u128(base, base, exp_taylor_u128(base, fraction));
+
+    if whole > 0 {
+        let inverse_e = complex_div_u128(base, base, exp_taylor_u128(base, base));
+        for _ in 0..whole {
+            result = complex_mul_u128(base, result, inverse_e);
+            if result == 0 {
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+/**
+ * @notice fixed point natural logarithm of `x` where `x` and the result are denominated in `base`, `x` can't be less than 1
+ */
+pub fn complex_ln_u128(base: u128, x: u128) -> u128 {
+    assert!(x >= base, "ERR_LN_UNDERFLOW");
+    // ln(x) = k * ln(2) + ln(x / 2^k) with x / 2^k in [1, 2)
+    let mut remainder = x;
+    let mut halvings = 0;
+    while remainder >= 2 * base {
+        remainder /= 2;
+        halvings += 1;
+    }
+
+    halvings * ln_series_u128(base, 2 * base) + ln_series_u128(base, remainder)
+}
+
+/**
+ * @notice Taylor series of e^x, only accurate for `x` up to 1
+ */
+fn exp_taylor_u128(base: u128, x: u128) -> u128 {
+    let mut sum = base;
+    let mut term = base;
+    let mut n = 1;
+    while term > 0 {
+        term = simple_mul_u128(base, term, x) / n;
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+/**
+ * @notice ln(x) = 2 * atanh((x - 1) / (x + 1)), converges quickly for `x` in [1, 2]
+ */
+fn ln_series_u128(base: u128, x: u128) -> u128 {
+    let y = complex_div_u128(base, x - base, x + base);
+    let y_squared = complex_mul_u128(base, y, y);
+    let mut sum = 0;
+    let mut term = y;
+    let mut n = 1;
+    while term > 0 {
+        sum += term / n;
+        term = complex_mul_u128(base, term, y_squared);
+        n += 2;
+    }
+    2 * sum
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::*;
+
+    const DENOM: u128 = 1_000_000_000_000_000_000_000_000;
+
+    #[test]
+    fn complex_mul_near_max_operands() {
+        assert_eq!(complex_mul_u128(DENOM, u128::MAX, DENOM), u128::MAX);
+        assert_eq!(complex_mul_u128(DENOM, u128::MAX, DENOM / 2), 1 << 127);
+        assert_eq!(complex_mul_u128(DENOM, DENOM * 1_000_000, DENOM * 1_000_000), DENOM * 1_000_000_000_000);
+    }
+
+    #[test]
+    fn complex_div_near_max_operands() {
+        assert_eq!(complex_div_u128(DENOM, u128::MAX, DENOM), u128::MAX);
+        assert_eq!(complex_div_u128(DENOM, u128::MAX / 2, u128::MAX), DENOM / 2);
+    }
+
+    #[test]
+    fn simple_mul_and_div_near_max_operands() {
+        assert_eq!(simple_mul_u128(u128::MAX, u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(simple_div_u128(DENOM, u128::MAX, DENOM), u128::MAX);
+    }
+
+    #[test]
+    fn rounding_is_preserved() {
+        // Complex functions round half up, simple functions truncate
+        assert_eq!(complex_mul_u128(10, 15, 5), 8);
+        assert_eq!(complex_mul_u128(10, 14, 5), 7);
+        assert_eq!(complex_div_u128(10, 1, 3), 3);
+        assert_eq!(complex_div_u128(10, 2, 3), 7);
+        assert_eq!(simple_mul_u128(10, 15, 5), 7);
+        assert_eq!(simple_div_u128(10, 2, 3), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MATH_OVERFLOW")]
+    fn complex_mul_result_overflow() {
+        complex_mul_u128(DENOM, u128::MAX, 2 * DENOM);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MATH_OVERFLOW")]
+    fn complex_div_result_overflow() {
+        complex_div_u128(DENOM, u128::MAX, DENOM / 2);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::*;
+use crate::resolution_escrow::ResolutionEscrows;
+use crate::outcome_token::MintableFungibleToken;
+
+/*** Storage layouts as they were before the current release, only used to migrate existing state ***/
+
+// Adding a field to `Market`, `Pool` or `AMMContract` changes their Borsh layout, already stored state then no longer deserializes.
+// New fields get a default in the `From` conversions below (or in `migrate` for contract fields), which is enough as long as
+// the layout the contract is upgraded from is the one described here. Once a release with new fields has been deployed and
+// migrated, the current structs become the next `V2` legacy layouts and `migrate` reads those instead.
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PoolV1 {
+    pub id: u64,
+    pub collateral_token_id: AccountId,
+    pub collateral_denomination: u128,
+    pub outcomes: u16,
+    pub outcome_tokens: UnorderedMap<u16, MintableFungibleToken>,
+    pub pool_token: MintableFungibleToken,
+    pub swap_fee: Balance,
+    pub withdrawn_fees: LookupMap<AccountId, Balance>,
+    pub total_withdrawn_fees: Balance,
+    pub fee_pool_weight: u128,
+    pub resolution_escrow: ResolutionEscrows
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MarketV1 {
+    pub end_time: Timestamp,
+    pub resolution_time: Timestamp,
+    pub pool: PoolV1,
+    pub outcome_tags: Vec<String>,
+    pub payout_numerator: Option<Vec<U128>>,
+    pub finalized: bool,
+    pub enabled: bool,
+    pub is_scalar: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AMMContractV1 {
+    oracle: AccountId,
+    gov: AccountId,
+    markets: Vector<MarketV1>,
+    collateral_whitelist: Whitelist,
+    paused: bool,
+    accounts: LookupMap<AccountId, AccountStorageBalance>
+}
+
+impl From<PoolV1> for Pool {
+    fn from(pool: PoolV1) -> Self {
+        let pool_id = pool.id;
+        // Collateral flows from before the upgrade aren't tracked, the ledger starts out balanced against the outstanding obligations
+        let collateral_locked = pool.outcome_tokens.get(&0).map(|token| token.total_supply()).unwrap_or(0);
+        let collateral_balance = collateral_locked + pool.fee_pool_weight.saturating_sub(pool.total_withdrawn_fees);
+        let pool_token_supply = pool.pool_token.total_supply();
+        Self {
+            id: pool.id,
+            collateral_token_id: pool.collateral_token_id,
+            collateral_denomination: pool.collateral_denomination,
+            outcomes: pool.outcomes,
+            outcome_tokens: pool.outcome_tokens,
+            pool_token: pool.pool_token,
+            swap_fee: pool.swap_fee,
+            withdrawn_fees: pool.withdrawn_fees,
+            total_withdrawn_fees: pool.total_withdrawn_fees,
+            fee_pool_weight: pool.fee_pool_weight,
+            resolution_escrow: pool.resolution_escrow,
+            // Pool token balances are stored in a `LookupMap` that can't be iterated, so the LPs from before the upgrade can't be counted.
+            // A funded pool has at least one, undercounting only makes `min_lp_count_to_trade` stricter
+            lp_count: if pool_token_supply > 0 { 1 } else { 0 },
+            lp_cost_basis: LookupMap::new(format!("p{}lcb", pool_id).as_bytes().to_vec()),
+            lp_added_at: LookupMap::new(format!("p{}la", pool_id).as_bytes().to_vec()),
+            // Trades from before the upgrade aren't tracked
+            cumulative_volume: 0,
+            total_fees_collected: 0,
+            pricing_mode: PricingMode::ConstantProduct,
+            collateral_balance,
+            total_escrowed: 0,
+        }
+    }
+}
+
+impl From<MarketV1> for Market {
+    fn from(market: MarketV1) -> Self {
+        Self {
+            end_time: market.end_time,
+            resolution_time: market.resolution_time,
+            pool: market.pool.into(),
+            outcome_tags: market.outcome_tags,
+            payout_numerator: market.payout_numerator,
+            finalized: market.finalized,
+            enabled: market.enabled,
+            is_scalar: market.is_scalar,
+            min_lp_count_to_trade: None,
+            categories: vec![],
+            ambiguity_policy: AmbiguityPolicy::FirstMatch,
+            min_liquidity_add: 0,
+            lp_lockup_ms: None,
+            price_tick: None,
+            notification_endpoint: None,
+            max_pool_collateral: None,
+            start_time: None,
+            cancelled: false,
+            // Creation time isn't known for existing markets, they have no fee schedule that depends on it
+            created_at: 0,
+            fee_schedule: None,
+            min_bet: None,
+            max_bet: None,
+            trader_allowlist: None,
+            allowlist_nonce: 0,
+            // The challenge period was only forwarded to the oracle, existing markets are finalized by the oracle directly
+            challenge_period: 0,
+            proposed_outcome: None,
+            // Claims from before the upgrade aren't tracked, markets that were already finalized may have been claimed from
+            claims_started: market.finalized,
+        }
+    }
+}
+
+#[near_bindgen]
+impl AMMContract {
+    /**
+     * @notice migrates the contract state and every stored market from the previous storage layout to the current one
+     * can only be called by the contract itself, e.g. as a function call batched with the code deployment
+     */
+    #[init]
+    pub fn migrate() -> Self {
+        assert_self();
+        let old_state: AMMContractV1 = env::state_read().expect("ERR_NO_STATE");
+
+        // Markets are stored at `prefix + index` so pushing them in order overwrites each entry after it's been read
+        let mut markets: Vector<Market> = Vector::new(b"m".to_vec());
+        let mut finalized_market_count = 0;
+        let mut invalid_market_count = 0;
+        let mut active_collateral_tokens: UnorderedMap<AccountId, u64> = UnorderedMap::new(b"ac".to_vec());
+        for market_id in 0..old_state.markets.len() {
+            let market: Market = old_state.markets.get(market_id).expect("ERR_NO_MARKET").into();
+            if market.finalized {
+                finalized_market_count += 1;
+                if market.payout_numerator.is_none() {
+                    invalid_market_count += 1;
+                }
+            } else {
+                let token_id = &market.pool.collateral_token_id;
+                let count = active_collateral_tokens.get(token_id).unwrap_or(0);
+                active_collateral_tokens.insert(token_id, &(count + 1));
+            }
+            markets.push(&market);
+        }
+
+        let gov = old_state.gov;
+        Self {
+            oracle: old_state.oracle,
+            gov: gov.clone(),
+            markets,
+            collateral_whitelist: old_state.collateral_whitelist,
+            paused: old_state.paused,
+            accounts: old_state.accounts,
+            allow_negative_bounds: true,
+            finalized_market_count,
+            invalid_market_count,
+            pending_gov: None,
+            min_market_duration_ms: 0,
+            resolution_halted: false,
+            oracle_timeout_ms: None,
+            active_collateral_tokens,
+            max_swap_fee_bps: constants::DEFAULT_MAX_SWAP_FEE_BPS,
+            protocol_fee_fraction: 0,
+            treasury_beneficiaries: vec![(gov, constants::FEE_BPS_DENOMINATION)],
+            treasury_balances: LookupMap::new(b"tb".to_vec()),
+            allow_withdrawals_while_paused: false,
+            min_initial_liquidity: 0,
+            blacklist: LookupSet::new(b"bl".to_vec()),
+            failed_payouts: LookupMap::new(b"fp".to_vec()),
+            oracles: vec![],
+            oracle_quorum: 0,
+            pending_answers: LookupMap::new(b"pa".to_vec()),
+            optimistic_resolution: false,
+            challenge_bond: 0,
+            max_outcomes: constants::MAX_OUTCOMES,
+            reentrancy_locks: LookupSet::new(b"rl".to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod migration_tests {
+    use super::*;
+    use near_sdk::{ MockedBlockchain };
+    use near_sdk::{ testing_env };
+    use crate::test_utils::*;
+
+    fn legacy_market(market_id: u64, finalized: bool) -> MarketV1 {
+        legacy_market_with_lps(market_id, finalized, &[])
+    }
+
+    fn legacy_market_with_lps(market_id: u64, finalized: bool, lps: &[AccountId]) -> MarketV1 {
+        let mut pool = pool_factory::new_pool(market_id, 2, token(), 24, 10_u128.pow(24) / 50, PricingMode::ConstantProduct);
+        for (i, lp) in lps.iter().enumerate() {
+            let weight_indication = if i == 0 { Some(vec![1, 1]) } else { None };
+            pool.add_liquidity(lp, 10_u128.pow(24), weight_indication);
+        }
+        MarketV1 {
+            end_time: 1609951265967,
+            resolution_time: 1619882574000,
+            pool: PoolV1 {
+                id: pool.id,
+                collateral_token_id: pool.collateral_token_id,
+                collateral_denomination: pool.collateral_denomination,
+                outcomes: pool.outcomes,
+                outcome_tokens: pool.outcome_tokens,
+                pool_token: pool.pool_token,
+                swap_fee: pool.swap_fee,
+                withdrawn_fees: pool.withdrawn_fees,
+                total_withdrawn_fees: pool.total_withdrawn_fees,
+                fee_pool_weight: pool.fee_pool_weight,
+                resolution_escrow: pool.resolution_escrow,
+            },
+            outcome_tags: vec!["YES".to_string(), "NO".to_string()],
+            payout_numerator: None,
+            finalized,
+            enabled: true,
+            is_scalar: false,
+        }
+    }
+
+    #[test]
+    fn migrate_legacy_state() {
+        testing_env!(get_context(alice(), 0));
+        let mut markets: Vector<MarketV1> = Vector::new(b"m".to_vec());
+        markets.push(&legacy_market(0, false));
+        markets.push(&legacy_market(1, true));
+
+        // Stored Borsh-encoded under the state key, the way the previous release left it
+        env::state_write(&AMMContractV1 {
+            oracle: oracle(),
+            gov: bob(),
+            markets,
+            collateral_whitelist: Whitelist::new(vec![collateral_whitelist::Token { account_id: token(), decimals: 24 }]),
+            paused: false,
+            accounts: LookupMap::new(b"as".to_vec()),
+        });
+
+        let contract = AMMContract::migrate();
+
+        let market = contract.get_market_expect(U64(0));
+        assert_eq!(market.outcome_tags, vec!["YES".to_string(), "NO".to_string()]);
+        assert_eq!(market.pool.swap_fee, 10_u128.pow(24) / 50);
+        assert!(market.categories.is_empty());
+        assert!(market.ambiguity_policy == AmbiguityPolicy::FirstMatch);
+        assert_eq!(market.max_pool_collateral, None);
+        assert_eq!(market.pool.cumulative_volume, 0);
+        assert_eq!(market.pool.total_fees_collected, 0);
+        assert!(contract.get_market_expect(U64(1)).finalized);
+
+        assert_eq!(contract.get_market_counts(), (U64(2), U64(1), U64(1)));
+        assert_eq!(contract.get_active_collateral_tokens(), vec![token()]);
+        assert_eq!(contract.get_treasury_beneficiaries(), vec![(bob(), 10000)]);
+    }
+
+    #[test]
+    fn migrate_seeded_pool_lps_can_exit() {
+        testing_env!(get_context(alice(), 0));
+        let mut markets: Vector<MarketV1> = Vector::new(b"m".to_vec());
+        markets.push(&legacy_market_with_lps(0, false, &[bob(), carol()]));
+
+        env::state_write(&AMMContractV1 {
+            oracle: oracle(),
+            gov: bob(),
+            markets,
+            collateral_whitelist: Whitelist::new(vec![collateral_whitelist::Token { account_id: token(), decimals: 24 }]),
+            paused: false,
+            accounts: LookupMap::new(b"as".to_vec()),
+        });
+
+        let contract = AMMContract::migrate();
+        let mut market = contract.get_market_expect(U64(0));
+        assert_eq!(market.pool.lp_count, 1);
+
+        // Both LPs predate the upgrade, exiting fully mustn't underflow the count
+        for lp in [bob(), carol()].iter() {
+            let balance = market.pool.get_pool_token_balance(lp);
+            market.pool.exit_pool(lp, balance);
+        }
+        assert_eq!(market.pool.lp_count, 0);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::*;
+
+/**
+ * @notice outcome set by the oracle while optimistic resolution is enabled, only finalized once its challenge period passed unchallenged
+ */
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ProposedOutcome {
+    pub proposer: AccountId, // Account that set the outcome, receives the bond of a failed challenge
+    pub payout_numerator: Option<Vec<U128>>, // Proposed payout numerator, `None` if the market is proposed to be invalid
+    pub proposed_at: WrappedTimestamp, // Time in ms when the outcome was proposed, the challenge period starts here
+    pub challenge: Option<Challenge>, // Dispute of the proposal, settled by `gov` through `resolute_market`
+}
+
+/**
+ * @notice dispute of a proposed outcome backed by a bond in NEAR
+ */
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Challenge {
+    pub challenger: AccountId, // Account that posted the bond
+    pub payout_numerator: Option<Vec<U128>>, // Payout numerator the challenger proposes instead
+    pub bond: WrappedBalance, // Attached deposit, refunded if the proposal gets rejected and forfeited to the proposer otherwise
+}
+
+#[near_bindgen]
+impl AMMContract {
+    /**
+     * @notice sets whether oracle outcomes are proposed and only finalized after the market's challenge period, only callable by `gov`
+     * @param optimistic_resolution if true `set_outcome` and `submit_outcome` propose outcomes instead of finalizing markets
+     */
+    pub fn set_optimistic_resolution(&mut self, optimistic_resolution: bool) {
+        self.assert_gov();
+        self.optimistic_resolution = optimistic_resolution;
+    }
+
+    /**
+     * @returns whether oracle outcomes are proposed instead of finalizing markets
+     */
+    pub fn get_optimistic_resolution(&self) -> bool {
+        self.optimistic_resolution
+    }
+
+    /**
+     * @notice sets the minimum bond in NEAR that has to be attached to `challenge_outcome`, only callable by `gov`
+     * @param challenge_bond the minimum bond
+     */
+    pub fn set_challenge_bond(&mut self, challenge_bond: WrappedBalance) {
+        self.assert_gov();
+        self.challenge_bond = challenge_bond.into();
+    }
+
+    /**
+     * @returns the minimum bond in NEAR that has to be attached to `challenge_outcome`
+     */
+    pub fn get_challenge_bond(&self) -> WrappedBalance {
+        U128(self.challenge_bond)
+    }
+
+    /**
+     * @param market_id the market to retrieve the proposal for
+     * @returns the outcome proposed for `market_id` and its challenge, if any
+     */
+    pub fn get_proposed_outcome(&self, market_id: U64) -> Option<ProposedOutcome> {
+        self.get_market_expect(market_id).proposed_outcome
+    }
+
+    /**
+     * @notice disputes the proposed outcome of a market within its challenge period, the attached deposit is the bond
+     * `gov` settles the challenge through `resolute_market`, the bond is refunded unless the market resolves to the original proposal
+     * @param market_id the market to challenge
+     * @param proposed_numerator the payout numerator the challenger proposes instead, `None` proposes an invalid market
+     */
+    #[payable]
+    pub fn challenge_outcome(&mut self, market_id: U64, proposed_numerator: Option<Vec<U128>>) {
+        self.assert_unpaused();
+        self.assert_resolution_not_halted();
+        let challenger = env::predecessor_account_id();
+        self.assert_not_blacklisted(&challenger);
+        let bond = env::attached_deposit();
+        assert!(bond >= self.challenge_bond && bond > 0, "ERR_INSUFFICIENT_BOND");
+
+        let mut market = self.get_market_expect(market_id);
+        assert!(!market.finalized, "ERR_IS_FINALIZED");
+        market.assert_valid_payout_numerator(&proposed_numerator);
+        let challenge_end = market.challenge_end().expect("ERR_NO_PROPOSED_OUTCOME");
+        assert!(ns_to_ms(env::block_timestamp()) < challenge_end, "ERR_CHALLENGE_PERIOD_OVER");
+
+        let mut proposed_outcome = market.proposed_outcome.take().unwrap();
+        assert!(proposed_outcome.challenge.is_none(), "ERR_ALREADY_CHALLENGED");
+        assert!(proposed_outcome.payout_numerator != proposed_numerator, "ERR_SAME_OUTCOME");
+
+        proposed_outcome.challenge = Some(Challenge {
+            challenger,
+            payout_numerator: proposed_numerator,
+            bond: U128(bond),
+        });
+        market.proposed_outcome = Some(proposed_outcome);
+        self.markets.replace(market_id.into(), &market);
+        logger::log_outcome_challenged(&market);
+    }
+
+    /**
+     * @notice finalizes a market with its proposed outcome once the challenge period passed without a challenge, callable by anyone
+     * Also callable after `gov` disabled optimistic resolution, outcomes proposed before that still finalize this way
+     * @param market_id the market to finalize
+     */
+    pub fn finalize_after_challenge(&mut self, market_id: U64) {
+        self.assert_resolution_not_halted();
+        let mut market = self.get_market_expect(market_id);
+        assert!(!market.finalized, "ERR_IS_FINALIZED");
+        let challenge_end = market.challenge_end().expect("ERR_NO_PROPOSED_OUTCOME");
+        assert!(ns_to_ms(env::block_timestamp()) >= challenge_end, "ERR_CHALLENGE_PERIOD_ACTIVE");
+
+        let proposed_outcome = market.proposed_outcome.clone().unwrap();
+        assert!(proposed_outcome.challenge.is_none(), "ERR_CHALLENGED");
+
+        market.payout_numerator = proposed_outcome.payout_numerator;
+        market.finalized = true;
+        self.record_finalization(&market);
+        self.markets.replace(market_id.into(), &market);
+        logger::log_market_status(&market);
+    }
+}
+
+impl AMMContract {
+    /**
+     * @notice pays out the bond of a challenged proposal after `gov` finalized the market,
+     * the challenger is refunded if the final payout differs from the proposal, the proposer receives it otherwise
+     */
+    pub fn settle_challenge(&self, market: &Market) {
+        if let Some((receiver, bond, challenge_succeeded)) = market.challenge_settlement() {
+            logger::log_challenge_settled(market.pool.id, &receiver, challenge_succeeded);
+            Promise::new(receiver).transfer(bond);
+        }
+    }
+}
+
+impl Market {
+    /**
+     * @returns the time in ms after which the proposed outcome can no longer be challenged, `None` if no outcome was proposed
+     */
+    pub fn challenge_end(&self) -> Option<Timestamp> {
+        self.proposed_outcome.as_ref().map(|proposed_outcome| {
+            u64::from(proposed_outcome.proposed_at) + ns_to_ms(self.challenge_period)
+        })
+    }
+
+    /**
+     * @returns the account that receives the challenge bond given the market's current payout numerator, the bond
+     * and whether the challenge succeeded, `None` if the proposed outcome wasn't challenged
+     */
+    pub fn challenge_settlement(&self) -> Option<(AccountId, Balance, bool)> {
+        let proposed_outcome = self.proposed_outcome.as_ref()?;
+        let challenge = proposed_outcome.challenge.as_ref()?;
+        let challenge_succeeded = self.payout_numerator != proposed_outcome.payout_numerator;
+        let receiver = if challenge_succeeded {
+            challenge.challenger.to_string()
+        } else {
+            proposed_outcome.proposer.to_string()
+        };
+        Some((receiver, challenge.bond.into(), challenge_succeeded))
+    }
+}
@@ -0,0 +1,335 @@
+use crate::*;
+
+/**
+ * @notice an answer submitted by one of the quorum oracles that hasn't been aggregated yet
+ */
+#[derive(Serialize, Deserialize)]
+pub struct PendingAnswer {
+    pub oracle: AccountId, // The oracle that submitted the answer
+    pub outcome: Outcome, // The submitted answer
+}
+
+#[near_bindgen]
+impl AMMContract {
+    /**
+     * @notice sets the oracle accounts that can resolve markets through `submit_outcome` and how many of them need to answer, only callable by `gov`
+     * while oracles are set the single `oracle` can no longer resolve markets through `set_outcome` or `set_outcomes_batch`
+     * @param oracles the accounts whose answers are aggregated, answers of accounts that are removed are ignored, empty to hand resolution back to `oracle`
+     * @param quorum number of answers required before a market is finalized, at most the number of `oracles` and 0 without oracles
+     */
+    pub fn set_oracles(&mut self, oracles: Vec<ValidAccountId>, quorum: u16) {
+        self.assert_gov();
+        let mut oracles: Vec<AccountId> = oracles.into_iter().map(|oracle| oracle.into()).collect();
+        oracles.sort();
+        oracles.dedup();
+        let valid_quorum = if oracles.is_empty() {
+            quorum == 0
+        } else {
+            quorum > 0 && quorum as usize <= oracles.len()
+        };
+        assert!(valid_quorum, "ERR_INVALID_QUORUM");
+        self.oracles = oracles;
+        self.oracle_quorum = quorum;
+    }
+
+    /**
+     * @returns the oracle accounts that can resolve markets through `submit_outcome`
+     */
+    pub fn get_oracles(&self) -> Vec<AccountId> {
+        self.oracles.clone()
+    }
+
+    /**
+     * @returns number of oracle answers required before a market is finalized
+     */
+    pub fn get_oracle_quorum(&self) -> u16 {
+        self.oracle_quorum
+    }
+
+    /**
+     * @param market_id the market to retrieve the answers for
+     * @returns the answers the current oracles submitted for `market_id` that haven't reached quorum yet
+     */
+    pub fn get_pending_answers(&self, market_id: U64) -> Vec<PendingAnswer> {
+        self.oracles.iter()
+            .filter_map(|oracle| {
+                self.pending_answers.get(&(market_id.into(), oracle.to_string())).map(|outcome| PendingAnswer {
+                    oracle: oracle.to_string(),
+                    outcome,
+                })
+            })
+            .collect()
+    }
+
+    /**
+     * @notice submits the sender's answer for a market, once `oracle_quorum` oracles answered the market is finalized
+     *      with the median answer for scalar markets or the most given answer for categorical markets
+     * @param market_id the market to answer
+     * @param outcome the sender's answer, can be replaced until the market is finalized
+     */
+    pub fn submit_outcome(&mut self, market_id: U64, outcome: Outcome) {
+        let oracle = env::predecessor_account_id();
+        assert!(self.oracles.contains(&oracle), "ERR_NOT_AN_ORACLE");
+        self.assert_resolution_not_halted();
+
+        let market = self.get_market_expect(market_id);
+        assert!(!market.finalized, "ERR_OUTCOME_ALREADY_SET");
+        assert!(ns_to_ms(env::block_timestamp()) >= market.resolution_time, "ERR_RESOLUTION_TIME_NOT_REACHED");
+        if let Err(err) = market.check_answer_type(&outcome) {
+            panic!("{}", err);
+        }
+
+        logger::log_oracle_answer(market_id.into(), &oracle, &outcome);
+        self.pending_answers.insert(&(market_id.into(), oracle), &outcome);
+
+        let answers: Vec<Outcome> = self.get_pending_answers(market_id).into_iter().map(|answer| answer.outcome).collect();
+        if answers.len() < self.oracle_quorum as usize {
+            return;
+        }
+
+        let aggregated_outcome = if market.is_scalar {
+            median_outcome(&answers)
+        } else {
+            plurality_outcome(&answers)
+        };
+        if let Err(err) = self.internal_set_outcome(market_id, &aggregated_outcome) {
+            panic!("{}", err);
+        }
+
+        for oracle in self.oracles.iter() {
+            self.pending_answers.remove(&(market_id.into(), oracle.to_string()));
+        }
+    }
+}
+
+impl AMMContract {
+    /**
+     * @notice once `gov` configured quorum oracles markets can only be resolved through `submit_outcome`, not by the single `oracle`
+     * @panics with `ERR_ORACLE_QUORUM_ACTIVE` if quorum oracles are set
+     */
+    pub fn assert_no_oracle_quorum(&self) {
+        assert!(self.oracles.is_empty(), "ERR_ORACLE_QUORUM_ACTIVE");
+    }
+}
+
+/**
+ * @notice aggregates scalar answers, `Invalid` wins if more oracles answered it than gave a number
+ * answers are compared as fixed point integers like the scalar payout math, f64 is only used if one of them isn't a plain decimal
+ * @returns the median of the numeric answers, the lower of the two middle answers for an even number of answers
+ */
+fn median_outcome(answers: &[Outcome]) -> Outcome {
+    let numeric_answers: Vec<&String> = answers.iter()
+        .filter_map(|outcome| match outcome {
+            Outcome::Answer(answer) => Some(answer),
+            Outcome::Invalid => None,
+        })
+        .collect();
+
+    if numeric_answers.len() * 2 < answers.len() {
+        return Outcome::Invalid;
+    }
+
+    let decimals = numeric_answers.iter().map(|answer| decimal_places(answer)).max().unwrap_or(0);
+    let fixed_point_answers: Option<Vec<(i128, &String)>> = numeric_answers.iter()
+        .map(|answer| parse_fixed_point(answer, decimals).map(|value| (value, *answer)))
+        .collect();
+
+    let median_index = (numeric_answers.len() - 1) / 2;
+    let median = match fixed_point_answers {
+        Some(mut fixed_point_answers) => {
+            fixed_point_answers.sort_by_key(|(value, _)| *value);
+            fixed_point_answers[median_index].1
+        },
+        None => {
+            let mut f64_answers: Vec<(f64, &String)> = numeric_answers.iter()
+                .filter_map(|answer| answer.parse::<f64>().ok().map(|value| (value, *answer)))
+                .collect();
+            // `partial_cmp` only fails for NaN answers, which are ordered as equal to keep the sort total
+            f64_answers.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            f64_answers[median_index.min(f64_answers.len() - 1)].1
+        }
+    };
+    Outcome::Answer(median.to_string())
+}
+
+/**
+ * @notice aggregates categorical answers, ties are won by the answer of the first oracle in `oracles` order
+ * @returns the answer given by most oracles
+ */
+fn plurality_outcome(answers: &[Outcome]) -> Outcome {
+    let mut winner = &answers[0];
+    let mut winner_count = 0;
+    for answer in answers {
+        let count = answers.iter().filter(|other| *other == answer).count();
+        if count > winner_count {
+            winner = answer;
+            winner_count = count;
+        }
+    }
+    winner.clone()
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod oracle_quorum_tests {
+    use std::convert::TryInto;
+    use near_sdk::{ MockedBlockchain };
+    use near_sdk::{ testing_env };
+    use super::*;
+    use crate::test_utils::*;
+
+    fn quorum_oracles() -> Vec<AccountId> {
+        vec!["oracle1.near".to_string(), "oracle2.near".to_string(), "oracle3.near".to_string()]
+    }
+
+    fn set_quorum_oracles(contract: &mut AMMContract, quorum: u16) {
+        testing_env!(get_context(bob(), 0));
+        contract.set_oracles(quorum_oracles().into_iter().map(|oracle| oracle.try_into().unwrap()).collect(), quorum);
+    }
+
+    fn submit_outcomes(contract: &mut AMMContract, market_id: U64, answers: Vec<Outcome>) {
+        for (oracle, outcome) in quorum_oracles().into_iter().zip(answers) {
+            testing_env!(get_context(oracle, ms_to_ns(1619882574000)));
+            contract.submit_outcome(market_id, outcome);
+        }
+    }
+
+    #[test]
+    fn quorum_scalar_market_resolves_to_median() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcome_tags = vec!["0".to_string(), "10".to_string()];
+        args.is_scalar = true;
+        let market_id = contract.create_market(&args);
+        set_quorum_oracles(&mut contract, 3);
+
+        submit_outcomes(&mut contract, market_id, vec![
+            Outcome::Answer("2".to_string()),
+            Outcome::Answer("7".to_string()),
+            Outcome::Answer("4".to_string()),
+        ]);
+
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, Some(vec![U128(6 * 10_u128.pow(23)), U128(4 * 10_u128.pow(23))]));
+        assert!(contract.get_pending_answers(market_id).is_empty());
+    }
+
+    #[test]
+    fn quorum_categorical_market_resolves_to_plurality() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let mut args = default_market_args();
+        args.outcomes = 3;
+        args.outcome_tags = vec!["yes".to_string(), "no".to_string(), "maybe".to_string()];
+        args.categories = empty_string_vec(3);
+        let market_id = contract.create_market(&args);
+        set_quorum_oracles(&mut contract, 3);
+
+        submit_outcomes(&mut contract, market_id, vec![
+            Outcome::Answer("no".to_string()),
+            Outcome::Answer("yes".to_string()),
+            Outcome::Answer("no".to_string()),
+        ]);
+
+        let market = contract.get_market_expect(market_id);
+        assert!(market.finalized);
+        assert_eq!(market.payout_numerator, Some(vec![U128(0), U128(10_u128.pow(24)), U128(0)]));
+    }
+
+    #[test]
+    fn quorum_not_met_keeps_market_unresolved() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        set_quorum_oracles(&mut contract, 3);
+
+        submit_outcomes(&mut contract, market_id, vec![
+            Outcome::Answer("0".to_string()),
+            Outcome::Answer("0".to_string()),
+        ]);
+
+        let market = contract.get_market_expect(market_id);
+        assert!(!market.finalized);
+        assert_eq!(market.payout_numerator, None);
+        let pending_answers = contract.get_pending_answers(market_id);
+        assert_eq!(pending_answers.len(), 2);
+        assert_eq!(pending_answers[0].oracle, "oracle1.near".to_string());
+        assert_eq!(pending_answers[1].outcome, Outcome::Answer("0".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_AN_ORACLE")]
+    fn submit_outcome_requires_quorum_oracle() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        set_quorum_oracles(&mut contract, 2);
+
+        testing_env!(get_context(carol(), ms_to_ns(1619882574000)));
+        contract.submit_outcome(market_id, Outcome::Answer("0".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ORACLE_QUORUM_ACTIVE")]
+    fn set_outcome_rejected_with_quorum() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        set_quorum_oracles(&mut contract, 2);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![market_id]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ORACLE_QUORUM_ACTIVE")]
+    fn set_outcomes_batch_rejected_with_quorum() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        set_quorum_oracles(&mut contract, 2);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcomes_batch(vec![(vec![market_id], Outcome::Answer("0".to_string()))]);
+    }
+
+    #[test]
+    fn median_keeps_precision_of_large_answers() {
+        // All three answers are the same f64, only the fixed point comparison finds the middle one
+        let answers = vec![
+            Outcome::Answer("10000000000000000001".to_string()),
+            Outcome::Answer("10000000000000000003".to_string()),
+            Outcome::Answer("10000000000000000002".to_string()),
+        ];
+        assert_eq!(median_outcome(&answers), Outcome::Answer("10000000000000000002".to_string()));
+    }
+
+    #[test]
+    fn median_compares_decimals_of_different_lengths() {
+        let answers = vec![
+            Outcome::Answer("1.25".to_string()),
+            Outcome::Answer("1.3".to_string()),
+            Outcome::Answer("-2".to_string()),
+            Outcome::Invalid,
+        ];
+        assert_eq!(median_outcome(&answers), Outcome::Answer("1.25".to_string()));
+    }
+
+    #[test]
+    fn cleared_oracles_hand_resolution_back_to_oracle() {
+        testing_env!(get_context(oracle(), 0));
+        let mut contract = init_contract();
+        let market_id = contract.create_market(&default_market_args());
+        set_quorum_oracles(&mut contract, 2);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_oracles(vec![], 0);
+
+        testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+        contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![market_id]));
+        assert!(contract.get_market_expect(market_id).finalized);
+    }
+}
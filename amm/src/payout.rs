@@ -0,0 +1,134 @@
+use crate::*;
+use near_sdk::PromiseResult;
+
+#[ext_contract(ext_self)]
+trait PayoutResolver {
+    fn on_transfer_resolved(&mut self, account_id: AccountId, collateral_token_id: AccountId, amount: U128, unlock_market_ids: Vec<U64>) -> bool;
+}
+
+#[near_bindgen]
+impl AMMContract {
+    /**
+     * @notice callback of every collateral payout, credits the payout to `account_id`'s failed payouts if the transfer failed
+     * so it can be retried through `withdraw_failed_payout`, can only be called by the contract itself
+     * @param account_id the receiver of the payout
+     * @param collateral_token_id the collateral token that was transferred
+     * @param amount the amount of collateral that was transferred
+     * @param unlock_market_ids the markets `account_id` was locked on while the transfer was pending
+     * @returns whether the transfer succeeded
+     */
+    pub fn on_transfer_resolved(
+        &mut self,
+        account_id: AccountId,
+        collateral_token_id: AccountId,
+        amount: U128,
+        unlock_market_ids: Vec<U64>
+    ) -> bool {
+        assert_self();
+        for market_id in unlock_market_ids {
+            self.unlock_account_market(&account_id, market_id.into());
+        }
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => true,
+            PromiseResult::Failed => {
+                let key = (account_id, collateral_token_id);
+                let failed_payout = self.failed_payouts.get(&key).unwrap_or(0);
+                self.failed_payouts.insert(&key, &(failed_payout + u128::from(amount)));
+                logger::log_failed_payout(&key.0, &key.1, amount.into());
+                false
+            }
+        }
+    }
+
+    /**
+     * @param account_id the account to retrieve the failed payouts for
+     * @param collateral_token_id the collateral token of the failed payouts
+     * @returns wrapped amount of collateral from failed transfers that `account_id` can withdraw again
+     */
+    pub fn get_failed_payout(&self, account_id: AccountId, collateral_token_id: AccountId) -> WrappedBalance {
+        U128(self.failed_payouts.get(&(account_id, collateral_token_id)).unwrap_or(0))
+    }
+
+    /**
+     * @notice retries the sender's failed payouts in `collateral_token_id`, credited again if the transfer fails another time
+     * @param collateral_token_id the collateral token to withdraw
+     * @returns a promise referencing the collateral token transaction
+     */
+    #[payable]
+    pub fn withdraw_failed_payout(&mut self, collateral_token_id: ValidAccountId) -> Promise {
+        let account_id = env::predecessor_account_id();
+        self.assert_not_blacklisted(&account_id);
+        self.assert_withdrawals_allowed();
+        let amount = self.failed_payouts
+            .remove(&(account_id.to_string(), collateral_token_id.to_string()))
+            .unwrap_or(0);
+        assert!(amount > 0, "ERR_NO_FAILED_PAYOUT");
+
+        self.transfer_payout(account_id, amount, collateral_token_id.as_ref())
+    }
+}
+
+impl AMMContract {
+    /**
+     * @notice transfers `amount` of `collateral_token_id` to `account_id` and keeps the amount claimable if the transfer fails
+     * @returns a promise referencing the collateral token transaction and its callback
+     */
+    pub fn transfer_payout(&self, account_id: AccountId, amount: Balance, collateral_token_id: &AccountId) -> Promise {
+        self.internal_transfer_payout(account_id, amount, collateral_token_id, vec![])
+    }
+
+    /**
+     * @notice same as `transfer_payout` for a payout out of `market_id`, releases the account's lock on the market once the transfer resolved
+     * @returns a promise referencing the collateral token transaction and its callback
+     */
+    pub fn transfer_payout_and_unlock(&self, account_id: AccountId, market_id: u64, amount: Balance, collateral_token_id: &AccountId) -> Promise {
+        self.internal_transfer_payout(account_id, amount, collateral_token_id, vec![U64(market_id)])
+    }
+
+    /**
+     * @notice same as `transfer_payout_and_unlock` for a payout summed up over multiple markets, releases the account's lock on all of them
+     * @returns a promise referencing the collateral token transaction and its callback
+     */
+    pub fn transfer_payout_and_unlock_markets(&self, account_id: AccountId, market_ids: Vec<U64>, amount: Balance, collateral_token_id: &AccountId) -> Promise {
+        self.internal_transfer_payout(account_id, amount, collateral_token_id, market_ids)
+    }
+
+    /**
+     * @notice locks `account_id` out of the market's payout-bearing methods until its pending payout resolved
+     * Every method that takes the lock has to release it through `transfer_payout_and_unlock` or `unlock_account_market`,
+     * internal balances are final by the time the lock is released so a re-entering call can never pay out the same balance twice
+     * @panics with `ERR_REENTRANT` if the account already holds the lock on the market
+     */
+    pub fn lock_account_market(&mut self, account_id: &AccountId, market_id: u64) {
+        assert!(self.reentrancy_locks.insert(&(account_id.to_string(), market_id)), "ERR_REENTRANT");
+    }
+
+    /**
+     * @notice releases the lock taken through `lock_account_market`
+     */
+    pub fn unlock_account_market(&mut self, account_id: &AccountId, market_id: u64) {
+        self.reentrancy_locks.remove(&(account_id.to_string(), market_id));
+    }
+
+    fn internal_transfer_payout(&self, account_id: AccountId, amount: Balance, collateral_token_id: &AccountId, unlock_market_ids: Vec<U64>) -> Promise {
+        collateral_token::ft_transfer(
+            account_id.to_string(),
+            U128(amount),
+            None,
+            collateral_token_id,
+            1,
+            GAS_BASE_COMPUTE
+        ).then(
+            ext_self::on_transfer_resolved(
+                account_id,
+                collateral_token_id.to_string(),
+                U128(amount),
+                unlock_market_ids,
+                &env::current_account_id(),
+                0,
+                GAS_BASE_COMPUTE
+            )
+        )
+    }
+}
@@ -4,6 +4,15 @@ use crate::resolution_escrow::ResolutionEscrows;
 use crate::outcome_token::MintableFungibleToken;
 use near_sdk::Balance;
 
+/**
+ * @notice how prices are derived from the pool's outcome balances
+ */
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PricingMode {
+    ConstantProduct, // Prices follow the product of the other outcomes' balances
+    Lmsr { b: U128 }, // Logarithmic market scoring rule with liquidity parameter `b`, denominated in collateral
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Pool {
     pub id: u64, // unique identifier - used for storage pointers
@@ -12,11 +21,19 @@ pub struct Pool {
     pub outcomes: u16, // the number of outcomes tokens in this pool
     pub outcome_tokens: UnorderedMap<u16, MintableFungibleToken>, // maps outcome => outcome token implementation
     pub pool_token: MintableFungibleToken, // the token representing LP positions
-    pub swap_fee: Balance, // the fee paid to LPs on every swap, denominated in 1e4, meaning that 1 = 0.01% and 10000 = 100%
+    pub swap_fee: Balance, // the fee paid to LPs on every swap, denominated in 1e4, meaning that 1 = 0.01% and 10000 = 100%. Follows the market's fee schedule if it has one
     pub withdrawn_fees: LookupMap<AccountId, Balance>, // amount of accumulated fees an account is (no longer) ineligable to claim
     pub total_withdrawn_fees: Balance, // total withdrawn fees
     pub fee_pool_weight: u128, // weighted fee pool used to calculate fees owed to accounts based on LP token share
-    pub resolution_escrow: ResolutionEscrows // maps account_id => Resolution Escrow scruct
+    pub resolution_escrow: ResolutionEscrows, // maps account_id => Resolution Escrow scruct
+    pub lp_count: u64, // number of distinct accounts that currently hold LP tokens
+    pub lp_cost_basis: LookupMap<AccountId, Balance>, // maps account_id => collateral an LP has provided that's still in the pool
+    pub lp_added_at: LookupMap<AccountId, Timestamp>, // maps account_id => time in ms of the LP's latest liquidity addition
+    pub cumulative_volume: Balance, // total collateral traded through the pool, buys count the collateral paid in and sells the collateral paid out
+    pub total_fees_collected: Balance, // total swap fees charged by the pool including the protocol's part, unaffected by fee withdrawals
+    pub pricing_mode: PricingMode, // how prices, buys and sells are calculated from the pool balances
+    pub collateral_balance: Balance, // collateral the pool received minus what it paid out, excluding protocol fees, independent of the share accounting
+    pub total_escrowed: Balance, // collateral withheld from sells and redemptions that's claimable through the resolution escrow
 }
 
 impl Pool {
@@ -28,6 +45,7 @@ impl Pool {
      * @param collateral_decimals is the amount of decimals the corresponding collateral token has
      * @param outcomes is the number outcomes in the pool
      * @param swap_fee is the fee paid out to LPs on every swap (buy or sell) denominated in 1e4
+     * @param pricing_mode how prices are calculated from the pool balances
      * @returns a new `Pool` instance
      */
     pub fn new(
@@ -35,12 +53,13 @@ impl Pool {
         collateral_token_id: AccountId,
         collateral_decimals: u32,
         outcomes: u16,
-        swap_fee: Balance
+        swap_fee: Balance,
+        pricing_mode: PricingMode
     ) -> Self {
         assert!(outcomes >= constants::MIN_OUTCOMES, "ERR_MIN_OUTCOMES");
         assert!(outcomes <= constants::MAX_OUTCOMES, "ERR_MAX_OUTCOMES");
         let collateral_denomination = 10_u128.pow(collateral_decimals);
-        assert!(swap_fee == 0 || (swap_fee <= collateral_denomination / 20 && swap_fee >= collateral_denomination / 10_000), "ERR_INVALID_FEE");
+        assert_min_swap_fee(swap_fee, collateral_denomination);
 
         Self {
             id: pool_id,
@@ -53,7 +72,15 @@ impl Pool {
             withdrawn_fees: LookupMap::new(format!("p{}wf", pool_id).as_bytes().to_vec()),
             total_withdrawn_fees: 0,
             fee_pool_weight: 0,
-            resolution_escrow: ResolutionEscrows::new(pool_id)
+            resolution_escrow: ResolutionEscrows::new(pool_id),
+            lp_count: 0,
+            lp_cost_basis: LookupMap::new(format!("p{}lcb", pool_id).as_bytes().to_vec()),
+            lp_added_at: LookupMap::new(format!("p{}la", pool_id).as_bytes().to_vec()),
+            cumulative_volume: 0,
+            total_fees_collected: 0,
+            pricing_mode,
+            collateral_balance: 0,
+            total_escrowed: 0,
         }
     }
 
@@ -64,6 +91,15 @@ impl Pool {
         self.swap_fee
     }
 
+    /**
+     * @notice updates the swap fee, only possible before the pool is seeded so existing LPs keep the fee they joined with
+     */
+    pub fn set_swap_fee(&mut self, swap_fee: Balance) {
+        assert_eq!(self.pool_token.total_supply(), 0, "ERR_POOL_ALREADY_SEEDED");
+        assert_min_swap_fee(swap_fee, self.collateral_denomination);
+        self.swap_fee = swap_fee;
+    }
+
     /**
      * @param account_id to return the share balance of
      * @param outcome for which the `account_id`'s balance should be returned
@@ -95,19 +131,74 @@ impl Pool {
         }).collect()
     }
 
+    /**
+     * @panics with `ERR_NO_LIQUIDITY` if the pool has never been seeded or any of its outcome balances has been drained
+     */
+    pub fn assert_has_liquidity(&self) {
+        assert_has_liquidity(&self.get_pool_balances());
+    }
+
+    /**
+     * @notice every unit of collateral that enters the market mints one share of each outcome, and complete sets are burned when collateral leaves
+     * @returns the collateral currently locked in the market, excluding fees
+     */
+    pub fn get_collateral_locked(&self) -> Balance {
+        self.outcome_tokens.get(&0).map(|token| token.total_supply()).unwrap_or(0)
+    }
+
+    /**
+     * @returns the total minted shares per outcome, including the pool's own balances
+     */
+    pub fn get_total_shares(&self) -> Vec<Balance> {
+        self.outcome_tokens.iter().map(|(_outcome, token)| token.total_supply()).collect()
+    }
+
     pub fn add_liquidity(
         &mut self,
         sender: &AccountId,
         total_in: Balance,
         weight_indication: Option<Vec<u128>>
-    ) {
+    ) -> Balance {
+        let (to_mint, outcome_tokens_to_return) = self.calc_add_liquidity(total_in, &weight_indication);
+
+        self.mint_and_transfer_outcome_tokens(
+            sender.to_string(),
+            total_in,
+            &outcome_tokens_to_return
+        );
+
+        self.mint_internal(sender, to_mint);
+
+        let cost_basis = self.lp_cost_basis.get(sender).unwrap_or(0);
+        self.lp_cost_basis.insert(sender, &(cost_basis + total_in));
+        self.collateral_balance += total_in;
+        self.lp_added_at.insert(sender, &ns_to_ms(env::block_timestamp()));
+
+        logger::log_pool(&self);
+        logger::log_transaction(&logger::TransactionType::AddLiquidity, &sender, total_in, to_mint, U64(self.id), None);
+        logger::log_user_pool_status(&self, &env::predecessor_account_id(), total_in);
+
+        to_mint
+    }
+
+    /**
+     * @notice calculates the result of adding liquidity without mutating the pool
+     * @param total_in is the amount of collateral to add
+     * @param weight_indication token weights, only expected for the first liquidity addition
+     * @returns a tuple of the LP tokens to mint and the outcome tokens that are returned to the sender per outcome
+     */
+    pub fn calc_add_liquidity(
+        &self,
+        total_in: Balance,
+        weight_indication: &Option<Vec<u128>>
+    ) -> (Balance, Vec<Balance>) {
         assert!(total_in >= self.min_liquidity_amount(), "ERR_MIN_LIQUIDITY_AMOUNT");
         let mut outcome_tokens_to_return: Vec<u128> = vec![];
 
         let to_mint = if self.pool_token.total_supply() == 0 {
             assert!(weight_indication.is_some(), "ERR_EXPECTED_WEIGHT_INDICATION");
-            let weights = weight_indication.unwrap();
-            assert!(weights.len() as u16 == self.outcomes, "ERR_INVALID_WEIGHTS");
+            let weights = weight_indication.as_ref().unwrap();
+            self.assert_valid_weights(weights);
             let max_weight = weights.iter().max().unwrap();
 
             for (i, weight) in weights.iter().enumerate() {
@@ -124,24 +215,30 @@ impl Pool {
             let pool_supply = self.pool_token.total_supply();
 
             for (i, balance) in pool_balances.iter().enumerate() {
-                let remaining = math::complex_div_u128(self.collateral_denomination, math::complex_mul_u128(self.collateral_denomination, total_in, *balance), *max_balance); // remaining = amt_in * balance / max_balance
-                outcome_tokens_to_return.insert(i, total_in - remaining);
+                let to_return = match self.pricing_mode {
+                    // LMSR prices depend on the differences between balances, adding to every balance equally keeps them
+                    PricingMode::Lmsr { .. } => 0,
+                    PricingMode::ConstantProduct => {
+                        let remaining = math::complex_div_u128(self.collateral_denomination, math::complex_mul_u128(self.collateral_denomination, total_in, *balance), *max_balance); // remaining = amt_in * balance / max_balance
+                        total_in - remaining
+                    }
+                };
+                outcome_tokens_to_return.insert(i, to_return);
             }
 
             math::complex_div_u128(self.collateral_denomination, math::complex_mul_u128(self.collateral_denomination, total_in, pool_supply), *max_balance)
         };
 
-        self.mint_and_transfer_outcome_tokens(
-            sender.to_string(),
-            total_in,
-            &outcome_tokens_to_return
-        );
-
-        self.mint_internal(sender, to_mint);
+        (to_mint, outcome_tokens_to_return)
+    }
 
-        logger::log_pool(&self);
-        logger::log_transaction(&logger::TransactionType::AddLiquidity, &sender, total_in, to_mint, U64(self.id), None);
-        logger::log_user_pool_status(&self, &env::predecessor_account_id(), total_in);
+    /**
+     * @notice asserts that a weight indication has a weight for every outcome and none of them is zero
+     * @param weights the weights to validate
+     */
+    pub fn assert_valid_weights(&self, weights: &[u128]) {
+        assert!(weights.len() as u16 == self.outcomes, "ERR_INVALID_WEIGHTS_LENGTH");
+        assert!(weights.iter().all(|weight| *weight > 0), "ERR_ZERO_WEIGHT");
     }
 
     fn mint_and_transfer_outcome_tokens(
@@ -229,11 +326,102 @@ impl Pool {
         }
 
         self.resolution_escrow.insert(&sender, &escrow_account);
+
+        // Reduce the cost basis pro rata to the LP tokens that are being exited
+        let cost_basis = self.lp_cost_basis.get(sender).unwrap_or(0);
+        let exited_cost_basis = math::simple_mul_u128(sender_pool_token_balance, cost_basis, total_in);
+        self.lp_cost_basis.insert(sender, &(cost_basis - exited_cost_basis));
+
         let fees = self.burn_internal(sender, total_in);
         logger::log_exit_pool(&self, sender, total_in, fees);
         fees
     }
 
+    /**
+     * @notice moves `amount` shares of `outcome` from `sender` to `receiver_id`
+     * What `sender` spent on the transferred shares moves along pro rata, so the receiver can sell or claim a refund on them
+     */
+    pub fn transfer_outcome_tokens(
+        &mut self,
+        sender: &AccountId,
+        receiver_id: &AccountId,
+        outcome: u16,
+        amount: Balance
+    ) {
+        assert_ne!(sender, receiver_id, "ERR_SELF_TRANSFER");
+        assert!(amount > 0, "ERR_ZERO_AMOUNT");
+        let mut token = self.outcome_tokens.get(&outcome).expect("ERR_NO_OUTCOME");
+        let sender_balance = token.get_balance(sender);
+        assert!(amount <= sender_balance, "ERR_INSUFFICIENT_SHARES");
+
+        let mut sender_escrow = self.resolution_escrow.get_or_new(sender.to_string());
+        let mut receiver_escrow = self.resolution_escrow.get_or_new(receiver_id.to_string());
+        let spent = math::simple_mul_u128(sender_balance, sender_escrow.get_spent(outcome), amount);
+        let sender_spent = sender_escrow.sub_from_spent(outcome, spent);
+        let receiver_spent = receiver_escrow.add_to_spent(outcome, spent);
+        logger::log_account_outcome_spent(&self, sender, outcome, sender_spent);
+        logger::log_account_outcome_spent(&self, receiver_id, outcome, receiver_spent);
+        self.resolution_escrow.insert(sender, &sender_escrow);
+        self.resolution_escrow.insert(receiver_id, &receiver_escrow);
+
+        token.safe_transfer_internal(sender, receiver_id, amount);
+        self.outcome_tokens.insert(&outcome, &token);
+    }
+
+    /**
+     * @notice moves `amount` LP tokens from `sender` to `receiver_id` together with the matching part of the sender's LP accounting
+     * Fees accrued by `sender` up to the transfer are settled, from then on the transferred tokens earn fees for `receiver_id`
+     * @returns the fees earned by `sender` that are to be paid out
+     */
+    pub fn transfer_pool_tokens(
+        &mut self,
+        sender: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance
+    ) -> Balance {
+        assert_ne!(sender, receiver_id, "ERR_SELF_TRANSFER");
+        assert!(amount > 0, "ERR_ZERO_AMOUNT");
+        let sender_balance = self.pool_token.get_balance(sender);
+        let receiver_balance = self.pool_token.get_balance(receiver_id);
+        assert!(amount <= sender_balance, "ERR_INSUFFICIENT_BALANCE");
+
+        // Move what the sender spent on the transferred part of the LP position, used for refunds if the market turns out invalid
+        let mut sender_escrow = self.resolution_escrow.get_expect(sender);
+        let mut receiver_escrow = self.resolution_escrow.get_or_new(receiver_id.to_string());
+        for outcome in 0..self.outcomes {
+            let lp_spent = math::simple_mul_u128(sender_balance, sender_escrow.get_lp_spent(outcome), amount);
+            sender_escrow.sub_from_lp_spent(outcome, lp_spent);
+            receiver_escrow.add_to_lp_spent(outcome, lp_spent);
+        }
+        self.resolution_escrow.insert(sender, &sender_escrow);
+        self.resolution_escrow.insert(receiver_id, &receiver_escrow);
+
+        let cost_basis = self.lp_cost_basis.get(sender).unwrap_or(0);
+        let transferred_cost_basis = math::simple_mul_u128(sender_balance, cost_basis, amount);
+        self.lp_cost_basis.insert(sender, &(cost_basis - transferred_cost_basis));
+        let receiver_cost_basis = self.lp_cost_basis.get(receiver_id).unwrap_or(0);
+        self.lp_cost_basis.insert(receiver_id, &(receiver_cost_basis + transferred_cost_basis));
+
+        // Transfers can't be used to skip a lockup, the receiver is locked until the later of both additions
+        if let Some(sender_added_at) = self.lp_added_at.get(sender) {
+            let receiver_added_at = self.lp_added_at.get(receiver_id).unwrap_or(0);
+            self.lp_added_at.insert(receiver_id, &sender_added_at.max(receiver_added_at));
+        }
+
+        let fees = self.before_pool_token_transfer(Some(sender), Some(receiver_id), amount);
+        self.pool_token.safe_transfer_internal(sender, receiver_id, amount);
+        if receiver_balance == 0 {
+            self.lp_count += 1;
+        }
+        if amount == sender_balance {
+            // Pools migrated from before LPs were counted can have more holders than `lp_count`
+            self.lp_count = self.lp_count.saturating_sub(1);
+        }
+
+        logger::log_pool_token_transfer(&self, sender, receiver_id, amount, fees);
+        fees
+    }
+
     pub fn burn_outcome_tokens_redeem_collateral(
         &mut self,
         sender: &AccountId,
@@ -265,29 +453,81 @@ impl Pool {
             sum + avg_price_paid
         });
 
+        let (escrow_invalid, escrow_valid, in_escrow) = self.calc_redeem_escrow(avg_price_paid, to_burn);
+        if escrow_invalid > 0 {
+            escrow_account.add_to_escrow_invalid(escrow_invalid);
+        }
+        if escrow_valid > 0 {
+            escrow_account.add_to_escrow_valid(escrow_valid);
+        }
+
+        // Store updated account
+        self.resolution_escrow.insert(sender, &escrow_account);
+        self.pay_out_collateral(to_burn - in_escrow);
+        self.total_escrowed += in_escrow;
+
+        in_escrow
+    }
+
+    /**
+     * @notice previews `burn_outcome_tokens_redeem_collateral` without mutating the pool
+     * @param sender the account that would redeem
+     * @param to_burn the amount of each outcome token to burn
+     * @returns the collateral `sender` would receive, 0 if `sender` holds less than `to_burn` of any outcome
+     */
+    pub fn calc_redeem_collateral(
+        &self,
+        sender: &AccountId,
+        to_burn: Balance
+    ) -> Balance {
+        let escrow_account = match self.resolution_escrow.get(sender) {
+            Some(account) => account,
+            None => return 0
+        };
+        if to_burn == 0 {
+            return 0;
+        }
+
+        let mut avg_price_paid = 0;
+        for (outcome, token) in self.outcome_tokens.iter() {
+            let outcome_balance = token.get_balance(sender);
+            if outcome_balance < to_burn {
+                return 0;
+            }
+            avg_price_paid += math::complex_div_u128(self.collateral_denomination, escrow_account.get_spent(outcome), outcome_balance);
+        }
+
+        let (_escrow_invalid, _escrow_valid, in_escrow) = self.calc_redeem_escrow(avg_price_paid, to_burn);
+        to_burn - in_escrow
+    }
+
+    /**
+     * @notice splits the redemption of `to_burn` complete sets, bought at `avg_price_paid` per set, into escrowed amounts
+     * @returns a tuple of the amount claimable if the market turns out invalid, the amount claimable if it turns out valid, 
+     *      and the amount withheld from the redemption's payout
+     */
+    fn calc_redeem_escrow(
+        &self,
+        avg_price_paid: Balance,
+        to_burn: Balance
+    ) -> (Balance, Balance, Balance) {
         // If the user paid less than 1 they have the right to claim the difference if the market turns out valid
         // If the users paid more than 1 they will have the right to claim the difference if the market turns out invalid
-        let in_escrow = match avg_price_paid.cmp(&self.collateral_denomination) {
+        match avg_price_paid.cmp(&self.collateral_denomination) {
             std::cmp::Ordering::Greater => {
                 let loss_per_share = avg_price_paid - self.collateral_denomination;
                 // Escrow loss_per_share * shares to burn. this will be claimable if the market is invalid
-                escrow_account.add_to_escrow_invalid(math::complex_mul_u128(self.collateral_denomination, loss_per_share, to_burn) - 1); // TODO: remove need for -1
-                0
+                let to_escrow = math::complex_mul_u128(self.collateral_denomination, loss_per_share, to_burn) - 1; // TODO: remove need for -1
+                (to_escrow, 0, 0)
             },
             std::cmp::Ordering::Less => {
                 let profit_per_share = self.collateral_denomination - avg_price_paid;
                 // Escrow loss_per_share * shares to burn - this will be claimable if the market is invalid
-                let to_escrow =math::complex_mul_u128(self.collateral_denomination, profit_per_share, to_burn) - 1; // TODO: remove need for -1
-                escrow_account.add_to_escrow_valid(to_escrow); 
-                to_escrow + 1 // TODO: remove need for +1
+                let to_escrow = math::complex_mul_u128(self.collateral_denomination, profit_per_share, to_burn) - 1; // TODO: remove need for -1
+                (0, to_escrow, to_escrow + 1) // TODO: remove need for +1
             }, 
-            std::cmp::Ordering::Equal => 0
-        };
-
-        // Store updated account
-        self.resolution_escrow.insert(sender, &escrow_account);
-
-        in_escrow
+            std::cmp::Ordering::Equal => (0, 0, 0)
+        }
     }
 
     // move to view impl
@@ -307,6 +547,9 @@ impl Pool {
         amount: Balance
     ) {
         self.before_pool_token_transfer(None, Some(to), amount);
+        if amount > 0 && self.pool_token.get_balance(to) == 0 {
+            self.lp_count += 1;
+        }
         self.pool_token.mint(to, amount)
     }
 
@@ -317,6 +560,9 @@ impl Pool {
     ) -> Balance {
         let fees = self.before_pool_token_transfer(Some(from), None, amount);
         self.pool_token.burn(from, amount);
+        if amount > 0 && self.pool_token.get_balance(from) == 0 {
+            self.lp_count = self.lp_count.saturating_sub(1);
+        }
         fees
     }
 
@@ -368,24 +614,92 @@ impl Pool {
 
     pub fn get_fees_withdrawable(&self, account_id: &AccountId) -> Balance {
         let pool_token_bal = self.pool_token.get_balance(account_id);
+        // Exiting the pool pays out all fees accrued up to the exit, accounts without LP tokens have nothing left to withdraw
+        if pool_token_bal == 0 {
+            return 0;
+        }
         let pool_token_total_supply = self.pool_token.total_supply();
         let raw_amount = math::complex_div_u128(self.collateral_denomination, math::complex_mul_u128(self.collateral_denomination, self.fee_pool_weight, pool_token_bal), pool_token_total_supply);
         let ineligible_fee_amount = self.withdrawn_fees.get(account_id).unwrap_or(0);
         raw_amount - ineligible_fee_amount
     }
 
-    pub fn withdraw_fees(
-        &mut self,
-        account_id: &AccountId
-    ) -> Balance {
+    /**
+     * @notice calculates, per outcome, the resolution price at which an LP breaks even on the collateral they provided
+     * @param account_id the LP to calculate the break-even prices for
+     * @returns a list of prices denominated in `collateral_denomination`, `u128::MAX` if the LP holds no shares of an outcome
+     */
+    pub fn calc_lp_breakeven(&self, account_id: &AccountId) -> Vec<Balance> {
+        let cost_basis = self.lp_cost_basis.get(account_id).unwrap_or(0);
         let pool_token_bal = self.pool_token.get_balance(account_id);
         let pool_token_total_supply = self.pool_token.total_supply();
+        let fees = if pool_token_bal > 0 { self.get_fees_withdrawable(account_id) } else { 0 };
+        // Fees are paid out regardless of the outcome so they reduce the amount that needs to be recouped
+        let net_cost = if cost_basis > fees { cost_basis - fees } else { 0 };
+
+        self.get_pool_balances().iter().enumerate().map(|(outcome, pool_balance)| {
+            // Shares the LP has a claim on through its LP tokens plus the shares it holds directly
+            let pool_share = if pool_token_total_supply > 0 {
+                math::simple_mul_u128(pool_token_total_supply, *pool_balance, pool_token_bal)
+            } else {
+                0
+            };
+            let holdings = pool_share + self.get_share_balance(account_id, outcome as u16);
+
+            if net_cost == 0 {
+                0
+            } else if holdings == 0 {
+                u128::MAX
+            } else {
+                math::complex_div_u128(self.collateral_denomination, net_cost, holdings)
+            }
+        }).collect()
+    }
+
+    /**
+     * @notice calculates the fees and outcome shares `account_id` receives when exiting the pool with `total_in` LP tokens
+     * @returns a tuple of the fees paid out in collateral and the outcome shares returned per outcome
+     */
+    pub fn calc_exit_pool(
+        &self,
+        account_id: &AccountId,
+        total_in: Balance
+    ) -> (Balance, Vec<Balance>) {
+        let pool_token_supply = self.pool_token.total_supply();
+        let account_pool_token_balance = self.pool_token.get_balance(account_id);
+        assert!(total_in <= account_pool_token_balance, "sender only has {} lp tokens which is insufficient", account_pool_token_balance);
+
+        let outcome_shares = self.get_pool_balances().iter().map(|balance| {
+            math::complex_mul_u128(self.collateral_denomination, math::complex_div_u128(self.collateral_denomination, total_in, pool_token_supply), *balance)
+        }).collect();
+
+        (self.calc_withdrawable_fees(account_id).1, outcome_shares)
+    }
+
+    /**
+     * @returns a tuple of the total fees `account_id` is entitled to and the part of it that hasn't been withdrawn yet
+     */
+    fn calc_withdrawable_fees(&self, account_id: &AccountId) -> (Balance, Balance) {
+        let pool_token_bal = self.pool_token.get_balance(account_id);
+        // Also avoids dividing by an empty LP token supply once every LP has exited
+        if pool_token_bal == 0 {
+            return (0, 0);
+        }
+        let pool_token_total_supply = self.pool_token.total_supply();
         let raw_amount = math::simple_mul_u128(pool_token_total_supply, self.fee_pool_weight, pool_token_bal);
         let withdrawn_fees = self.withdrawn_fees.get(account_id).unwrap_or(0);
-        let withdrawable_amount = raw_amount - withdrawn_fees;
+        (raw_amount, raw_amount - withdrawn_fees)
+    }
+
+    pub fn withdraw_fees(
+        &mut self,
+        account_id: &AccountId
+    ) -> Balance {
+        let (raw_amount, withdrawable_amount) = self.calc_withdrawable_fees(account_id);
         if withdrawable_amount > 0 {
             self.withdrawn_fees.insert(account_id, &raw_amount);
             self.total_withdrawn_fees += withdrawable_amount;
+            self.pay_out_collateral(withdrawable_amount);
             logger::log_withdrawn_fees(&self.pool_token.token, account_id, raw_amount);
         }
 
@@ -396,18 +710,65 @@ impl Pool {
         &self,
         collateral_in: Balance,
         outcome_target: u16
+    ) -> Balance {
+        let collateral_in_minus_fees = collateral_in - self.calc_swap_fee(collateral_in);
+        self.calc_buy_amount_sans_fee(collateral_in_minus_fees, outcome_target)
+    }
+
+    /**
+     * @notice calculates a buy with and without the swap fee applied
+     * @returns a tuple of (shares out with fee, shares out without fee, fee paid in collateral)
+     */
+    pub fn calc_buy_amount_detailed(
+        &self,
+        collateral_in: Balance,
+        outcome_target: u16
+    ) -> (Balance, Balance, Balance) {
+        let fee = self.calc_swap_fee(collateral_in);
+        (
+            self.calc_buy_amount_sans_fee(collateral_in - fee, outcome_target),
+            self.calc_buy_amount_sans_fee(collateral_in, outcome_target),
+            fee
+        )
+    }
+
+    /**
+     * @returns the swap fee charged on `amount` of collateral
+     */
+    pub fn calc_swap_fee(&self, amount: Balance) -> Balance {
+        math::complex_mul_u128(self.collateral_denomination, amount, self.swap_fee)
+    }
+
+    /**
+     * @notice calculates the shares out for collateral that has already had fees deducted
+     */
+    fn calc_buy_amount_sans_fee(
+        &self,
+        collateral_in_minus_fees: Balance,
+        outcome_target: u16
     ) -> Balance {
         assert!(outcome_target <= self.outcomes, "ERR_INVALID_OUTCOME");
+        self.calc_buy_amount_for_balances(&self.get_pool_balances(), collateral_in_minus_fees, outcome_target)
+    }
 
-        let outcome_tokens = &self.outcome_tokens;
-        let collateral_in_minus_fees = collateral_in - math::complex_mul_u128(self.collateral_denomination, collateral_in, self.swap_fee);
-        let token_to_buy = outcome_tokens.get(&outcome_target).expect("ERR_NO_TOKEN");
-        let token_to_buy_balance = token_to_buy.get_balance(&env::current_account_id());
+    /**
+     * @notice calculates the shares out for collateral that has already had fees deducted given hypothetical pool `balances`
+     */
+    fn calc_buy_amount_for_balances(
+        &self,
+        balances: &[Balance],
+        collateral_in_minus_fees: Balance,
+        outcome_target: u16
+    ) -> Balance {
+        assert_has_liquidity(balances);
+        if let PricingMode::Lmsr { b } = self.pricing_mode {
+            return self.calc_lmsr_buy_amount(balances, b.into(), collateral_in_minus_fees, outcome_target);
+        }
+        let token_to_buy_balance = *balances.get(outcome_target as usize).expect("ERR_NO_TOKEN");
         let mut new_buy_token_balance = token_to_buy_balance;
 
-        for (outcome, token) in outcome_tokens.iter() {
-            if outcome != outcome_target {
-                let balance = token.get_balance(&env::current_account_id());
+        for (outcome, &balance) in balances.iter().enumerate() {
+            if outcome != outcome_target as usize {
                 let dividend = math::complex_mul_u128(self.collateral_denomination, new_buy_token_balance, balance);
                 let divisor = balance + collateral_in_minus_fees;
 
@@ -419,22 +780,103 @@ impl Pool {
         token_to_buy_balance + collateral_in_minus_fees - new_buy_token_balance
     }
 
+    /**
+     * @notice inverse of `calc_buy_amount`, searches the smallest amount of collateral that buys at least `shares_out`
+     * @returns the amount of collateral, including fees, required to buy `shares_out` of `outcome_target`
+     */
+    pub fn calc_buy_collateral_in(
+        &self,
+        shares_out: Balance,
+        outcome_target: u16
+    ) -> Balance {
+        assert!(shares_out > 0, "ERR_ZERO_SHARES_OUT");
+
+        // Outcome prices never exceed 1, so buying `shares_out` costs at most `shares_out` plus fees
+        let mut low = 0;
+        let mut high = math::complex_div_u128(self.collateral_denomination, shares_out, self.collateral_denomination - self.swap_fee) + 1;
+        while self.calc_buy_amount(high, outcome_target) < shares_out {
+            high *= 2;
+        }
+
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.calc_buy_amount(mid, outcome_target) >= shares_out {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        high
+    }
+
+    /**
+     * @notice simulates buying with `collateral_in` and immediately selling all resulting shares back
+     * @returns the collateral lost to fees and price impact
+     */
+    pub fn calc_round_trip_cost(
+        &self,
+        collateral_in: Balance,
+        outcome_target: u16
+    ) -> Balance {
+        assert!(outcome_target < self.outcomes, "ERR_INVALID_OUTCOME");
+
+        let collateral_in_minus_fees = collateral_in - self.calc_swap_fee(collateral_in);
+        let mut balances = self.get_pool_balances();
+        let shares_out = self.calc_buy_amount_for_balances(&balances, collateral_in_minus_fees, outcome_target);
+
+        // Mirror `buy`: the collateral mints every outcome into the pool and the bought shares leave it
+        for balance in balances.iter_mut() {
+            *balance += collateral_in_minus_fees;
+        }
+        balances[outcome_target as usize] -= shares_out;
+
+        // Search the largest `collateral_out` the shares can be sold for, it can't exceed what was put in minus the sell fee
+        let mut low = 0;
+        let mut high = collateral_in_minus_fees - self.calc_swap_fee(collateral_in_minus_fees);
+        if self.calc_sell_collateral_out_for_balances(&balances, high, outcome_target) <= shares_out {
+            low = high;
+        }
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if self.calc_sell_collateral_out_for_balances(&balances, mid, outcome_target) <= shares_out {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        collateral_in - low
+    }
+
     pub fn calc_sell_collateral_out(
         &self,
         collateral_out: Balance,
         outcome_target: u16
     ) -> Balance {
         assert!(outcome_target <= self.outcomes, "ERR_INVALID_OUTCOME");
+        self.calc_sell_collateral_out_for_balances(&self.get_pool_balances(), collateral_out, outcome_target)
+    }
 
-        let outcome_tokens = &self.outcome_tokens;
+    /**
+     * @notice calculates the shares in needed for `collateral_out` given hypothetical pool `balances`
+     */
+    fn calc_sell_collateral_out_for_balances(
+        &self,
+        balances: &[Balance],
+        collateral_out: Balance,
+        outcome_target: u16
+    ) -> Balance {
+        assert_has_liquidity(balances);
         let collateral_out_plus_fees = math::complex_div_u128(self.collateral_denomination, collateral_out, self.collateral_denomination - self.swap_fee);
-        let token_to_sell = outcome_tokens.get(&outcome_target).expect("ERR_NO_TOKEN");
-        let token_to_sell_balance = token_to_sell.get_balance(&env::current_account_id());
+        if let PricingMode::Lmsr { b } = self.pricing_mode {
+            return self.calc_lmsr_sell_shares_in(balances, b.into(), collateral_out_plus_fees, outcome_target);
+        }
+        let token_to_sell_balance = *balances.get(outcome_target as usize).expect("ERR_NO_TOKEN");
         let mut new_sell_token_balance = token_to_sell_balance;
 
-        for (outcome, token) in outcome_tokens.iter() {
-            if outcome != outcome_target {
-                let balance = token.get_balance(&env::current_account_id());
+        for (outcome, &balance) in balances.iter().enumerate() {
+            if outcome != outcome_target as usize {
                 let dividend = math::complex_mul_u128(self.collateral_denomination, new_sell_token_balance, balance);
                 let divisor = balance - collateral_out_plus_fees;
 
@@ -446,13 +888,100 @@ impl Pool {
         collateral_out_plus_fees + new_sell_token_balance - token_to_sell_balance
     }
 
+    /**
+     * @notice LMSR weight of every outcome, e^(-balance / b) relative to the largest weight
+     * Outcomes the pool holds less of have been bought more and are priced higher, the pool balances stand in for the LMSR quantities
+     */
+    fn calc_lmsr_weights(&self, balances: &[Balance], b: Balance) -> Vec<Balance> {
+        let min_balance = *balances.iter().min().expect("ERR_NO_BALANCES");
+        balances
+            .iter()
+            .map(|balance| {
+                let exponent = math::complex_div_u128(self.collateral_denomination, balance - min_balance, b);
+                math::complex_exp_neg_u128(self.collateral_denomination, exponent)
+            })
+            .collect()
+    }
+
+    /**
+     * @notice LMSR spot price of `outcome_target`, e^(-balance_i / b) / sum(e^(-balance_j / b))
+     */
+    fn calc_lmsr_spot_price(&self, balances: &[Balance], b: Balance, outcome_target: u16) -> Balance {
+        let weights = self.calc_lmsr_weights(balances, b);
+        let weight_sum: Balance = weights.iter().sum();
+        math::complex_div_u128(self.collateral_denomination, weights[outcome_target as usize], weight_sum)
+    }
+
+    /**
+     * @notice shares out for which the LMSR cost function increases by `collateral_in_minus_fees`
+     * shares_out = b * ln(1 + sum * (e^(collateral_in / b) - 1) / weight_target)
+     */
+    fn calc_lmsr_buy_amount(
+        &self,
+        balances: &[Balance],
+        b: Balance,
+        collateral_in_minus_fees: Balance,
+        outcome_target: u16
+    ) -> Balance {
+        let denomination = self.collateral_denomination;
+        let weights = self.calc_lmsr_weights(balances, b);
+        let weight_sum: Balance = weights.iter().sum();
+        let target_weight = weights[outcome_target as usize];
+
+        // (e^x - 1) / w is evaluated as (1 - e^-x) / (w * e^-x) to keep every exponent negative
+        let exp_neg_in = math::complex_exp_neg_u128(denomination, math::complex_div_u128(denomination, collateral_in_minus_fees, b));
+        let divisor = math::complex_mul_u128(denomination, target_weight, exp_neg_in);
+        assert!(divisor > 0, "ERR_MATH_APPROX");
+        let ratio = math::complex_div_u128(denomination, math::complex_mul_u128(denomination, weight_sum, denomination - exp_neg_in), divisor);
+        let shares_out = math::complex_mul_u128(denomination, b, math::complex_ln_u128(denomination, denomination + ratio));
+
+        // The pool has to hold the shares it pays out, `b` can't exceed what the liquidity covers
+        assert!(shares_out <= balances[outcome_target as usize] + collateral_in_minus_fees, "ERR_INSUFFICIENT_LIQUIDITY");
+        shares_out
+    }
+
+    /**
+     * @notice shares in for which the LMSR cost function decreases by `collateral_out_plus_fees`
+     * shares_in = b * ln(weight_target / (weight_target - sum * (1 - e^(-collateral_out / b))))
+     */
+    fn calc_lmsr_sell_shares_in(
+        &self,
+        balances: &[Balance],
+        b: Balance,
+        collateral_out_plus_fees: Balance,
+        outcome_target: u16
+    ) -> Balance {
+        let denomination = self.collateral_denomination;
+        for (outcome, &balance) in balances.iter().enumerate() {
+            if outcome != outcome_target as usize {
+                assert!(balance >= collateral_out_plus_fees, "ERR_INSUFFICIENT_LIQUIDITY");
+            }
+        }
+
+        let weights = self.calc_lmsr_weights(balances, b);
+        let weight_sum: Balance = weights.iter().sum();
+        let target_weight = weights[outcome_target as usize];
+
+        let exp_neg_out = math::complex_exp_neg_u128(denomination, math::complex_div_u128(denomination, collateral_out_plus_fees, b));
+        let cost_decrease = math::complex_mul_u128(denomination, weight_sum, denomination - exp_neg_out);
+        assert!(cost_decrease < target_weight, "ERR_INSUFFICIENT_LIQUIDITY");
+
+        let ratio = math::complex_div_u128(denomination, target_weight, target_weight - cost_decrease);
+        math::complex_mul_u128(denomination, b, math::complex_ln_u128(denomination, ratio))
+    }
+
+    /**
+     * @notice buys `outcome_target` shares with `amount_in` collateral, `protocol_fee_fraction` of the swap fee goes to the protocol instead of LPs
+     * @returns a tuple of (shares out, protocol fee)
+     */
     pub fn buy(
         &mut self,
         sender: &AccountId,
         amount_in: Balance,
         outcome_target: u16,
-        min_shares_out: Balance
-    ) {
+        min_shares_out: Balance,
+        protocol_fee_fraction: u16
+    ) -> (Balance, Balance) {
 
         assert!(outcome_target < self.outcomes, "ERR_INVALID_OUTCOME");
 
@@ -463,7 +992,9 @@ impl Pool {
 
         // Transfer collateral in
         let fee = math::complex_mul_u128(self.collateral_denomination, amount_in, self.swap_fee);
-        self.fee_pool_weight += fee;
+        let protocol_fee = self.accrue_fee(fee, protocol_fee_fraction);
+        self.cumulative_volume += amount_in;
+        self.collateral_balance += amount_in - protocol_fee;
 
         let spent = escrow_account.add_to_spent(outcome_target, amount_in - fee);
         logger::log_account_outcome_spent(&self, sender, outcome_target, spent);
@@ -478,15 +1009,22 @@ impl Pool {
 
         logger::log_buy(&self, &sender, outcome_target, amount_in, shares_out, fee);
         logger::log_pool(&self);
+
+        (shares_out, protocol_fee)
     }
 
+    /**
+     * @notice sells `outcome_target` shares for `amount_out` collateral, `protocol_fee_fraction` of the swap fee goes to the protocol instead of LPs
+     * @returns a tuple of (collateral escrowed, protocol fee)
+     */
     pub fn sell(
         &mut self,
         sender: &AccountId,
         amount_out: Balance,
         outcome_target: u16,
-        max_shares_in: Balance
-    ) -> Balance {
+        max_shares_in: Balance,
+        protocol_fee_fraction: u16
+    ) -> (Balance, Balance) {
 
         assert!(outcome_target < self.outcomes, "ERR_INVALID_OUTCOME");
         let shares_in = self.calc_sell_collateral_out(amount_out, outcome_target);
@@ -506,7 +1044,8 @@ impl Pool {
         token_in.transfer(&env::current_account_id(), shares_in);
         self.outcome_tokens.insert(&outcome_target, &token_in);
 
-        self.fee_pool_weight += fee;
+        let protocol_fee = self.accrue_fee(fee, protocol_fee_fraction);
+        self.cumulative_volume += amount_out;
 
         let to_escrow = match (sell_price).cmp(&avg_price) {
             Ordering::Less => {
@@ -516,7 +1055,9 @@ impl Pool {
                 logger::log_to_invalid_escrow(self.id, &sender, invalid_escrow);
 
                 // TODO: sub from spent and logging it is done in both cases, remove dup code
-                let new_spent = escrow_account.sub_from_spent(outcome_target, amount_out + escrow_amt + fee);
+                // Rounding of the average and sell price can overshoot `spent` by dust when selling the full position
+                let entries_to_sub = std::cmp::min(amount_out + escrow_amt + fee, spent);
+                let new_spent = escrow_account.sub_from_spent(outcome_target, entries_to_sub);
                 logger::log_account_outcome_spent(&self, &sender, outcome_target, new_spent);
                 0
             },
@@ -549,11 +1090,25 @@ impl Pool {
         let tokens_to_burn = amount_out + fee;
         self.remove_from_pools(tokens_to_burn);
         self.resolution_escrow.insert(sender, &escrow_account);
+        self.pay_out_collateral(amount_out - to_escrow + protocol_fee);
+        self.total_escrowed += to_escrow;
 
         logger::log_sell(&self, &env::predecessor_account_id(), outcome_target, shares_in, amount_out, fee, to_escrow);
         logger::log_pool(&self);
 
-        to_escrow
+        (to_escrow, protocol_fee)
+    }
+
+    /**
+     * @notice adds the LP part of a swap fee to the fee pool
+     * @param protocol_fee_fraction the part of `fee` that's taken by the protocol, denominated in 1e4
+     * @returns the protocol's part of `fee`
+     */
+    fn accrue_fee(&mut self, fee: Balance, protocol_fee_fraction: u16) -> Balance {
+        let protocol_fee = fee * protocol_fee_fraction as u128 / constants::FEE_BPS_DENOMINATION as u128;
+        self.fee_pool_weight += fee - protocol_fee;
+        self.total_fees_collected += fee;
+        protocol_fee
     }
 
     pub fn payout(
@@ -574,24 +1129,105 @@ impl Pool {
             None => return 0
         };
 
-        let payout = if payout_numerators.is_some() {
-            payout_numerators.as_ref().unwrap().iter().enumerate().fold(0, |sum, (outcome, num)| {
+        let payout = match payout_numerators {
+            Some(numerators) => numerators.iter().enumerate().fold(0, |sum, (outcome, num)| {
                 let bal = balances[outcome];
                 let payout = math::complex_mul_u128(self.collateral_denomination, bal, u128::from(*num));
                 sum + payout
-            }) + escrow_account.valid
-        } else {
-            balances.iter().enumerate().fold(0, |sum, (outcome, _bal)| {                
-                let spent = escrow_account.get_spent(outcome as u16);
-                sum + spent
+            }) + escrow_account.valid,
+            // Invalid markets refund the collateral spent on every outcome, net of fees, whatever shares are held.
+            // Complete sets were bought at par so they're refunded at par, partial holdings get back what they cost
+            None => (0..self.outcomes).fold(0, |sum, outcome| {
+                sum + escrow_account.get_spent(outcome)
             }) + escrow_account.invalid
         };
 
         self.resolution_escrow.remove(&account_id);
+        self.pay_out_collateral(payout);
+        self.total_escrowed = self.total_escrowed.saturating_sub(escrow_account.valid);
+
+        payout + fees_earned
+    }
+
+
+    /**
+     * @notice values the pool's outcome token balances at their current spot prices, excluding fees
+     * @returns the collateral value of the pool's outcome tokens
+     */
+    pub fn get_pool_value(&self) -> Balance {
+        self.get_pool_balances().iter().enumerate().fold(0, |sum, (outcome, balance)| {
+            sum + math::complex_mul_u128(self.collateral_denomination, *balance, self.get_spot_price_sans_fee(outcome as u16))
+        })
+    }
+
+    /**
+     * @notice calculates the collateral value of `account_id`'s LP tokens: their share of the pool's value plus their withdrawable fees
+     * @returns the collateral value of the LP position
+     */
+    pub fn get_pool_token_value(&self, account_id: &AccountId) -> Balance {
+        let pool_token_bal = self.pool_token.get_balance(account_id);
+        if pool_token_bal == 0 {
+            return 0;
+        }
+
+        let pool_share = math::simple_mul_u128(self.pool_token.total_supply(), self.get_pool_value(), pool_token_bal);
+        pool_share + self.calc_withdrawable_fees(account_id).1
+    }
+
+    /**
+     * @notice calculates what `payout` would pay out to `account_id` without mutating the pool
+     * @returns the amount of collateral `account_id` can claim, including fees from exiting any remaining LP position
+     */
+    pub fn calc_payout(
+        &self,
+        account_id: &AccountId,
+        payout_numerators: &Option<Vec<U128>>
+    ) -> Balance {
+        let pool_token_balance = self.get_pool_token_balance(account_id);
+        let (fees_earned, exit_shares) = if pool_token_balance > 0 {
+            self.calc_exit_pool(account_id, pool_token_balance)
+        } else {
+            (0, vec![0; self.outcomes as usize])
+        };
+
+        let escrow_account = match self.resolution_escrow.get(account_id) {
+            Some(account) => account,
+            None => return 0
+        };
+
+        let payout = match payout_numerators {
+            Some(numerators) => numerators.iter().enumerate().fold(0, |sum, (outcome, num)| {
+                let bal = self.get_share_balance(account_id, outcome as u16) + exit_shares[outcome];
+                sum + math::complex_mul_u128(self.collateral_denomination, bal, u128::from(*num))
+            }) + escrow_account.valid,
+            None => (0..self.outcomes).fold(0, |sum, outcome| {
+                // Exiting the full LP position moves everything spent on the LP position to spent
+                let lp_spent = if pool_token_balance > 0 { escrow_account.get_lp_spent(outcome) } else { 0 };
+                sum + escrow_account.get_spent(outcome) + lp_spent
+            }) + escrow_account.invalid
+        };
 
         payout + fees_earned
     }
 
+    /**
+     * @notice deducts collateral leaving the pool from `collateral_balance`
+     * Saturates instead of panicking so accounting drift shows up in `get_collateral_invariant` rather than blocking withdrawals
+     */
+    fn pay_out_collateral(&mut self, amount: Balance) {
+        self.collateral_balance = self.collateral_balance.saturating_sub(amount);
+    }
+
+    /**
+     * @returns a tuple of the collateral the pool owes: outstanding complete sets, fees that haven't been withdrawn and escrowed collateral
+     */
+    pub fn get_collateral_obligations(&self) -> (Balance, Balance, Balance) {
+        (
+            self.get_collateral_locked(),
+            self.fee_pool_weight.saturating_sub(self.total_withdrawn_fees),
+            self.total_escrowed
+        )
+    }
 
     fn add_to_pools(&mut self, amount: Balance) {
         for outcome in 0..self.outcomes {
@@ -623,6 +1259,11 @@ impl Pool {
         &self,
         target_outcome: u16
     ) -> Balance {
+        if let PricingMode::Lmsr { b } = self.pricing_mode {
+            let ratio = self.calc_lmsr_spot_price(&self.get_pool_balances(), b.into(), target_outcome);
+            let scale = math::complex_div_u128(self.collateral_denomination, self.collateral_denomination, self.collateral_denomination - self.swap_fee);
+            return math::complex_mul_u128(self.collateral_denomination, ratio, scale);
+        }
 
         let mut odds_weight_for_target = 0;
         let mut odds_weight_sum = 0;
@@ -647,6 +1288,14 @@ impl Pool {
         &self,
         target_outcome: u16
     ) -> Balance {
+        if let PricingMode::Lmsr { b } = self.pricing_mode {
+            let balances = self.get_pool_balances();
+            if balances.iter().all(|balance| *balance == 0) {
+                return 0
+            }
+            return self.calc_lmsr_spot_price(&balances, b.into(), target_outcome);
+        }
+
         let mut odds_weight_for_target = 0;
         let mut odds_weight_sum = 0;
 
@@ -686,3 +1335,156 @@ impl Pool {
         odds_weight_for_target
     }
 }
+
+/**
+ * @panics if `swap_fee` is non-zero but below 0.01%, the upper bound is governance configurable and checked by the contract
+ */
+fn assert_min_swap_fee(swap_fee: Balance, collateral_denomination: Balance) {
+    assert!(swap_fee == 0 || swap_fee >= collateral_denomination / 10_000, "ERR_INVALID_FEE");
+}
+
+/**
+ * @panics if the pool has never been seeded or any of its outcome `balances` has been drained, the pricing math divides by them
+ */
+fn assert_has_liquidity(balances: &[Balance]) {
+    assert!(!balances.is_empty() && balances.iter().all(|balance| *balance > 0), "ERR_NO_LIQUIDITY");
+}
+
+#[cfg(test)]
+mod pool_property_tests {
+    use super::*;
+    use near_sdk::{ MockedBlockchain };
+    use near_sdk::{ testing_env };
+    use proptest::prelude::*;
+    use crate::test_utils::*;
+
+    const DENOM: u128 = 1_000_000_000_000_000_000_000_000;
+
+    // Every trade logs several events, resetting the context between trades keeps the mocked log limit out of the way
+    fn reset_context() {
+        testing_env!(get_context(alice(), 0));
+    }
+
+    fn seeded_pool(outcomes: u16, swap_fee: Balance, seed: Balance) -> Pool {
+        reset_context();
+        let mut pool = Pool::new(0, token(), 24, outcomes, swap_fee, PricingMode::ConstantProduct);
+        pool.add_liquidity(&carol(), seed, Some(vec![1; outcomes as usize]));
+        pool
+    }
+
+    /**
+     * @returns the most collateral selling at most `shares` of `outcome` returns, searched up to `max_collateral_out`
+     */
+    fn max_collateral_out(pool: &Pool, shares: Balance, outcome: u16, max_collateral_out: Balance) -> Balance {
+        let mut low = 0;
+        let mut high = max_collateral_out;
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            if pool.calc_sell_collateral_out(mid, outcome) <= shares {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    }
+
+    /**
+     * @returns the most collateral that can be sold against the pool without draining the other outcomes
+     */
+    fn sell_capacity(pool: &Pool, outcome: u16) -> Balance {
+        let balances = pool.get_pool_balances();
+        let min_other_balance = balances.iter().enumerate()
+            .filter(|(i, _)| *i != outcome as usize)
+            .map(|(_, balance)| *balance)
+            .min()
+            .unwrap();
+        min_other_balance / 2
+    }
+
+    fn swap_fee_from_bps(fee_bps: u128) -> Balance {
+        fee_bps * DENOM / 10_000
+    }
+
+    #[test]
+    fn sell_full_position_below_average_price_clears_spent() {
+        let mut pool = seeded_pool(2, swap_fee_from_bps(100), DENOM * 100);
+        reset_context();
+        let (shares_out, _) = pool.buy(&bob(), DENOM * 10, 0, 0, 0);
+        reset_context();
+        pool.buy(&carol(), DENOM, 1, 0, 0);
+
+        // The sell lands below bob's average price, the rounded prices add up to a few yocto more than bob spent
+        reset_context();
+        let collateral_out = max_collateral_out(&pool, shares_out, 0, shares_out.min(sell_capacity(&pool, 0)));
+        pool.sell(&bob(), collateral_out, 0, shares_out, 0);
+
+        assert_eq!(pool.get_share_balance(&bob(), 0), 0);
+        assert_eq!(pool.resolution_escrow.get(&bob()).unwrap().get_spent(0), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn buy_then_sell_returns_input_minus_fees(
+            outcomes in 2u16..=4,
+            seed in DENOM..DENOM * 1_000,
+            fee_bps in prop_oneof![Just(0u128), 1u128..=1000],
+            trade_bps in 1u128..=2500,
+            target in 0u16..4
+        ) {
+            let outcome = target % outcomes;
+            let swap_fee = swap_fee_from_bps(fee_bps);
+            let mut pool = seeded_pool(outcomes, swap_fee, seed);
+            let collateral_in = seed * trade_bps / 10_000;
+
+            reset_context();
+            let (shares_out, _) = pool.buy(&bob(), collateral_in, outcome, 0, 0);
+            prop_assert!(shares_out > 0);
+
+            let collateral_out = max_collateral_out(&pool, shares_out, outcome, collateral_in.min(sell_capacity(&pool, outcome)));
+            if collateral_out > 0 {
+                reset_context();
+                pool.sell(&bob(), collateral_out, outcome, shares_out, 0);
+            }
+
+            // Selling never returns more than was paid in, and at most the fee of each swap plus rounding is lost
+            let fees = 2 * pool.calc_swap_fee(collateral_in);
+            let tolerance = collateral_in / 1_000_000 + 1;
+            prop_assert!(collateral_out <= collateral_in, "sold for {} after buying for {}", collateral_out, collateral_in);
+            prop_assert!(collateral_out + fees + tolerance >= collateral_in, "sold for {} after buying for {} with {} fees", collateral_out, collateral_in, fees);
+        }
+
+        #[test]
+        fn spot_prices_stay_within_denomination(
+            outcomes in 2u16..=4,
+            seed in DENOM..DENOM * 1_000,
+            fee_bps in prop_oneof![Just(0u128), 1u128..=1000],
+            trades in prop::collection::vec((0u16..4, 1u128..=2500, any::<bool>()), 1..8)
+        ) {
+            let mut pool = seeded_pool(outcomes, swap_fee_from_bps(fee_bps), seed);
+
+            for (target, trade_bps, is_sell) in trades {
+                let outcome = target % outcomes;
+                reset_context();
+
+                let spent = pool.resolution_escrow.get(&bob()).map(|escrow| escrow.get_spent(outcome)).unwrap_or(0);
+                if is_sell && spent > 0 {
+                    let shares = pool.get_share_balance(&bob(), outcome);
+                    let collateral_out = max_collateral_out(&pool, shares, outcome, shares.min(sell_capacity(&pool, outcome)));
+                    if collateral_out > 0 {
+                        pool.sell(&bob(), collateral_out, outcome, shares, 0);
+                    }
+                } else {
+                    pool.buy(&bob(), seed * trade_bps / 10_000, outcome, 0, 0);
+                }
+
+                let prices: Vec<Balance> = (0..outcomes).map(|outcome| pool.get_spot_price_sans_fee(outcome)).collect();
+                let price_sum: Balance = prices.iter().sum();
+                for price in prices.iter() {
+                    prop_assert!(*price <= DENOM, "spot price {} exceeds the denomination", price);
+                }
+                prop_assert!(price_sum + outcomes as u128 >= DENOM && price_sum <= DENOM + outcomes as u128, "spot prices sum up to {}", price_sum);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use near_sdk::AccountId;
-use crate::pool::Pool;
+use crate::pool::{ Pool, PricingMode };
 use near_sdk::Balance;
 
 // TODO: remove, completely redunant function
@@ -13,12 +13,14 @@ pub fn new_pool(
     collateral_token_id: AccountId,
     collateral_decimals: u32,
     swap_fee: Balance,
+    pricing_mode: PricingMode,
 ) -> Pool {
     Pool::new(
         pool_id,
         collateral_token_id,
         collateral_decimals,
         outcomes,
-        swap_fee
+        swap_fee,
+        pricing_mode
     )
 }
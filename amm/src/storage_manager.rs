@@ -12,8 +12,8 @@ pub const STORAGE_MINIMUM_BALANCE: Balance = 10_000_000_000_000_000_000_000;
 #[derive(Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct StorageBalance {
-    total: U128,
-    available: U128,
+    pub total: U128,
+    pub available: U128,
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -113,6 +113,25 @@ impl AMMContract {
             .unwrap_or(AccountStorageBalance { total: 0, available: 0 })
     }
 
+    /**
+     * @notice settles the storage used by a call, registered accounts pay from their storage balance and any attached deposit is added to it
+     * Accounts that haven't called `storage_deposit` pay from the attached deposit and are refunded the rest, see `helper::refund_storage`
+     * @param initial_storage is the storage at the beginning of the function call
+     * @param sender_id is the `AccountId` that pays for the storage
+     */
+    pub fn refund_storage(&mut self, initial_storage: StorageUsage, sender_id: AccountId) {
+        match self.accounts.get(&sender_id) {
+            Some(mut account) => {
+                let attached_deposit = env::attached_deposit();
+                account.total += attached_deposit;
+                account.available += attached_deposit;
+                self.accounts.insert(&sender_id, &account);
+                self.use_storage(&sender_id, initial_storage, account.available);
+            },
+            None => helper::refund_storage(initial_storage, sender_id)
+        }
+    }
+
     pub fn use_storage(&mut self, sender_id: &AccountId, initial_storage_usage: u64, initial_available_balance: u128) {
         if env::storage_usage() >= initial_storage_usage {
             // used more storage, deduct from balance
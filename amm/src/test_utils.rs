@@ -0,0 +1,149 @@
+use crate::*;
+use std::convert::TryInto;
+use near_sdk::{ MockedBlockchain };
+use near_sdk::{ testing_env, VMContext };
+
+/*** Fixtures shared by the unit test modules, `alice` is the contract account, `bob` is `gov` and `oracle` the oracle ***/
+
+pub fn alice() -> AccountId {
+    "alice.near".to_string()
+}
+
+pub fn bob() -> AccountId {
+    "bob.near".to_string()
+}
+
+// `alice` is the contract account in these tests, LPs whose share balances are checked need a separate account
+pub fn carol() -> AccountId {
+    "carol.near".to_string()
+}
+
+pub fn token() -> AccountId {
+    "token.near".to_string()
+}
+
+pub fn oracle() -> AccountId {
+    "oracle.near".to_string()
+}
+
+pub fn empty_string() -> String {
+    "".to_string()
+}
+
+pub fn empty_string_vec(len: u16) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for _i in 0..len {
+        tags.push(empty_string());
+    }
+    tags
+}
+
+// Distinct tags, markets can't be created with duplicate outcome tags
+pub fn outcome_tags(len: u16) -> Vec<String> {
+    (0..len).map(|outcome| outcome.to_string()).collect()
+}
+
+pub fn get_context(predecessor_account_id: AccountId, timestamp: u64) -> VMContext {
+    VMContext {
+        current_account_id: alice(),
+        signer_account_id: alice(),
+        signer_account_pk: vec![0, 1, 2],
+        predecessor_account_id,
+        input: vec![],
+        block_index: 0,
+        block_timestamp: timestamp,
+        account_balance: 1000 * 10u128.pow(24),
+        account_locked_balance: 0,
+        storage_usage: 10u64.pow(6),
+        attached_deposit: 33400000000000000000000,
+        prepaid_gas: 10u64.pow(18),
+        random_seed: vec![0, 1, 2],
+        is_view: false,
+        output_data_receivers: vec![],
+        epoch_height: 0,
+    }
+}
+
+pub fn default_market_args() -> CreateMarketArgs {
+    CreateMarketArgs {
+        description: empty_string(), // market description
+        extra_info: empty_string(), // extra info
+        outcomes: 2, // outcomes
+        outcome_tags: outcome_tags(2), // outcome tags
+        categories: empty_string_vec(2), // categories
+        end_time: 1609951265967.into(), // end_time
+        resolution_time: 1619882574000.into(), // resolution_time (~1 day after end_time)
+        sources: vec![Source{end_point: "test".to_string(), source_path: "test".to_string()}],
+        collateral_token_id: token(), // collateral_token_id
+        swap_fee: (10_u128.pow(24) / 50).into(), // swap fee, 2%
+        challenge_period: U64(1),
+        is_scalar: false, // is_scalar,
+        ambiguity_policy: None, // ambiguity_policy
+        lp_lockup_ms: None, // lp_lockup_ms
+        price_tick: None, // price_tick
+        notification_endpoint: None, // notification_endpoint
+        max_pool_collateral: None, // max_pool_collateral
+        start_time: None, // start_time
+        pricing_mode: None, // pricing_mode
+        fee_schedule: None, // fee_schedule
+        min_bet: None, // min_bet
+        max_bet: None, // max_bet
+    }
+}
+
+pub fn init_contract() -> AMMContract {
+    AMMContract::init(
+        bob().try_into().unwrap(),
+        vec![collateral_whitelist::Token{account_id: token(), decimals: 24}],
+        oracle().try_into().unwrap()
+    )
+}
+
+pub fn enable_market(contract: &mut AMMContract, market_id: U64) {
+    let mut market = contract.get_market_expect(market_id);
+    market.enabled = true;
+    contract.markets.replace(market_id.into(), &market);
+}
+
+pub fn seed_market(contract: &mut AMMContract, market_id: U64, sender: AccountId, total_in: u128, weight_indication: Option<Vec<U128>>) {
+    testing_env!(get_context(token(), 0));
+    contract.add_liquidity(
+        &sender,
+        total_in,
+        AddLiquidityArgs {
+            market_id,
+            weight_indication,
+            min_lp_tokens_out: None,
+            deadline: None
+        }
+    );
+}
+
+pub fn seed_market_with_fees(contract: &mut AMMContract) -> U64 {
+    testing_env!(get_context(alice(), 0));
+    let market_id = contract.create_market(&default_market_args());
+    enable_market(contract, market_id);
+    seed_market(contract, market_id, carol(), 10_u128.pow(24), Some(vec![U128(1), U128(1)]));
+
+    contract.buy(
+        &bob(),
+        10_u128.pow(23),
+        BuyArgs {
+            market_id,
+            outcome_target: 0,
+            min_shares_out: U128(0),
+            deadline: None
+        }
+    );
+    market_id
+}
+
+pub fn create_resolved_market(contract: &mut AMMContract) -> U64 {
+    testing_env!(get_context(alice(), 0));
+    let market_id = contract.create_market(&default_market_args());
+
+    testing_env!(get_context(oracle(), ms_to_ns(1619882574000)));
+    contract.set_outcome(alice(), Outcome::Answer("0".to_string()), Some(vec![market_id]));
+    testing_env!(get_context(bob(), ms_to_ns(1619882574000)));
+    market_id
+}
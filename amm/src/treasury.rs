@@ -0,0 +1,215 @@
+use crate::*;
+
+#[near_bindgen]
+impl AMMContract {
+    /**
+     * @returns the part of every swap fee that goes to the treasury, denominated in 1e4
+     */
+    pub fn get_protocol_fee_fraction(&self) -> u16 {
+        self.protocol_fee_fraction
+    }
+
+    /**
+     * @returns the accounts that receive swept protocol fees and their share of every sweep, denominated in 1e4
+     */
+    pub fn get_treasury_beneficiaries(&self) -> Vec<(AccountId, u16)> {
+        self.treasury_beneficiaries.clone()
+    }
+
+    /**
+     * @param token is the collateral token to retrieve the treasury balance for
+     * @returns wrapped amount of protocol fees accrued in `token` that haven't been swept yet
+     */
+    pub fn get_treasury_balance(&self, token: AccountId) -> WrappedBalance {
+        U128(self.treasury_balances.get(&token).unwrap_or(0))
+    }
+
+    /**
+     * @notice sets the part of every swap fee that goes to the treasury instead of LPs, only callable by `gov`
+     * @param protocol_fee_fraction the fraction denominated in 1e4, e.g. 2000 = 20%
+     */
+    pub fn set_protocol_fee_fraction(&mut self, protocol_fee_fraction: u16) {
+        self.assert_gov();
+        assert!(protocol_fee_fraction <= constants::FEE_BPS_DENOMINATION, "ERR_INVALID_PROTOCOL_FEE");
+        self.protocol_fee_fraction = protocol_fee_fraction;
+    }
+
+    /**
+     * @notice sets the accounts that receive swept protocol fees, only callable by `gov`
+     * @param beneficiaries list of (account, share) pairs, shares are denominated in 1e4 and have to sum up to 10000
+     */
+    pub fn set_treasury_beneficiaries(&mut self, beneficiaries: Vec<(ValidAccountId, u16)>) {
+        self.assert_gov();
+        let beneficiaries = to_beneficiaries(&beneficiaries);
+        assert_valid_beneficiaries(&beneficiaries);
+        self.treasury_beneficiaries = beneficiaries;
+    }
+
+    /**
+     * @notice transfers all protocol fees accrued in `token` to the treasury beneficiaries, split by their shares, only callable by `gov`
+     * if a transfer fails that beneficiary's share becomes a failed payout it can withdraw through `withdraw_failed_payout`
+     * @param token is the collateral token to sweep
+     * @returns a joint `Promise` of one collateral transfer per beneficiary with a non-zero share
+     */
+    pub fn sweep_treasury(&mut self, token: AccountId) -> Promise {
+        self.assert_gov();
+        let amount = self.treasury_balances.remove(&token).unwrap_or(0);
+        assert!(amount > 0, "ERR_NO_TREASURY_BALANCE");
+
+        self.calc_treasury_payouts(amount).into_iter()
+            .filter(|(_, payout)| *payout > 0)
+            .map(|(account_id, payout)| self.transfer_payout(account_id, payout, &token))
+            .fold(None, |joint: Option<Promise>, transfer| match joint {
+                Some(joint) => Some(joint.and(transfer)),
+                None => Some(transfer)
+            })
+            .expect("ERR_NO_TREASURY_BALANCE")
+    }
+}
+
+impl AMMContract {
+    /**
+     * @notice adds `protocol_fee` to the treasury balance of `token`
+     */
+    pub fn accrue_treasury(&mut self, token: &AccountId, protocol_fee: Balance) {
+        if protocol_fee > 0 {
+            let balance = self.treasury_balances.get(token).unwrap_or(0);
+            self.treasury_balances.insert(token, &(balance + protocol_fee));
+        }
+    }
+
+    /**
+     * @notice splits `amount` between the treasury beneficiaries, the last beneficiary receives the rounding remainder
+     * @returns a list of beneficiaries and their payout, in the order they were set
+     */
+    pub fn calc_treasury_payouts(&self, amount: Balance) -> Vec<(AccountId, Balance)> {
+        let denomination = u128::from(constants::FEE_BPS_DENOMINATION);
+        let mut remaining = amount;
+        self.treasury_beneficiaries.iter().enumerate().map(|(i, (account_id, share))| {
+            let payout = if i == self.treasury_beneficiaries.len() - 1 {
+                remaining
+            } else {
+                // Split before multiplying so large balances can't overflow
+                amount / denomination * u128::from(*share) + amount % denomination * u128::from(*share) / denomination
+            };
+            remaining -= payout;
+            (account_id.to_string(), payout)
+        }).collect()
+    }
+}
+
+/**
+ * @returns `beneficiaries` with their account ids unwrapped
+ */
+pub fn to_beneficiaries(beneficiaries: &[(ValidAccountId, u16)]) -> Vec<(AccountId, u16)> {
+    beneficiaries.iter().map(|(account_id, share)| (account_id.as_ref().to_string(), *share)).collect()
+}
+
+/**
+ * @panics if there are no or too many beneficiaries, any share is zero or the shares don't sum up to 10000
+ */
+pub fn assert_valid_beneficiaries(beneficiaries: &[(AccountId, u16)]) {
+    assert!(!beneficiaries.is_empty() && beneficiaries.len() <= constants::MAX_TREASURY_BENEFICIARIES, "ERR_INVALID_BENEFICIARIES");
+    assert!(beneficiaries.iter().all(|(_, share)| *share > 0), "ERR_ZERO_BENEFICIARY_SHARE");
+    let total_shares = beneficiaries.iter().fold(0u32, |sum, (_, share)| sum + u32::from(*share));
+    assert_eq!(total_shares, u32::from(constants::FEE_BPS_DENOMINATION), "ERR_INVALID_BENEFICIARY_SHARES");
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg(test)]
+mod treasury_tests {
+    use std::convert::TryInto;
+    use near_sdk::{ MockedBlockchain };
+    use near_sdk::{ testing_env };
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn protocol_fee_reduces_lp_fees() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        let market_without_protocol_fee = seed_market_with_fees(&mut contract);
+
+        testing_env!(get_context(bob(), 0));
+        contract.set_protocol_fee_fraction(2000);
+        let market_with_protocol_fee = seed_market_with_fees(&mut contract);
+
+        // bob buys 1e23 at a 2% fee in both markets
+        let fee = 10_u128.pow(23) / 50;
+        let protocol_fee = fee * 2000 / 10000;
+        let lp_fees_without: u128 = contract.get_fees_withdrawable(market_without_protocol_fee, &carol()).into();
+        let lp_fees_with: u128 = contract.get_fees_withdrawable(market_with_protocol_fee, &carol()).into();
+
+        assert_eq!(lp_fees_without, fee);
+        assert_eq!(lp_fees_with, fee - protocol_fee);
+        assert_eq!(contract.get_treasury_balance(token()), U128(protocol_fee));
+
+        testing_env!(get_context(bob(), 0));
+        contract.sweep_treasury(token());
+        assert_eq!(contract.get_treasury_balance(token()), U128(0));
+    }
+
+    #[test]
+    fn default_treasury_beneficiary_is_gov() {
+        testing_env!(get_context(bob(), 0));
+        let contract = init_contract();
+        assert_eq!(contract.get_treasury_beneficiaries(), vec![(bob(), 10000)]);
+        assert_eq!(contract.calc_treasury_payouts(1001), vec![(bob(), 1001)]);
+    }
+
+    #[test]
+    fn treasury_sweep_splits_70_30() {
+        testing_env!(get_context(alice(), 0));
+        let mut contract = init_contract();
+        testing_env!(get_context(bob(), 0));
+        contract.set_protocol_fee_fraction(2000);
+        contract.set_treasury_beneficiaries(vec![(alice().try_into().unwrap(), 7000), (carol().try_into().unwrap(), 3000)]);
+        seed_market_with_fees(&mut contract);
+
+        // bob buys 1e23 at a 2% fee, 20% of it goes to the treasury
+        let protocol_fee = 10_u128.pow(23) / 50 * 2000 / 10000;
+        assert_eq!(contract.get_treasury_balance(token()), U128(protocol_fee));
+        assert_eq!(
+            contract.calc_treasury_payouts(protocol_fee),
+            vec![(alice(), protocol_fee * 7 / 10), (carol(), protocol_fee * 3 / 10)]
+        );
+
+        testing_env!(get_context(bob(), 0));
+        contract.sweep_treasury(token());
+        assert_eq!(contract.get_treasury_balance(token()), U128(0));
+    }
+
+    #[test]
+    fn treasury_split_rounding_goes_to_last_beneficiary() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_treasury_beneficiaries(vec![(alice().try_into().unwrap(), 7000), (carol().try_into().unwrap(), 3000)]);
+        assert_eq!(contract.calc_treasury_payouts(9), vec![(alice(), 6), (carol(), 3)]);
+        assert_eq!(contract.calc_treasury_payouts(u128::MAX), vec![(alice(), u128::MAX / 10 * 7 + 3), (carol(), u128::MAX - (u128::MAX / 10 * 7 + 3))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_BENEFICIARY_SHARES")]
+    fn treasury_beneficiaries_not_summing_to_full_share() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_treasury_beneficiaries(vec![(alice().try_into().unwrap(), 7000), (carol().try_into().unwrap(), 2000)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_BENEFICIARIES")]
+    fn treasury_beneficiaries_empty() {
+        testing_env!(get_context(bob(), 0));
+        let mut contract = init_contract();
+        contract.set_treasury_beneficiaries(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_GOVERNANCE_ADDRESS")]
+    fn sweep_treasury_not_gov() {
+        testing_env!(get_context(carol(), 0));
+        let mut contract = init_contract();
+        contract.sweep_treasury(token());
+    }
+}
@@ -150,4 +150,110 @@ fn test_specific_fee_scenario() {
     assert_eq!(bob_bal, expected_bob_balance);
     assert_eq!(carol_bal, expected_carol_balance);
     assert_eq!(alice_bal, expected_alice_balance);
-}
\ No newline at end of file
+}
+
+#[test]
+fn failed_fee_payout_can_be_withdrawn_again() {
+    let test_utils = TestUtils::init(carol());
+    
+    // variables
+    let market_id = 0;
+
+    let target_price = to_yocto("5") / 10;
+    let seed_amount = to_yocto("100");
+    let buy_amount = to_yocto("10");
+    let weights = Some(calc_weights_from_price(vec![target_price, target_price]));
+    let swap_fee = to_yocto("2") / 100;
+    test_utils.alice.create_market(2, Some(U128(swap_fee)));
+    test_utils.alice.add_liquidity(market_id, seed_amount, weights);
+
+    // bob provides liquidity with all of his tokens and unregisters from the token so transfers to him fail
+    let bob_balance = test_utils.bob.get_token_balance(None);
+    test_utils.bob.add_liquidity(market_id, bob_balance, None);
+    test_utils.bob.token_storage_unregister();
+
+    test_utils.carol.buy(market_id, buy_amount, 0, 0);
+    let fees = test_utils.bob.get_fees_withdrawable(market_id, None);
+    assert!(fees > 0);
+
+    // Exiting pays out the fees, the failed transfer keeps them withdrawable
+    test_utils.bob.exit_liquidity(market_id, to_yocto("1"));
+    assert_eq!(test_utils.bob.get_fees_withdrawable(market_id, None), 0);
+    assert_eq!(test_utils.bob.get_failed_payout(None), fees);
+
+    storage_deposit(TOKEN_CONTRACT_ID, &test_utils.bob.account, SAFE_STORAGE_AMOUNT, Some(test_utils.bob.account.account_id()));
+    test_utils.bob.withdraw_failed_payout();
+    assert_eq!(test_utils.bob.get_failed_payout(None), 0);
+    assert_eq!(test_utils.bob.get_token_balance(None), fees);
+}
+
+#[test]
+fn failed_treasury_sweep_can_be_withdrawn_again() {
+    let test_utils = TestUtils::init(carol());
+    
+    // variables
+    let market_id = 0;
+
+    let target_price = to_yocto("5") / 10;
+    let seed_amount = to_yocto("100");
+    let buy_amount = to_yocto("10");
+    let weights = Some(calc_weights_from_price(vec![target_price, target_price]));
+    let swap_fee = to_yocto("2") / 100;
+    test_utils.carol.set_protocol_fee_fraction(5000);
+    test_utils.carol.set_treasury_beneficiaries(vec![(test_utils.bob.account.account_id(), 10000)]);
+    test_utils.alice.create_market(2, Some(U128(swap_fee)));
+    test_utils.alice.add_liquidity(market_id, seed_amount, weights);
+
+    // bob is the treasury, provides liquidity with all of their tokens and unregisters from the token so transfers to bob fail
+    let bob_balance = test_utils.bob.get_token_balance(None);
+    test_utils.bob.add_liquidity(market_id, bob_balance, None);
+    test_utils.bob.token_storage_unregister();
+
+    test_utils.carol.buy(market_id, buy_amount, 0, 0);
+    let protocol_fees = test_utils.carol.get_treasury_balance();
+    assert!(protocol_fees > 0);
+
+    // The failed sweep keeps the protocol fees withdrawable by the treasury
+    test_utils.carol.sweep_treasury();
+    assert_eq!(test_utils.carol.get_treasury_balance(), 0);
+    assert_eq!(test_utils.bob.get_failed_payout(None), protocol_fees);
+
+    storage_deposit(TOKEN_CONTRACT_ID, &test_utils.bob.account, SAFE_STORAGE_AMOUNT, Some(test_utils.bob.account.account_id()));
+    test_utils.bob.withdraw_failed_payout();
+    assert_eq!(test_utils.bob.get_failed_payout(None), 0);
+    assert_eq!(test_utils.bob.get_token_balance(None), protocol_fees);
+}
+
+#[test]
+fn treasury_sweep_splits_between_beneficiaries() {
+    let test_utils = TestUtils::init(carol());
+    
+    // variables
+    let market_id = 0;
+
+    let target_price = to_yocto("5") / 10;
+    let seed_amount = to_yocto("100");
+    let buy_amount = to_yocto("10");
+    let weights = Some(calc_weights_from_price(vec![target_price, target_price]));
+    let swap_fee = to_yocto("2") / 100;
+    test_utils.carol.set_protocol_fee_fraction(5000);
+    test_utils.carol.set_treasury_beneficiaries(vec![
+        (test_utils.alice.account.account_id(), 7000),
+        (test_utils.bob.account.account_id(), 3000)
+    ]);
+    test_utils.alice.create_market(2, Some(U128(swap_fee)));
+    test_utils.alice.add_liquidity(market_id, seed_amount, weights);
+
+    test_utils.carol.buy(market_id, buy_amount, 0, 0);
+    let protocol_fees = test_utils.carol.get_treasury_balance();
+    assert!(protocol_fees > 0);
+
+    let alice_balance_before = test_utils.alice.get_token_balance(None);
+    let bob_balance_before = test_utils.bob.get_token_balance(None);
+    test_utils.carol.sweep_treasury();
+
+    let alice_share = protocol_fees / 10000 * 7000 + protocol_fees % 10000 * 7000 / 10000;
+    assert_eq!(test_utils.carol.get_treasury_balance(), 0);
+    assert_eq!(test_utils.alice.get_token_balance(None) - alice_balance_before, alice_share);
+    assert_eq!(test_utils.bob.get_token_balance(None) - bob_balance_before, protocol_fees - alice_share);
+}
@@ -10,3 +10,4 @@ mod uneven_lp_shares_solvency_tests;
 mod swap_tests;
 mod market_end_tests;
 mod fee_tests;
+mod reentrancy_tests;
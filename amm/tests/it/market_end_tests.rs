@@ -147,4 +147,41 @@ fn payout_lp_no_exit() {
     assert_eq!(bob_final_balance, expected_bob_final_balance);
     assert_eq!(amm_final_balance, expected_amm_final_balance);
     
-}
\ No newline at end of file
+}
+
+#[test]
+fn cancelled_market_refunds() {
+    let test_utils = TestUtils::init(carol());
+    
+    // variables
+    let creation_bond = 100;
+    let market_id = 0;
+    let alice_init_balance: u128 = test_utils.alice.get_token_balance(None);
+    let bob_init_balance: u128 = test_utils.bob.get_token_balance(None);
+    
+    let target_price = to_yocto("5") / 10;
+    let seed_amount = to_yocto("100");
+    let buy_amount = to_yocto("1");
+    let weights = Some(calc_weights_from_price(vec![target_price, target_price]));
+
+    test_utils.alice.create_market(2, Some(U128(0)));
+    test_utils.alice.add_liquidity(market_id, seed_amount, weights);
+
+    test_utils.bob.buy(market_id, buy_amount, 0, 0);
+    test_utils.bob.buy(market_id, buy_amount, 1, 0);
+    test_utils.bob.buy(market_id, buy_amount, 0, 0);
+
+    test_utils.carol.cancel_market(market_id);
+
+    test_utils.bob.claim_refund(market_id);
+    test_utils.alice.claim_refund(market_id);
+    
+    let alice_final_balance = test_utils.alice.get_token_balance(None);
+    let bob_final_balance = test_utils.bob.get_token_balance(None);
+    let amm_final_balance = test_utils.bob.get_token_balance(Some(AMM_CONTRACT_ID.to_string()));
+
+    // Both the LP and the trader get back everything they put in
+    assert_eq!(alice_final_balance, alice_init_balance - creation_bond);
+    assert_eq!(bob_final_balance, bob_init_balance);
+    assert_eq!(amm_final_balance, 0);
+}
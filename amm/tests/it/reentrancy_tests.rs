@@ -0,0 +1,41 @@
+use crate::utils::*;
+use near_sdk::json_types::{U64, U128};
+use near_sdk::serde_json::json;
+use near_sdk_sim::{to_yocto, STORAGE_AMOUNT, DEFAULT_GAS};
+
+#[test]
+fn exit_pool_reentry_during_pending_transfer_fails() {
+    let test_utils = TestUtils::init(carol());
+    
+    // variables
+    let market_id = 0;
+
+    let target_price = to_yocto("5") / 10;
+    let seed_amount = to_yocto("100");
+    let buy_amount = to_yocto("10");
+    let weights = Some(calc_weights_from_price(vec![target_price, target_price]));
+    test_utils.alice.create_market(2, Some(U128(to_yocto("2") / 100)));
+    test_utils.alice.add_liquidity(market_id, seed_amount, weights);
+    test_utils.bob.buy(market_id, buy_amount, 0, 0);
+
+    let pool_token_balance = test_utils.alice.get_pool_token_balance(market_id, None);
+    let exit_args = json!({
+        "market_id": U64(market_id),
+        "total_in": U128(to_yocto("1"))
+    }).to_string().into_bytes();
+
+    // Both actions execute in the same receipt, the second one runs while the fee transfer of the first is still pending
+    let res = test_utils.alice.account
+        .create_transaction(AMM_CONTRACT_ID.to_string())
+        .function_call("exit_pool".to_string(), exit_args.clone(), DEFAULT_GAS / 2, STORAGE_AMOUNT)
+        .function_call("exit_pool".to_string(), exit_args, DEFAULT_GAS / 2, STORAGE_AMOUNT)
+        .submit();
+    assert!(!res.is_ok());
+    assert!(format!("{:?}", res.status()).contains("ERR_REENTRANT"));
+    assert_eq!(test_utils.alice.get_pool_token_balance(market_id, None), pool_token_balance);
+
+    // The lock is released once a transfer resolved, a regular exit afterwards succeeds
+    test_utils.alice.exit_liquidity(market_id, to_yocto("1"));
+    test_utils.alice.exit_liquidity(market_id, to_yocto("1"));
+    assert_eq!(test_utils.alice.get_pool_token_balance(market_id, None), pool_token_balance - to_yocto("2"));
+}
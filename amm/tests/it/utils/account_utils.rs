@@ -195,6 +195,42 @@ impl TestAccount {
         wrapped_balance.into()
     }
 
+    pub fn get_failed_payout(&self, account_id: Option<String>) -> u128 {
+        let account_id = match account_id {
+            Some(account_id) => account_id,
+            None => self.account.account_id()
+        };
+
+        let wrapped_balance: U128 = self.account.view(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "get_failed_payout", 
+                json!({
+                    "account_id": account_id,
+                    "collateral_token_id": TOKEN_CONTRACT_ID,
+                }), 
+                true
+            )
+        ).unwrap_json();
+
+        wrapped_balance.into()
+    }
+
+    pub fn get_treasury_balance(&self) -> u128 {
+        let wrapped_balance: U128 = self.account.view(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "get_treasury_balance", 
+                json!({
+                    "token": TOKEN_CONTRACT_ID,
+                }), 
+                true
+            )
+        ).unwrap_json();
+
+        wrapped_balance.into()
+    }
+
     /*** Setters ***/
     pub fn create_market(&self, outcomes: u16, fee_opt: Option<U128>) -> ExecutionResult {
         let msg = json!({
@@ -202,15 +238,15 @@ impl TestAccount {
                 "description": empty_string(),
                 "extra_info": empty_string(),
                 "outcomes": outcomes,
-                "outcome_tags": empty_string_vec(outcomes),
+                "outcome_tags": outcome_tags(outcomes),
                 "categories": empty_string_vec(outcomes),
-                "end_time": env_time(),
+                "end_time": market_end_time(),
                 "sources": vec![Source {
                     end_point: empty_string(),
                     source_path: empty_string()
                 }],
                 "challenge_period": U64(1000),
-                "resolution_time": env_time(),
+                "resolution_time": market_end_time(),
                 "collateral_token_id": TOKEN_CONTRACT_ID,
                 "swap_fee": fee_opt,
                 "is_scalar": false
@@ -332,6 +368,134 @@ impl TestAccount {
         res
     }
 
+    pub fn cancel_market(&self, market_id: u64) -> ExecutionResult {
+        let res = self.account.call(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "cancel_market", 
+                json!({
+                    "market_id": U64(market_id),
+                }), 
+                true
+            ),
+            0,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "cancel_market failed with res: {:?}", res);
+        res
+    }
+
+    pub fn claim_refund(&self, market_id: u64) -> ExecutionResult {
+        let res = self.account.call(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "claim_refund", 
+                json!({
+                    "market_id": U64(market_id),
+                }), 
+                true
+            ),
+            STORAGE_AMOUNT,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "claim_refund failed with res: {:?}", res);
+        res
+    }
+
+    pub fn withdraw_failed_payout(&self) -> ExecutionResult {
+        let res = self.account.call(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "withdraw_failed_payout", 
+                json!({
+                    "collateral_token_id": TOKEN_CONTRACT_ID,
+                }), 
+                true
+            ),
+            STORAGE_AMOUNT,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "withdraw_failed_payout failed with res: {:?}", res);
+        res
+    }
+
+    pub fn set_protocol_fee_fraction(&self, protocol_fee_fraction: u16) -> ExecutionResult {
+        let res = self.account.call(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "set_protocol_fee_fraction", 
+                json!({
+                    "protocol_fee_fraction": protocol_fee_fraction,
+                }), 
+                true
+            ),
+            0,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "set_protocol_fee_fraction failed with res: {:?}", res);
+        res
+    }
+
+    pub fn set_treasury_beneficiaries(&self, beneficiaries: Vec<(String, u16)>) -> ExecutionResult {
+        let res = self.account.call(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "set_treasury_beneficiaries", 
+                json!({
+                    "beneficiaries": beneficiaries,
+                }), 
+                true
+            ),
+            0,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "set_treasury_beneficiaries failed with res: {:?}", res);
+        res
+    }
+
+    pub fn sweep_treasury(&self) -> ExecutionResult {
+        let res = self.account.call(
+            PendingContractTx::new(
+                AMM_CONTRACT_ID, 
+                "sweep_treasury", 
+                json!({
+                    "token": TOKEN_CONTRACT_ID,
+                }), 
+                true
+            ),
+            0,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "sweep_treasury failed with res: {:?}", res);
+        res
+    }
+
+    pub fn token_storage_unregister(&self) -> ExecutionResult {
+        let storage_minimum_balance: U128 = self.account.view(
+            PendingContractTx::new(
+                TOKEN_CONTRACT_ID, 
+                "storage_minimum_balance", 
+                json!({}), 
+                true
+            )
+        ).unwrap_json();
+
+        let res = self.account.call(
+            PendingContractTx::new(
+                TOKEN_CONTRACT_ID, 
+                "storage_withdraw", 
+                json!({
+                    "amount": storage_minimum_balance,
+                }), 
+                true
+            ),
+            1,
+            DEFAULT_GAS
+        );
+        assert!(res.is_ok(), "storage_withdraw failed with res: {:?}", res);
+        res
+    }
+
     pub fn ft_transfer_call(
         &self,
         receiver: String,
@@ -15,10 +15,20 @@ pub fn empty_string_vec(len: u16) -> Vec<String> {
     tags
 }
 
+// Distinct tags, markets can't be created with duplicate outcome tags
+pub fn outcome_tags(len: u16) -> Vec<String> {
+    (0..len).map(|outcome| outcome.to_string()).collect()
+}
+
 pub fn env_time() -> U64{ 
     1609951265967.into()
 }
 
+// `end_time` used for test markets, one day after `env_time` so they pass a minimum market duration
+pub fn market_end_time() -> U64 {
+    (u64::from(env_time()) + 86_400_000).into()
+}
+
 pub fn fee() -> U128 {
     (10_u128.pow(24) / 50).into() // 2%
 }